@@ -0,0 +1,68 @@
+//! Per-method routes vs the batched RPC endpoint, for pages that make many
+//! KV lookups while rendering.
+//!
+//! Run with `cargo bench --bench emulator_rpc`.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use nrz::emulator::client::EmulatorClient;
+use nrz::emulator::protocol::{KvRequest, KvSetOptions, RpcCall};
+use nrz::emulator::server::EmulatorServer;
+use serde_json::json;
+
+const CALLS: &[usize] = &[1, 10, 50];
+
+fn kv_lookups(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let emulator = rt.block_on(EmulatorServer::ephemeral().bind()).unwrap();
+    let client = EmulatorClient::connect(&emulator).unwrap();
+    rt.block_on(async {
+        for i in 0..CALLS[CALLS.len() - 1] {
+            client
+                .kv_set(&format!("key:{i}"), "value", &KvSetOptions::default())
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut group = c.benchmark_group("kv_get");
+    for &n in CALLS {
+        group.bench_with_input(BenchmarkId::new("routes_sequential", n), &n, |b, &n| {
+            b.to_async(&rt).iter(|| async {
+                for i in 0..n {
+                    client.kv_get(&format!("key:{i}")).await.unwrap();
+                }
+            });
+        });
+        // What the bootstrap did before batching: one fetch per call, all
+        // in flight at once
+        group.bench_with_input(BenchmarkId::new("routes_concurrent", n), &n, |b, &n| {
+            b.to_async(&rt).iter(|| async {
+                let mut tasks = tokio::task::JoinSet::new();
+                for i in 0..n {
+                    let client = client.clone();
+                    tasks.spawn(async move { client.kv_get(&format!("key:{i}")).await });
+                }
+                while let Some(result) = tasks.join_next().await {
+                    result.unwrap().unwrap();
+                }
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("rpc_batch", n), &n, |b, &n| {
+            b.to_async(&rt).iter(|| async {
+                let calls: Vec<RpcCall> = (0..n)
+                    .map(|i| {
+                        let args = vec![json!(format!("key:{i}"))];
+                        RpcCall::new(i as u64, "kv.get", KvRequest { args }).unwrap()
+                    })
+                    .collect();
+                let responses = client.rpc(&calls).await.unwrap();
+                assert_eq!(responses.len(), n);
+            });
+        });
+    }
+    group.finish();
+    drop(emulator);
+}
+
+criterion_group!(benches, kv_lookups);
+criterion_main!(benches);
@@ -0,0 +1,191 @@
+//! Client for the ONREZA platform API.
+//!
+//! Remote data operations are scoped to a project environment:
+//!
+//! - `POST   /api/projects/:project/environments/:env/db/query` — same body and
+//!   response as the emulator's `/__nrz/db/query`
+//! - `GET    /api/projects/:project/environments/:env/kv?prefix=&limit=` → `{ keys }`
+//! - `GET    /api/projects/:project/environments/:env/kv/:key` → `{ value }` or 404
+//! - `PUT    /api/projects/:project/environments/:env/kv/:key` ← `{ value, ttl? }`
+//! - `DELETE /api/projects/:project/environments/:env/kv/:key` → 404 if missing
+
+use anyhow::Context;
+use serde::Deserialize;
+
+/// Platform API base URL. Override with `NRZ_API_URL`.
+pub const DEFAULT_API_URL: &str = "https://api.onreza.com";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Environment {
+    Preview,
+    Production,
+}
+
+impl Environment {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Preview => "preview",
+            Self::Production => "production",
+        }
+    }
+}
+
+/// API client bound to one project environment.
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: url::Url,
+    token: String,
+    project: String,
+    env: Environment,
+}
+
+#[derive(Deserialize)]
+struct KvValueResponse {
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct KvListResponse {
+    keys: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+impl ApiClient {
+    pub fn new(token: String, project: String, env: Environment) -> anyhow::Result<Self> {
+        let base = std::env::var("NRZ_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+        let base_url =
+            url::Url::parse(&base).with_context(|| format!("invalid NRZ_API_URL: {base}"))?;
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url,
+            token,
+            project,
+            env,
+        })
+    }
+
+    pub fn env(&self) -> Environment {
+        self.env
+    }
+
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    /// Build `/api/projects/:project/environments/:env/<segments...>`,
+    /// percent-encoding every segment.
+    fn url(&self, segments: &[&str]) -> url::Url {
+        let mut url = self.base_url.clone();
+        {
+            let mut path = url
+                .path_segments_mut()
+                .expect("API base URL cannot be a base");
+            path.pop_if_empty().extend([
+                "api",
+                "projects",
+                &self.project,
+                "environments",
+                self.env.as_str(),
+            ]);
+            path.extend(segments);
+        }
+        url
+    }
+
+    async fn send(&self, req: reqwest::RequestBuilder) -> anyhow::Result<reqwest::Response> {
+        let resp = req
+            .bearer_auth(&self.token)
+            .header("User-Agent", "nrz-cli")
+            .send()
+            .await
+            .context("failed to reach the ONREZA API")?;
+        Ok(resp)
+    }
+
+    async fn error(resp: reqwest::Response) -> anyhow::Error {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<ErrorResponse>(&text)
+            .map(|e| e.error)
+            .unwrap_or(text);
+        match status {
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => anyhow::anyhow!(
+                "authentication failed ({status}): {message}. Run `nrz login` or pass --token"
+            ),
+            _ => anyhow::anyhow!("API request failed ({status}): {message}"),
+        }
+    }
+
+    /// Run a query in `raw` mode; the first result row holds column names.
+    pub async fn db_query_raw(&self, sql: &str) -> anyhow::Result<serde_json::Value> {
+        let resp = self
+            .send(
+                self.http
+                    .post(self.url(&["db", "query"]))
+                    .json(&serde_json::json!({
+                        "sql": sql,
+                        "bindings": [],
+                        "mode": "raw",
+                        "columnNames": true,
+                    })),
+            )
+            .await?;
+        if !resp.status().is_success() {
+            return Err(Self::error(resp).await);
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn kv_get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let resp = self.send(self.http.get(self.url(&["kv", key]))).await?;
+        match resp.status() {
+            reqwest::StatusCode::NOT_FOUND => Ok(None),
+            s if s.is_success() => Ok(Some(resp.json::<KvValueResponse>().await?.value)),
+            _ => Err(Self::error(resp).await),
+        }
+    }
+
+    pub async fn kv_set(&self, key: &str, value: &str, ttl: u64) -> anyhow::Result<()> {
+        let mut body = serde_json::json!({ "value": value });
+        if ttl > 0 {
+            body["ttl"] = serde_json::json!(ttl);
+        }
+        let resp = self
+            .send(self.http.put(self.url(&["kv", key])).json(&body))
+            .await?;
+        if !resp.status().is_success() {
+            return Err(Self::error(resp).await);
+        }
+        Ok(())
+    }
+
+    /// Returns `false` if the key did not exist.
+    pub async fn kv_delete(&self, key: &str) -> anyhow::Result<bool> {
+        let resp = self.send(self.http.delete(self.url(&["kv", key]))).await?;
+        match resp.status() {
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            s if s.is_success() => Ok(true),
+            _ => Err(Self::error(resp).await),
+        }
+    }
+
+    pub async fn kv_list(&self, prefix: Option<&str>, limit: usize) -> anyhow::Result<Vec<String>> {
+        let mut url = self.url(&["kv"]);
+        {
+            let mut query = url.query_pairs_mut();
+            if let Some(p) = prefix {
+                query.append_pair("prefix", p);
+            }
+            query.append_pair("limit", &limit.to_string());
+        }
+        let resp = self.send(self.http.get(url)).await?;
+        if !resp.status().is_success() {
+            return Err(Self::error(resp).await);
+        }
+        Ok(resp.json::<KvListResponse>().await?.keys)
+    }
+}
@@ -37,6 +37,13 @@ pub async fn whoami() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Load the API token saved by `nrz login`.
+pub fn load_token() -> Option<String> {
+    let content = std::fs::read_to_string(credentials_path()).ok()?;
+    let creds: serde_json::Value = serde_json::from_str(&content).ok()?;
+    creds.get("token")?.as_str().map(str::to_string)
+}
+
 fn credentials_path() -> std::path::PathBuf {
     dirs_home()
         .join(".config")
@@ -3,6 +3,8 @@ use std::path::Path;
 use anyhow::Context;
 use serde::Deserialize;
 
+use crate::dev::cron::Cron;
+
 #[derive(Debug, Deserialize)]
 pub struct Manifest {
     pub version: u32,
@@ -12,6 +14,7 @@ pub struct Manifest {
     pub assets: AssetsConfig,
     pub routes: Vec<Route>,
     pub prerender: Option<PrerenderConfig>,
+    pub triggers: Option<Triggers>,
     #[allow(dead_code)]
     pub features: Option<serde_json::Value>,
 }
@@ -80,6 +83,29 @@ pub struct PrerenderPage {
     pub data: Option<String>,
 }
 
+/// Events the platform invokes the app for, besides requests.
+#[derive(Debug, Default, Deserialize)]
+pub struct Triggers {
+    /// Cron expressions (UTC) for the scheduled handler
+    #[serde(default)]
+    pub crons: Vec<String>,
+}
+
+/// Cron triggers of the manifest at `path`, without validating the rest
+/// of it. Empty when the manifest declares none.
+pub fn read_crons(path: &Path) -> anyhow::Result<Vec<String>> {
+    #[derive(Deserialize)]
+    struct TriggersOnly {
+        triggers: Option<Triggers>,
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let manifest: TriggersOnly = serde_json::from_str(&content)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(manifest.triggers.unwrap_or_default().crons)
+}
+
 pub fn load_and_validate(path: &Path) -> anyhow::Result<Manifest> {
     let content = std::fs::read_to_string(path)
         .with_context(|| format!("failed to read {}", path.display()))?;
@@ -128,6 +154,13 @@ pub fn load_and_validate(path: &Path) -> anyhow::Result<Manifest> {
         }
     }
 
+    // Cron triggers must parse
+    if let Some(triggers) = &manifest.triggers {
+        for cron in &triggers.crons {
+            Cron::parse(cron).map_err(|e| anyhow::anyhow!("triggers.crons: {e}"))?;
+        }
+    }
+
     Ok(manifest)
 }
 
@@ -2,7 +2,7 @@
 
 use std::path::Path;
 
-use super::manifest::{RouteType, load_and_validate, verify_files};
+use super::manifest::{RouteType, load_and_validate, read_crons, verify_files};
 
 const VALID_MANIFEST: &str = r#"{
     "version": 1,
@@ -234,3 +234,35 @@ fn verify_files_missing_prerender_dir() {
     let err = verify_files(dir.path(), &m).unwrap_err();
     assert!(err.to_string().contains("prerender directory not found"));
 }
+
+#[test]
+fn manifest_with_cron_triggers() {
+    let dir = tempfile::tempdir().unwrap();
+    let json = VALID_MANIFEST.replace(
+        r#""routes""#,
+        r#""triggers": { "crons": ["*/10 * * * *", "0 3 * * SUN"] }, "routes""#,
+    );
+    let path = write_manifest(dir.path(), &json);
+    let m = load_and_validate(&path).unwrap();
+    assert_eq!(m.triggers.unwrap().crons, ["*/10 * * * *", "0 3 * * SUN"]);
+    assert_eq!(read_crons(&path).unwrap(), ["*/10 * * * *", "0 3 * * SUN"]);
+
+    let path = write_manifest(dir.path(), VALID_MANIFEST);
+    assert!(read_crons(&path).unwrap().is_empty());
+}
+
+#[test]
+fn invalid_cron_trigger() {
+    let dir = tempfile::tempdir().unwrap();
+    let json = VALID_MANIFEST.replace(
+        r#""routes""#,
+        r#""triggers": { "crons": ["every hour"] }, "routes""#,
+    );
+    let path = write_manifest(dir.path(), &json);
+    let err = load_and_validate(&path).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("triggers.crons: invalid cron expression 'every hour'"),
+        "{err}"
+    );
+}
@@ -8,23 +8,43 @@ use std::path::Path;
 use anyhow::Context;
 
 use crate::cli::BuildArgs;
+use crate::config::Config;
+use crate::dev::detect::detect_framework;
+use crate::dev::workspace::resolve_project_dir;
+
+/// Manifest written by the adapter, relative to the output directory.
+const MANIFEST_FILE: &str = ".onreza/manifest.json";
 
 /// Validate build output and manifest.
 ///
-/// 1. Locate output directory (dist/, build/, .output/)
+/// 1. Locate output directory (config `build.outputDir` or the framework's)
 /// 2. Find .onreza/manifest.json
 /// 3. Parse and validate against BUILD_OUTPUT_SPEC v1
 /// 4. Verify referenced files exist (server entry, assets dir, prerender dir)
 /// 5. Report results
 pub async fn run(args: BuildArgs) -> anyhow::Result<()> {
-    let project_dir = Path::new(&args.dir)
+    let dir = Path::new(&args.dir)
         .canonicalize()
         .with_context(|| format!("project directory not found: {}", args.dir))?;
+    let project_dir = resolve_project_dir(&dir, args.app.as_deref())?;
 
-    let output_dir = detect_output_dir(&project_dir)?;
+    let config = Config::load(&project_dir)?;
+    let output_dir = match config.build.output_dir {
+        Some(dir) => {
+            let dir = project_dir.join(dir);
+            if !dir.is_dir() {
+                anyhow::bail!(
+                    "output directory from config not found: {}. Did you run the build?",
+                    dir.display()
+                );
+            }
+            dir
+        }
+        None => detect_output_dir(&project_dir)?,
+    };
     tracing::info!(?output_dir, "found output directory");
 
-    let manifest_path = output_dir.join(".onreza/manifest.json");
+    let manifest_path = output_dir.join(MANIFEST_FILE);
     if !manifest_path.exists() {
         anyhow::bail!(
             "manifest not found at {}. Did the adapter run during build?",
@@ -52,13 +72,44 @@ pub async fn run(args: BuildArgs) -> anyhow::Result<()> {
         manifest.routes.len(),
         manifest.server.entry,
     );
+    if let Some(triggers) = manifest.triggers.as_ref().filter(|t| !t.crons.is_empty()) {
+        eprintln!(
+            "  {} cron triggers: {}",
+            console::style("✓").green().bold(),
+            triggers.crons.join(", "),
+        );
+    }
 
     Ok(())
 }
 
-/// Try common output directory names.
+/// Cron triggers in the manifest of the last build, or none when the
+/// project has not been built.
+pub fn manifest_crons(project_dir: &Path, config: &Config) -> anyhow::Result<Vec<String>> {
+    let output_dir = match &config.build.output_dir {
+        Some(dir) => project_dir.join(dir),
+        None => match detect_output_dir(project_dir) {
+            Ok(dir) => dir,
+            Err(_) => return Ok(Vec::new()),
+        },
+    };
+    let manifest_path = output_dir.join(MANIFEST_FILE);
+    if !manifest_path.is_file() {
+        return Ok(Vec::new());
+    }
+    manifest::read_crons(&manifest_path)
+}
+
+/// Locate the build output using the detected framework's output
+/// directory, falling back to common names when detection fails.
 fn detect_output_dir(project_dir: &Path) -> anyhow::Result<std::path::PathBuf> {
-    for name in ["dist", ".output", "build"] {
+    let framework = detect_framework(project_dir).ok();
+    let candidates = match framework {
+        Some(ref fw) => vec![fw.output_dir],
+        None => vec!["dist", ".output", "build"],
+    };
+
+    for name in &candidates {
         let candidate = project_dir.join(name);
         if candidate.is_dir() && candidate.join(".onreza").is_dir() {
             return Ok(candidate);
@@ -66,7 +117,7 @@ fn detect_output_dir(project_dir: &Path) -> anyhow::Result<std::path::PathBuf> {
     }
 
     // Check if output dir exists but without .onreza
-    for name in ["dist", ".output", "build"] {
+    for name in &candidates {
         let candidate = project_dir.join(name);
         if candidate.is_dir() {
             anyhow::bail!(
@@ -77,8 +128,16 @@ fn detect_output_dir(project_dir: &Path) -> anyhow::Result<std::path::PathBuf> {
         }
     }
 
-    anyhow::bail!(
-        "no output directory found in {}. Expected dist/, .output/, or build/",
-        project_dir.display()
-    );
+    match framework {
+        Some(fw) => anyhow::bail!(
+            "no output directory found in {}. {:?} builds to {}/ — did you run the build?",
+            project_dir.display(),
+            fw.name,
+            fw.output_dir
+        ),
+        None => anyhow::bail!(
+            "no output directory found in {}. Expected dist/, .output/, or build/",
+            project_dir.display()
+        ),
+    }
 }
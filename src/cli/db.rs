@@ -1,7 +1,15 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use super::remote::{RemoteArgs, RemoteTarget};
 
 #[derive(Parser)]
 pub struct DbArgs {
+    /// App to use in a monorepo workspace (package name or path)
+    #[arg(long, global = true)]
+    pub app: Option<String>,
+
     #[command(subcommand)]
     pub command: DbCommand,
 }
@@ -15,10 +23,62 @@ pub enum DbCommand {
     Execute {
         /// SQL query to execute
         sql: String,
+
+        #[command(flatten)]
+        remote: RemoteArgs,
     },
 
     /// Show database info (tables, size)
-    Info,
+    Info {
+        #[command(flatten)]
+        remote: RemoteArgs,
+    },
+
+    /// Show schema: columns, keys, indexes, triggers and views
+    Schema {
+        /// Only show this table or view
+        table: Option<String>,
+
+        /// Print schema as JSON to stdout
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Generate TypeScript row types from the local database schema
+    Types {
+        /// Output file
+        #[arg(long, default_value = "src/db.d.ts")]
+        out: PathBuf,
+
+        /// Build the schema by applying migrations to a scratch database
+        /// instead of reading dev.db (default DIR: bindings.db.migrationsDir
+        /// from onreza.jsonc, or migrations)
+        #[arg(long, value_name = "DIR", num_args = 0..=1)]
+        migrations: Option<Option<PathBuf>>,
+    },
+
+    /// Compare schemas between migrations, the local and the remote database
+    Diff {
+        /// Schema to compare from
+        #[arg(long, value_enum, default_value = "migrations")]
+        from: SchemaSource,
+
+        /// Schema to compare to
+        #[arg(long, value_enum, default_value = "local")]
+        to: SchemaSource,
+
+        /// Migrations directory (default: bindings.db.migrationsDir from
+        /// onreza.jsonc, or migrations)
+        #[arg(long)]
+        migrations_dir: Option<PathBuf>,
+
+        /// Write a migration that turns --from into --to
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+
+        #[command(flatten)]
+        remote: RemoteTarget,
+    },
 
     /// Reset local database (delete and recreate)
     Reset {
@@ -27,3 +87,14 @@ pub enum DbCommand {
         force: bool,
     },
 }
+
+/// Where a schema for `nrz db diff` comes from.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaSource {
+    /// Local dev database (.onreza/data/dev.db)
+    Local,
+    /// Migrations applied to a scratch database
+    Migrations,
+    /// Deployed database (see --env / --project)
+    Remote,
+}
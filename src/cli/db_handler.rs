@@ -5,12 +5,25 @@ use std::path::Path;
 use anyhow::Context;
 use rusqlite::Connection;
 
+use crate::config::Config;
+use crate::dev::workspace::resolve_project_dir;
 use nrz::emulator::data_dir;
+use nrz::emulator::db::{self as schema_db, Column, Schema, quote_ident};
+use nrz::emulator::schema_diff::{self, Change};
+use nrz::emulator::typegen;
 
-use super::db::{DbArgs, DbCommand};
+use super::db::{DbArgs, DbCommand, SchemaSource};
+use super::remote::RemoteTarget;
+use crate::api::ApiClient;
+
+/// SQL condition leaving out SQLite's and the platform's internal tables
+/// in `sqlite_master`. `_` matches any character in LIKE, so it is escaped.
+const USER_OBJECTS: &str =
+    r"name NOT LIKE 'sqlite\_%' ESCAPE '\' AND name NOT LIKE '\_cf\_%' ESCAPE '\'";
 
 pub async fn run(args: DbArgs) -> anyhow::Result<()> {
-    let project_dir = Path::new(".").canonicalize()?;
+    let project_dir = resolve_project_dir(&Path::new(".").canonicalize()?, args.app.as_deref())?;
+    let config = Config::load(&project_dir)?;
     let data_dir = data_dir(&project_dir);
     let db_path = data_dir.join("dev.db");
 
@@ -20,7 +33,11 @@ pub async fn run(args: DbArgs) -> anyhow::Result<()> {
             eprintln!("nrz db shell: not yet implemented");
             eprintln!("  use `nrz db execute <sql>` for now");
         }
-        DbCommand::Execute { sql } => {
+        DbCommand::Execute { sql, remote } => {
+            if let Some(api) = remote.client(&config)? {
+                return remote_execute(&api, &sql).await;
+            }
+
             if !db_path.exists() {
                 std::fs::create_dir_all(&data_dir)?;
             }
@@ -60,39 +77,14 @@ pub async fn run(args: DbArgs) -> anyhow::Result<()> {
                     }
                     rows.push(values);
                 }
-
-                // Calculate column widths
-                let mut widths: Vec<usize> = col_names.iter().map(|n| n.len()).collect();
-                for row in &rows {
-                    for (i, val) in row.iter().enumerate() {
-                        widths[i] = widths[i].max(val.len());
-                    }
-                }
-
-                // Print header
-                let header: Vec<String> = col_names
-                    .iter()
-                    .enumerate()
-                    .map(|(i, n)| format!("{:width$}", n, width = widths[i]))
-                    .collect();
-                eprintln!("{}", header.join(" | "));
-                let sep: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
-                eprintln!("{}", sep.join("-+-"));
-
-                // Print rows
-                for row in &rows {
-                    let formatted: Vec<String> = row
-                        .iter()
-                        .enumerate()
-                        .map(|(i, v)| format!("{:width$}", v, width = widths[i]))
-                        .collect();
-                    eprintln!("{}", formatted.join(" | "));
-                }
-
-                eprintln!("\n{} row(s)", rows.len());
+                print_table(&col_names, &rows);
             }
         }
-        DbCommand::Info => {
+        DbCommand::Info { remote } => {
+            if let Some(api) = remote.client(&config)? {
+                return remote_info(&api).await;
+            }
+
             if !db_path.exists() {
                 eprintln!("database: {} (not created yet)", db_path.display());
                 eprintln!("  run `nrz dev` or `nrz db execute` to create it");
@@ -122,12 +114,130 @@ pub async fn run(args: DbArgs) -> anyhow::Result<()> {
                 eprintln!("\ntables:");
                 for table in &tables {
                     let count: i64 = conn
-                        .query_row(&format!("SELECT COUNT(*) FROM [{table}]"), [], |r| r.get(0))
+                        .query_row(
+                            &format!("SELECT COUNT(*) FROM {}", quote_ident(table)),
+                            [],
+                            |r| r.get(0),
+                        )
                         .unwrap_or(0);
                     eprintln!("  {table}: {count} row(s)");
                 }
             }
         }
+        DbCommand::Schema { table, json } => {
+            let mut schema = if db_path.exists() {
+                let conn = Connection::open(&db_path)
+                    .with_context(|| format!("failed to open {}", db_path.display()))?;
+                schema_db::introspect(&conn).context("failed to read schema")?
+            } else if json {
+                Schema::default()
+            } else {
+                eprintln!("database: {} (not created yet)", db_path.display());
+                eprintln!("  run `nrz dev` or `nrz db execute` to create it");
+                return Ok(());
+            };
+
+            if let Some(ref name) = table {
+                if schema.table(name).is_none() && schema.view(name).is_none() {
+                    anyhow::bail!("no table or view named '{name}'");
+                }
+                schema.tables.retain(|t| &t.name == name);
+                schema.views.retain(|v| &v.name == name);
+            }
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&schema)?);
+            } else {
+                print_schema(&schema);
+            }
+        }
+        DbCommand::Types { out, migrations } => {
+            let schema = if let Some(dir) = migrations {
+                let dir = dir.unwrap_or_else(|| config.bindings.db.migrations_dir());
+                let conn = Connection::open_in_memory()?;
+                let applied = schema_db::apply_migrations(&conn, &project_dir.join(&dir))?;
+                eprintln!(
+                    "applied {} migration(s) from {}",
+                    applied.len(),
+                    dir.display()
+                );
+                schema_db::introspect(&conn).context("failed to read schema")?
+            } else {
+                if !db_path.exists() {
+                    anyhow::bail!(
+                        "database not found: {}. Run `nrz dev` first or use --migrations",
+                        db_path.display()
+                    );
+                }
+                let conn = Connection::open(&db_path)
+                    .with_context(|| format!("failed to open {}", db_path.display()))?;
+                schema_db::introspect(&conn).context("failed to read schema")?
+            };
+
+            let out_path = project_dir.join(&out);
+            if let Some(parent) = out_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&out_path, typegen::generate_typescript(&schema))
+                .with_context(|| format!("failed to write {}", out_path.display()))?;
+            eprintln!(
+                "wrote {} table(s), {} view(s) to {}",
+                schema.tables.len(),
+                schema.views.len(),
+                out.display()
+            );
+        }
+        DbCommand::Diff {
+            from,
+            to,
+            migrations_dir,
+            out,
+            remote,
+        } => {
+            let migrations_dir = project_dir
+                .join(migrations_dir.unwrap_or_else(|| config.bindings.db.migrations_dir()));
+            let from_schema =
+                load_schema(from, &db_path, &migrations_dir, &remote, &config).await?;
+            let to_schema = load_schema(to, &db_path, &migrations_dir, &remote, &config).await?;
+
+            let changes = schema_diff::diff(&from_schema, &to_schema);
+            if changes.is_empty() {
+                eprintln!(
+                    "schemas are identical ({} -> {})",
+                    source_name(from),
+                    source_name(to)
+                );
+                return Ok(());
+            }
+
+            eprintln!("--- {}\n+++ {}", source_name(from), source_name(to));
+            for change in &changes {
+                let line = change.to_string();
+                let styled = match change {
+                    Change::CreateTable(_)
+                    | Change::AddColumn { .. }
+                    | Change::CreateIndex { .. }
+                    | Change::CreateView { .. } => console::style(line).green(),
+                    Change::DropTable(_)
+                    | Change::DropColumn { .. }
+                    | Change::DropIndex { .. }
+                    | Change::DropView(_) => console::style(line).red(),
+                    _ => console::style(line).yellow(),
+                };
+                eprintln!("{styled}");
+            }
+            eprintln!("\n{} change(s)", changes.len());
+
+            if let Some(out) = out {
+                let out_path = project_dir.join(&out);
+                if let Some(parent) = out_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&out_path, schema_diff::migration_sql(&to_schema, &changes))
+                    .with_context(|| format!("failed to write {}", out_path.display()))?;
+                eprintln!("wrote migration to {}", out.display());
+            }
+        }
         DbCommand::Reset { force } => {
             if !force {
                 eprintln!("use --force to confirm database reset");
@@ -149,6 +259,265 @@ pub async fn run(args: DbArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Print query results as an aligned text table.
+fn print_table(col_names: &[String], rows: &[Vec<String>]) {
+    // Calculate column widths
+    let mut widths: Vec<usize> = col_names.iter().map(|n| n.len()).collect();
+    for row in rows {
+        for (i, val) in row.iter().enumerate() {
+            widths[i] = widths[i].max(val.len());
+        }
+    }
+
+    // Print header
+    let header: Vec<String> = col_names
+        .iter()
+        .enumerate()
+        .map(|(i, n)| format!("{:width$}", n, width = widths[i]))
+        .collect();
+    eprintln!("{}", header.join(" | "));
+    let sep: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    eprintln!("{}", sep.join("-+-"));
+
+    // Print rows
+    for row in rows {
+        let formatted: Vec<String> = row
+            .iter()
+            .enumerate()
+            .map(|(i, v)| format!("{:width$}", v, width = widths[i]))
+            .collect();
+        eprintln!("{}", formatted.join(" | "));
+    }
+
+    eprintln!("\n{} row(s)", rows.len());
+}
+
+/// Split a `raw` mode response with column names into header and rows.
+fn raw_results(resp: &serde_json::Value) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut rows = resp["results"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default()
+        .into_iter();
+    let columns: Vec<String> = rows
+        .next()
+        .and_then(|r| r.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .map(|c| c.as_str().unwrap_or_default().to_string())
+        .collect();
+    let rows = rows
+        .map(|row| {
+            row.as_array()
+                .cloned()
+                .unwrap_or_default()
+                .iter()
+                .map(|v| match v {
+                    serde_json::Value::Null => "NULL".to_string(),
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect()
+        })
+        .collect();
+    (columns, rows)
+}
+
+async fn remote_execute(api: &ApiClient, sql: &str) -> anyhow::Result<()> {
+    let resp = api.db_query_raw(sql).await?;
+    let (columns, rows) = raw_results(&resp);
+    if columns.is_empty() {
+        let changes = resp["meta"]["changes"].as_i64().unwrap_or(0);
+        eprintln!("{changes} row(s) affected");
+    } else {
+        print_table(&columns, &rows);
+    }
+    Ok(())
+}
+
+async fn remote_info(api: &ApiClient) -> anyhow::Result<()> {
+    eprintln!("database: {} ({})", api.project(), api.env().as_str());
+
+    let resp = api
+        .db_query_raw(&format!(
+            "SELECT name FROM sqlite_master WHERE type='table' AND {USER_OBJECTS} ORDER BY name"
+        ))
+        .await?;
+    let (_, rows) = raw_results(&resp);
+    if rows.is_empty() {
+        eprintln!("tables: (none)");
+        return Ok(());
+    }
+
+    eprintln!("\ntables:");
+    for row in rows {
+        let table = &row[0];
+        let resp = api
+            .db_query_raw(&format!("SELECT COUNT(*) FROM {}", quote_ident(table)))
+            .await?;
+        let count = raw_results(&resp)
+            .1
+            .first()
+            .and_then(|r| r.first().cloned())
+            .unwrap_or_else(|| "?".into());
+        eprintln!("  {table}: {count} row(s)");
+    }
+    Ok(())
+}
+
+fn source_name(source: SchemaSource) -> &'static str {
+    match source {
+        SchemaSource::Local => "local",
+        SchemaSource::Migrations => "migrations",
+        SchemaSource::Remote => "remote",
+    }
+}
+
+/// Load a schema for `nrz db diff`.
+///
+/// Migrations and remote schemas are materialized in a scratch in-memory
+/// database so all sources are introspected the same way.
+async fn load_schema(
+    source: SchemaSource,
+    db_path: &Path,
+    migrations_dir: &Path,
+    remote: &RemoteTarget,
+    config: &Config,
+) -> anyhow::Result<Schema> {
+    match source {
+        SchemaSource::Local => {
+            if !db_path.exists() {
+                eprintln!(
+                    "  {} local database not created yet, treating as empty",
+                    console::style("!").yellow().bold(),
+                );
+                return Ok(Schema::default());
+            }
+            let conn = Connection::open(db_path)
+                .with_context(|| format!("failed to open {}", db_path.display()))?;
+            Ok(schema_db::introspect(&conn)?)
+        }
+        SchemaSource::Migrations => {
+            let conn = Connection::open_in_memory()?;
+            schema_db::apply_migrations(&conn, migrations_dir)?;
+            Ok(schema_db::introspect(&conn)?)
+        }
+        SchemaSource::Remote => {
+            let api = remote.client(config)?;
+            let resp = api
+                .db_query_raw(&format!(
+                    "SELECT sql FROM sqlite_master WHERE sql IS NOT NULL AND {USER_OBJECTS} \
+                     ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'index' THEN 1 \
+                     WHEN 'view' THEN 2 ELSE 3 END, name"
+                ))
+                .await?;
+            let conn = Connection::open_in_memory()?;
+            for row in raw_results(&resp).1 {
+                conn.execute_batch(&row[0])
+                    .with_context(|| format!("failed to replay remote schema: {}", row[0]))?;
+            }
+            Ok(schema_db::introspect(&conn)?)
+        }
+    }
+}
+
+fn print_schema(schema: &Schema) {
+    if schema.tables.is_empty() && schema.views.is_empty() {
+        eprintln!("(no tables)");
+        return;
+    }
+
+    for (i, table) in schema.tables.iter().enumerate() {
+        if i > 0 {
+            eprintln!();
+        }
+        eprintln!("table {}", table.name);
+        print_columns(&table.columns);
+
+        if !table.foreign_keys.is_empty() {
+            eprintln!("  foreign keys:");
+            for fk in &table.foreign_keys {
+                let to: Vec<&str> = fk
+                    .references_columns
+                    .iter()
+                    .map(|c| c.as_deref().unwrap_or("<pk>"))
+                    .collect();
+                eprintln!(
+                    "    ({}) -> {}({}) ON UPDATE {} ON DELETE {}",
+                    fk.columns.join(", "),
+                    fk.references_table,
+                    to.join(", "),
+                    fk.on_update,
+                    fk.on_delete,
+                );
+            }
+        }
+
+        if !table.indexes.is_empty() {
+            eprintln!("  indexes:");
+            for idx in &table.indexes {
+                let mut flags = Vec::new();
+                if idx.unique {
+                    flags.push("UNIQUE");
+                }
+                if idx.partial {
+                    flags.push("PARTIAL");
+                }
+                match idx.origin.as_str() {
+                    "pk" => flags.push("(primary key)"),
+                    "u" => flags.push("(constraint)"),
+                    _ => {}
+                }
+                eprintln!(
+                    "    {} ({}) {}",
+                    idx.name,
+                    idx.columns.join(", "),
+                    flags.join(" ")
+                );
+            }
+        }
+
+        if !table.triggers.is_empty() {
+            eprintln!("  triggers:");
+            for trigger in &table.triggers {
+                eprintln!("    {}", trigger.name);
+            }
+        }
+    }
+
+    for (i, view) in schema.views.iter().enumerate() {
+        if i > 0 || !schema.tables.is_empty() {
+            eprintln!();
+        }
+        eprintln!("view {}", view.name);
+        print_columns(&view.columns);
+    }
+}
+
+fn print_columns(columns: &[Column]) {
+    let name_width = columns.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let type_width = columns.iter().map(|c| c.decl_type.len()).max().unwrap_or(0);
+    for col in columns {
+        let mut attrs = Vec::new();
+        if col.primary_key {
+            attrs.push("PRIMARY KEY".to_string());
+        }
+        if col.not_null {
+            attrs.push("NOT NULL".to_string());
+        }
+        if let Some(ref default) = col.default {
+            attrs.push(format!("DEFAULT {default}"));
+        }
+        let line = format!(
+            "    {:name_width$}  {:type_width$}  {}",
+            col.name,
+            col.decl_type,
+            attrs.join(" ")
+        );
+        eprintln!("{}", line.trim_end());
+    }
+}
+
 fn format_size(bytes: u64) -> String {
     if bytes < 1024 {
         format!("{bytes} B")
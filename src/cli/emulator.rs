@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use nrz::emulator::d1_compat::CompatMode;
+
+#[derive(Parser)]
+pub struct EmulatorArgs {
+    /// App to use in a monorepo workspace (package name or path)
+    #[arg(long, global = true)]
+    pub app: Option<String>,
+
+    #[command(subcommand)]
+    pub command: EmulatorCommand,
+}
+
+#[derive(Subcommand)]
+pub enum EmulatorCommand {
+    /// Run only the KV/DB emulator, e.g. for test suites.
+    /// Prints its address, token and bootstrap path as JSON on stdout
+    Start(EmulatorStartArgs),
+}
+
+#[derive(Parser)]
+pub struct EmulatorStartArgs {
+    /// Port to listen on (0 picks a free one)
+    #[arg(long, default_value = "0")]
+    pub port: u16,
+
+    /// Start with empty data in a temporary directory, removed on exit
+    #[arg(long, conflicts_with = "data_dir")]
+    pub ephemeral: bool,
+
+    /// Directory for the database and bootstrap (default: .onreza/data)
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+
+    /// How to handle SQL that D1 would reject: strict, warn or off (default: warn)
+    #[arg(long)]
+    pub d1_compat: Option<CompatMode>,
+}
@@ -0,0 +1,102 @@
+//! CLI handler for `nrz emulator` subcommands.
+
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::config::Config;
+use crate::dev::inject::{BootstrapOptions, generate_bootstrap};
+use crate::dev::process::shutdown_signal;
+use crate::dev::workspace::resolve_project_dir;
+use nrz::emulator::auth::session_token;
+use nrz::emulator::kv::KvStore;
+use nrz::emulator::protocol::PROTOCOL_VERSION;
+use nrz::emulator::server::{EmulatorServer, SOCKET_FILE};
+use nrz::emulator::{EMULATOR_BOOTSTRAP_FILE, data_dir, write_private};
+
+use super::emulator::{EmulatorArgs, EmulatorCommand, EmulatorStartArgs};
+
+pub async fn run(args: EmulatorArgs) -> anyhow::Result<()> {
+    let project_dir = resolve_project_dir(&Path::new(".").canonicalize()?, args.app.as_deref())?;
+    let config = Config::load(&project_dir)?;
+
+    match args.command {
+        EmulatorCommand::Start(start_args) => start(&project_dir, &config, start_args).await,
+    }
+}
+
+/// Run the emulator until SIGINT, SIGTERM or SIGHUP.
+///
+/// Test runners read the first line of stdout, a JSON object with the
+/// address, socket, token and bootstrap path. Preload the bootstrap into
+/// the code under test (`node --import`, `bun --preload`) to get
+/// `ONREZA.kv` and `ONREZA.db` backed by this emulator.
+async fn start(project_dir: &Path, config: &Config, args: EmulatorStartArgs) -> anyhow::Result<()> {
+    let dir = if args.ephemeral {
+        let suffix = &session_token()?[..16];
+        std::env::temp_dir().join(format!("nrz-emulator-{suffix}"))
+    } else {
+        args.data_dir
+            .as_ref()
+            .map(|d| project_dir.join(d))
+            .unwrap_or_else(|| data_dir(project_dir))
+    };
+    std::fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+
+    let result = serve(config, &dir, &args).await;
+
+    let _ = std::fs::remove_file(dir.join(EMULATOR_BOOTSTRAP_FILE));
+    if args.ephemeral {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    result
+}
+
+async fn serve(config: &Config, dir: &Path, args: &EmulatorStartArgs) -> anyhow::Result<()> {
+    // Before the address is printed, so runners can signal right away
+    let mut signal = shutdown_signal();
+    let mut server = EmulatorServer::new(KvStore::new(), dir.join("dev.db"), args.port);
+    server.d1_compat = args.d1_compat.or(config.dev.d1_compat).unwrap_or_default();
+    server.socket = Some(dir.join(SOCKET_FILE));
+    let mut emulator = server.bind().await?;
+    let port = emulator.addr().port();
+    let bootstrap_path = dir.join(EMULATOR_BOOTSTRAP_FILE);
+    write_private(
+        &bootstrap_path,
+        generate_bootstrap(&BootstrapOptions {
+            data_dir: dir.to_path_buf(),
+            port,
+            socket: emulator.socket().map(Path::to_path_buf),
+            bindings: config.bindings.clone(),
+            token: emulator.token().to_string(),
+            ..Default::default()
+        })?,
+    )?;
+
+    let url = emulator.url();
+    println!(
+        "{}",
+        serde_json::json!({
+            "url": url,
+            "port": port,
+            "socket": emulator.socket(),
+            "token": emulator.token(),
+            "protocol": PROTOCOL_VERSION,
+            "bootstrap": bootstrap_path,
+            "dataDir": dir,
+            "pid": std::process::id(),
+        })
+    );
+    eprintln!(
+        "  {} emulator ready on {url}",
+        console::style("~").cyan().bold(),
+    );
+
+    tokio::select! {
+        result = emulator.wait() => return result,
+        _ = signal.wait_for(Option::is_some) => {}
+    }
+    emulator.shutdown().await?;
+    eprintln!("  {} emulator stopped", console::style("✓").green().bold());
+    Ok(())
+}
@@ -1,7 +1,13 @@
 use clap::{Parser, Subcommand};
 
+use super::remote::RemoteArgs;
+
 #[derive(Parser)]
 pub struct KvArgs {
+    /// App to use in a monorepo workspace (package name or path)
+    #[arg(long, global = true)]
+    pub app: Option<String>,
+
     #[command(subcommand)]
     pub command: KvCommand,
 }
@@ -9,7 +15,12 @@ pub struct KvArgs {
 #[derive(Subcommand)]
 pub enum KvCommand {
     /// Get a value by key
-    Get { key: String },
+    Get {
+        key: String,
+
+        #[command(flatten)]
+        remote: RemoteArgs,
+    },
 
     /// Set a key-value pair
     Set {
@@ -19,10 +30,18 @@ pub enum KvCommand {
         /// TTL in seconds (0 = no expiry)
         #[arg(long, default_value = "0")]
         ttl: u64,
+
+        #[command(flatten)]
+        remote: RemoteArgs,
     },
 
     /// Delete a key
-    Delete { key: String },
+    Delete {
+        key: String,
+
+        #[command(flatten)]
+        remote: RemoteArgs,
+    },
 
     /// List keys with optional prefix
     List {
@@ -33,6 +52,9 @@ pub enum KvCommand {
         /// Max number of keys to return
         #[arg(long, default_value = "100")]
         limit: usize,
+
+        #[command(flatten)]
+        remote: RemoteArgs,
     },
 
     /// Clear all KV data
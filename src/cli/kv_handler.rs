@@ -2,16 +2,26 @@
 
 use std::path::Path;
 
+use crate::config::Config;
+use crate::dev::workspace::resolve_project_dir;
 use nrz::emulator::kv::{KvFileEntry, is_expired, kv_file_path, load_kv_file, save_kv_file};
 
 use super::kv::{KvArgs, KvCommand};
 
 pub async fn run(args: KvArgs) -> anyhow::Result<()> {
-    let project_dir = Path::new(".").canonicalize()?;
+    let project_dir = resolve_project_dir(&Path::new(".").canonicalize()?, args.app.as_deref())?;
+    let config = Config::load(&project_dir)?;
     let path = kv_file_path(&project_dir);
 
     match args.command {
-        KvCommand::Get { key } => {
+        KvCommand::Get { key, remote } => {
+            if let Some(api) = remote.client(&config)? {
+                match api.kv_get(&key).await? {
+                    Some(value) => println!("{value}"),
+                    None => eprintln!("(not found)"),
+                }
+                return Ok(());
+            }
             let kv = load_kv_file(&path);
             match kv.entries.get(&key) {
                 Some(entry) if !is_expired(entry) => {
@@ -22,7 +32,17 @@ pub async fn run(args: KvArgs) -> anyhow::Result<()> {
                 }
             }
         }
-        KvCommand::Set { key, value, ttl } => {
+        KvCommand::Set {
+            key,
+            value,
+            ttl,
+            remote,
+        } => {
+            if let Some(api) = remote.client(&config)? {
+                api.kv_set(&key, &value, ttl).await?;
+                eprintln!("OK");
+                return Ok(());
+            }
             let mut kv = load_kv_file(&path);
             let expires_at = if ttl > 0 {
                 let now = std::time::SystemTime::now()
@@ -38,7 +58,15 @@ pub async fn run(args: KvArgs) -> anyhow::Result<()> {
             save_kv_file(&path, &kv)?;
             eprintln!("OK");
         }
-        KvCommand::Delete { key } => {
+        KvCommand::Delete { key, remote } => {
+            if let Some(api) = remote.client(&config)? {
+                if api.kv_delete(&key).await? {
+                    eprintln!("deleted");
+                } else {
+                    eprintln!("(not found)");
+                }
+                return Ok(());
+            }
             let mut kv = load_kv_file(&path);
             if kv.entries.remove(&key).is_some() {
                 save_kv_file(&path, &kv)?;
@@ -47,7 +75,21 @@ pub async fn run(args: KvArgs) -> anyhow::Result<()> {
                 eprintln!("(not found)");
             }
         }
-        KvCommand::List { prefix, limit } => {
+        KvCommand::List {
+            prefix,
+            limit,
+            remote,
+        } => {
+            if let Some(api) = remote.client(&config)? {
+                let keys = api.kv_list(prefix.as_deref(), limit).await?;
+                for key in &keys {
+                    println!("{key}");
+                }
+                if keys.is_empty() {
+                    eprintln!("(empty)");
+                }
+                return Ok(());
+            }
             let kv = load_kv_file(&path);
             let mut count = 0;
             for (key, entry) in &kv.entries {
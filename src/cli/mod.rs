@@ -1,13 +1,24 @@
 pub mod db;
 pub mod db_handler;
+pub mod emulator;
+pub mod emulator_handler;
 pub mod kv;
 pub mod kv_handler;
+pub mod remote;
+pub mod trigger;
+pub mod trigger_handler;
 
 pub use db::DbArgs;
+pub use emulator::EmulatorArgs;
 pub use kv::KvArgs;
+pub use trigger::TriggerArgs;
 
 use clap::{Parser, Subcommand};
 
+use crate::dev::js_runtime::JsRuntime;
+use crate::dev::package_manager::PackageManager;
+use nrz::emulator::d1_compat::CompatMode;
+
 /// ONREZA platform CLI
 #[derive(Parser)]
 #[command(
@@ -37,6 +48,15 @@ pub enum Command {
     /// Manage local KV store
     Kv(KvArgs),
 
+    /// Run the local emulator on its own (for tests and CI)
+    Emulator(EmulatorArgs),
+
+    /// Invoke the app's event handlers in the running `nrz dev`
+    Trigger(TriggerArgs),
+
+    /// Generate TypeScript declarations for the ONREZA global (onreza.d.ts)
+    Types(TypesArgs),
+
     /// Log in to ONREZA platform
     Login,
 
@@ -53,13 +73,49 @@ pub struct DevArgs {
     #[arg(long)]
     pub command: Option<String>,
 
-    /// Port for the dev server
-    #[arg(short, long, default_value = "4321")]
-    pub port: u16,
+    /// Port for the dev server (default: the framework's own default)
+    #[arg(short, long)]
+    pub port: Option<u16>,
+
+    /// Port for the emulator API (default: the next free port after --port)
+    #[arg(long, env = "NRZ_EMULATOR_PORT")]
+    pub emulator_port: Option<u16>,
+
+    /// Package manager that runs the dev command (default: detect from lockfile)
+    #[arg(long, env = "NRZ_PACKAGE_MANAGER")]
+    pub package_manager: Option<PackageManager>,
+
+    /// JS runtime of the dev server: node, bun or deno (default: detect from the command)
+    #[arg(long, env = "NRZ_RUNTIME")]
+    pub runtime: Option<JsRuntime>,
+
+    /// Mode for env files: loads .env.<mode> and .env.<mode>.local
+    #[arg(long, default_value = "development")]
+    pub mode: String,
+
+    /// How to handle SQL that D1 would reject: strict, warn or off (default: warn)
+    #[arg(long)]
+    pub d1_compat: Option<CompatMode>,
+
+    /// Geo for ONREZA.context, e.g. --geo country=DE --geo city=Berlin
+    #[arg(long, value_name = "KEY=VALUE")]
+    pub geo: Vec<String>,
+
+    /// Run the framework dev server directly, without the context proxy
+    #[arg(long)]
+    pub no_proxy: bool,
+
+    /// Exit when the dev server crashes instead of restarting it
+    #[arg(long)]
+    pub no_restart: bool,
 
     /// Path to project directory
     #[arg(default_value = ".")]
     pub dir: String,
+
+    /// App to use in a monorepo workspace (package name or path)
+    #[arg(long)]
+    pub app: Option<String>,
 }
 
 #[derive(Parser)]
@@ -68,17 +124,48 @@ pub struct BuildArgs {
     #[arg(default_value = ".")]
     pub dir: String,
 
+    /// App to use in a monorepo workspace (package name or path)
+    #[arg(long)]
+    pub app: Option<String>,
+
     /// Skip manifest validation
     #[arg(long)]
     pub skip_validation: bool,
 }
 
+#[derive(Parser)]
+pub struct TypesArgs {
+    /// Path to project directory
+    #[arg(default_value = ".")]
+    pub dir: String,
+
+    /// App to use in a monorepo workspace (package name or path)
+    #[arg(long)]
+    pub app: Option<String>,
+
+    /// Output file, relative to the project (default: onreza.d.ts)
+    #[arg(long)]
+    pub out: Option<std::path::PathBuf>,
+
+    /// Mode whose env files declare the variable names
+    #[arg(long, default_value = "development")]
+    pub mode: String,
+}
+
 #[derive(Parser)]
 pub struct DeployArgs {
     /// Path to project directory
     #[arg(default_value = ".")]
     pub dir: String,
 
+    /// App to use in a monorepo workspace (package name or path)
+    #[arg(long)]
+    pub app: Option<String>,
+
+    /// Project id (or NRZ_PROJECT_ID env var, or projectId in onreza.jsonc)
+    #[arg(long, env = "NRZ_PROJECT_ID")]
+    pub project: Option<String>,
+
     /// Deploy token (or NRZ_TOKEN env var)
     #[arg(long, env = "NRZ_TOKEN")]
     pub token: Option<String>,
@@ -0,0 +1,71 @@
+use clap::Args;
+
+use crate::api::{ApiClient, Environment};
+use crate::config::Config;
+
+/// Flags for running `db`/`kv` commands against a deployed environment.
+#[derive(Args, Clone)]
+pub struct RemoteArgs {
+    /// Operate on the deployed environment through the platform API
+    #[arg(long)]
+    pub remote: bool,
+
+    #[command(flatten)]
+    pub target: RemoteTarget,
+}
+
+/// Which deployed environment to talk to, and how to authenticate.
+#[derive(Args, Clone)]
+pub struct RemoteTarget {
+    /// Deployed environment
+    #[arg(long, value_enum, default_value = "preview")]
+    pub env: Environment,
+
+    /// Project id (or NRZ_PROJECT_ID env var, or projectId in onreza.jsonc)
+    #[arg(long, env = "NRZ_PROJECT_ID")]
+    pub project: Option<String>,
+
+    /// API token (or NRZ_TOKEN env var, defaults to `nrz login` credentials)
+    #[arg(long, env = "NRZ_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+}
+
+impl RemoteArgs {
+    /// API client for `--remote`, or `None` for local operation.
+    pub fn client(&self, config: &Config) -> anyhow::Result<Option<ApiClient>> {
+        if !self.remote {
+            return Ok(None);
+        }
+        self.target.client(config).map(Some)
+    }
+}
+
+impl RemoteTarget {
+    pub fn client(&self, config: &Config) -> anyhow::Result<ApiClient> {
+        let token = self
+            .token
+            .clone()
+            .or_else(crate::auth::load_token)
+            .ok_or_else(|| {
+                anyhow::anyhow!("not logged in. Run `nrz login`, pass --token or set NRZ_TOKEN")
+            })?;
+        let project = self
+            .project
+            .as_deref()
+            .or(config.project_id(self.env.as_str()))
+            .map(String::from)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "remote access requires a project. Use --project, set NRZ_PROJECT_ID or add projectId to onreza.jsonc"
+                )
+            })?;
+        let client = ApiClient::new(token, project, self.env)?;
+        eprintln!(
+            "  {} remote: {} ({})",
+            console::style("~").cyan().bold(),
+            client.project(),
+            client.env().as_str(),
+        );
+        Ok(client)
+    }
+}
@@ -0,0 +1,24 @@
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+pub struct TriggerArgs {
+    /// App to use in a monorepo workspace (package name or path)
+    #[arg(long, global = true)]
+    pub app: Option<String>,
+
+    #[command(subcommand)]
+    pub command: TriggerCommand,
+}
+
+#[derive(Subcommand)]
+pub enum TriggerCommand {
+    /// Run the scheduled handler as a cron trigger would, through the running `nrz dev`
+    Cron {
+        /// Cron expression of the trigger, e.g. "*/5 * * * *"
+        expression: String,
+
+        /// Scheduled time of the event, RFC 3339 (default: the current minute)
+        #[arg(long, value_name = "TIME")]
+        at: Option<String>,
+    },
+}
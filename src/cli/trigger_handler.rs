@@ -0,0 +1,67 @@
+//! CLI handler for `nrz trigger` subcommands.
+
+use std::path::Path;
+use std::time::Instant;
+
+use crate::config::Config;
+use crate::dev::cron::{Cron, format_time, parse_time};
+use crate::dev::scheduled::{self, ScheduledEvent};
+use crate::dev::workspace::resolve_project_dir;
+use nrz::emulator::data_dir;
+use nrz::emulator::runtime::RuntimeInfo;
+
+use super::trigger::{TriggerArgs, TriggerCommand};
+
+pub async fn run(args: TriggerArgs) -> anyhow::Result<()> {
+    let project_dir = resolve_project_dir(&Path::new(".").canonicalize()?, args.app.as_deref())?;
+
+    match args.command {
+        TriggerCommand::Cron { expression, at } => {
+            cron(&project_dir, &expression, at.as_deref()).await
+        }
+    }
+}
+
+/// Send a scheduled event to the app of the running `nrz dev` and wait
+/// for its handler.
+async fn cron(project_dir: &Path, expression: &str, at: Option<&str>) -> anyhow::Result<()> {
+    let cron = Cron::parse(expression)?;
+    let scheduled_time = match at {
+        Some(at) => parse_time(at)?,
+        None => scheduled::now_millis() / 60_000 * 60_000,
+    };
+    let info = RuntimeInfo::read(&data_dir(project_dir)).ok_or_else(|| {
+        anyhow::anyhow!(
+            "no running `nrz dev` found in {} — start it first",
+            project_dir.display()
+        )
+    })?;
+
+    // Any expression can be triggered, but one the app doesn't declare
+    // is likely a typo
+    let triggers = Config::load(project_dir)
+        .and_then(|config| scheduled::triggers(project_dir, &config))
+        .unwrap_or_default();
+    if !triggers.is_empty() && !triggers.iter().any(|t| t.as_str() == cron.as_str()) {
+        eprintln!(
+            "  {} {cron} is not one of the app's cron triggers ({})",
+            console::style("!").yellow().bold(),
+            triggers
+                .iter()
+                .map(Cron::as_str)
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+    }
+
+    let start = Instant::now();
+    let event = ScheduledEvent::new(&cron, scheduled_time);
+    scheduled::deliver(info.port, &info.token, &event).await?;
+    eprintln!(
+        "  {} scheduled handler ran for {cron} at {} ({}ms)",
+        console::style("✓").green().bold(),
+        format_time(scheduled_time),
+        start.elapsed().as_millis(),
+    );
+    Ok(())
+}
@@ -0,0 +1,170 @@
+//! Unit tests for project configuration
+
+use std::path::PathBuf;
+
+use super::{Config, strip_jsonc};
+use crate::dev::package_manager::PackageManager;
+use nrz::emulator::d1_compat::CompatMode;
+
+const FULL: &str = r#"{
+  "$schema": "https://onreza.com/schema/onreza.json",
+  // Project on the platform
+  "projectId": "prj_main",
+  "dev": {
+    "command": "astro dev --host",
+    "port": 3000,
+    "emulatorPort": 3100,
+    "packageManager": "yarn-berry",
+    "d1Compat": "strict",
+    "scheduledEntry": "src/cron.js",
+    "geo": { "country": "DE", "city": "Berlin" },
+  },
+  "build": { "outputDir": "out" },
+  "bindings": {
+    "kv": { "enabled": false },
+    "db": { "migrationsDir": "db/migrations" }, /* trailing comma */
+  },
+  "triggers": { "crons": ["*/5 * * * *", "0 9 * * MON"] },
+  "env": { "API_URL": "https://api.example.com", "FLAG": "on" },
+  "environments": {
+    "production": {
+      "projectId": "prj_prod",
+      "env": { "API_URL": "https://prod.example.com" }
+    },
+    "development": { "env": { "DEBUG": "1" } }
+  }
+}"#;
+
+#[test]
+fn parse_full_config() {
+    let config = Config::parse(FULL).unwrap();
+    assert_eq!(config.project_id.as_deref(), Some("prj_main"));
+    assert_eq!(config.dev.command.as_deref(), Some("astro dev --host"));
+    assert_eq!(config.dev.port, Some(3000));
+    assert_eq!(config.dev.geo.country.as_deref(), Some("DE"));
+    assert_eq!(config.dev.geo.region, None);
+    assert_eq!(config.dev.emulator_port, Some(3100));
+    assert_eq!(config.dev.package_manager, Some(PackageManager::YarnBerry));
+    assert_eq!(config.dev.d1_compat, Some(CompatMode::Strict));
+    assert_eq!(
+        config.dev.scheduled_entry,
+        Some(PathBuf::from("src/cron.js"))
+    );
+    assert_eq!(config.build.output_dir, Some(PathBuf::from("out")));
+    assert!(!config.bindings.kv.enabled);
+    assert!(config.bindings.db.enabled);
+    assert_eq!(
+        config.bindings.db.migrations_dir(),
+        PathBuf::from("db/migrations")
+    );
+    assert_eq!(config.triggers.crons, ["*/5 * * * *", "0 9 * * MON"]);
+}
+
+#[test]
+fn empty_config_uses_defaults() {
+    let config = Config::parse("{}").unwrap();
+    assert!(config.project_id.is_none());
+    assert!(config.bindings.kv.enabled);
+    assert!(config.bindings.db.enabled);
+    assert_eq!(
+        config.bindings.db.migrations_dir(),
+        PathBuf::from("migrations")
+    );
+}
+
+#[test]
+fn environment_overrides() {
+    let config = Config::parse(FULL).unwrap();
+    assert_eq!(config.project_id("production"), Some("prj_prod"));
+    assert_eq!(config.project_id("preview"), Some("prj_main"));
+
+    let prod = config.env("production");
+    assert_eq!(prod["API_URL"], "https://prod.example.com");
+    assert_eq!(prod["FLAG"], "on");
+
+    let dev = config.env("development");
+    assert_eq!(dev["API_URL"], "https://api.example.com");
+    assert_eq!(dev["DEBUG"], "1");
+    assert!(!config.env("preview").contains_key("DEBUG"));
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+    let err = Config::parse(r#"{ "dev": { "prot": 3000 } }"#).unwrap_err();
+    assert!(err.to_string().contains("unknown field `prot`"), "{err}");
+
+    let err = Config::parse(r#"{ "environments": { "staging": {} } }"#).unwrap_err();
+    assert!(err.to_string().contains("unknown field `staging`"), "{err}");
+}
+
+#[test]
+fn invalid_values_are_rejected() {
+    for (json, message) in [
+        (r#"{ "dev": { "port": 70000 } }"#, "invalid value"),
+        (r#"{ "dev": { "port": 0 } }"#, "between 1 and 65535"),
+        (
+            r#"{ "dev": { "port": 3000, "emulatorPort": 3000 } }"#,
+            "must differ",
+        ),
+        (
+            r#"{ "dev": { "packageManager": "pip" } }"#,
+            "unknown variant",
+        ),
+        (r#"{ "dev": { "d1Compat": "loose" } }"#, "unknown variant"),
+        (r#"{ "projectId": "" }"#, "projectId must not be empty"),
+        (
+            r#"{ "env": { "MY-VAR": "x" } }"#,
+            "invalid variable name 'MY-VAR'",
+        ),
+        (
+            r#"{ "environments": { "preview": { "env": { "1X": "x" } } } }"#,
+            "environments.preview",
+        ),
+        (
+            r#"{ "triggers": { "crons": ["0 25 * * *"] } }"#,
+            "triggers.crons: invalid cron expression '0 25 * * *'",
+        ),
+    ] {
+        let err = Config::parse(json).unwrap_err();
+        assert!(err.to_string().contains(message), "{json}: {err}");
+    }
+}
+
+#[test]
+fn error_positions_match_source() {
+    let err = Config::parse("{\n  // comment\n  \"dev\": 1\n}").unwrap_err();
+    assert!(err.to_string().contains("line 3"), "{err}");
+}
+
+#[test]
+fn strip_jsonc_keeps_strings() {
+    let input = r#"{"url": "https://x.dev/*not a comment*/", "q": "a\"//b", "list": [1, 2,]}"#;
+    let value: serde_json::Value = serde_json::from_str(&strip_jsonc(input)).unwrap();
+    assert_eq!(value["url"], "https://x.dev/*not a comment*/");
+    assert_eq!(value["q"], "a\"//b");
+    assert_eq!(value["list"], serde_json::json!([1, 2]));
+}
+
+#[test]
+fn load_missing_file_is_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config::load(dir.path()).unwrap();
+    assert!(config.dev.command.is_none());
+}
+
+#[test]
+fn load_prefers_jsonc() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("onreza.json"), r#"{"projectId":"json"}"#).unwrap();
+    std::fs::write(dir.path().join("onreza.jsonc"), r#"{"projectId":"jsonc"}"#).unwrap();
+    let config = Config::load(dir.path()).unwrap();
+    assert_eq!(config.project_id.as_deref(), Some("jsonc"));
+}
+
+#[test]
+fn load_reports_file_name() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("onreza.jsonc"), "{ nope }").unwrap();
+    let err = Config::load(dir.path()).unwrap_err();
+    assert!(format!("{err:#}").contains("onreza.jsonc"));
+}
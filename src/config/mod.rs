@@ -0,0 +1,317 @@
+//! Project configuration (`onreza.jsonc`).
+//!
+//! Every setting is optional. Commands resolve each value as CLI flag →
+//! environment variable → config file → built-in default.
+
+#[cfg(test)]
+mod config_tests;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::dev::cron::Cron;
+use crate::dev::js_runtime::JsRuntime;
+use crate::dev::package_manager::PackageManager;
+use crate::dev::proxy::Geo;
+use nrz::emulator::d1_compat::CompatMode;
+
+/// Config file names, in lookup order.
+pub const CONFIG_FILES: &[&str] = &["onreza.jsonc", "onreza.json"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Config {
+    /// JSON schema reference for editors; accepted and ignored
+    #[serde(rename = "$schema", default)]
+    _schema: serde::de::IgnoredAny,
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub dev: DevConfig,
+    #[serde(default)]
+    pub build: BuildConfig,
+    #[serde(default)]
+    pub bindings: Bindings,
+    #[serde(default)]
+    pub triggers: Triggers,
+    /// Variables available to the app in every environment
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub environments: Environments,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DevConfig {
+    pub command: Option<String>,
+    pub port: Option<u16>,
+    pub emulator_port: Option<u16>,
+    pub package_manager: Option<PackageManager>,
+    pub runtime: Option<JsRuntime>,
+    pub d1_compat: Option<CompatMode>,
+    /// Module exporting the app's `scheduled` handler, relative to the
+    /// project, for dev servers whose main module does not export it
+    pub scheduled_entry: Option<PathBuf>,
+    /// Geo reported in `ONREZA.context` unless request headers override it
+    #[serde(default)]
+    pub geo: Geo,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct BuildConfig {
+    /// Build output directory, overriding the framework default
+    pub output_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bindings {
+    #[serde(default)]
+    pub kv: KvBinding,
+    #[serde(default)]
+    pub db: DbBinding,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct KvBinding {
+    /// Expose `ONREZA.kv` to the app
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DbBinding {
+    /// Expose `ONREZA.db` to the app
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    pub migrations_dir: Option<PathBuf>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Default for KvBinding {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl Default for DbBinding {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            migrations_dir: None,
+        }
+    }
+}
+
+impl DbBinding {
+    pub fn migrations_dir(&self) -> PathBuf {
+        self.migrations_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("migrations"))
+    }
+}
+
+/// Cron triggers for the app's scheduled handler, fired by `nrz dev`.
+/// Take precedence over those in the build manifest.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Triggers {
+    /// Cron expressions, evaluated in UTC
+    #[serde(default)]
+    pub crons: Vec<String>,
+}
+
+/// Per-environment overrides. `development` applies to `nrz dev`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Environments {
+    pub development: Option<EnvironmentConfig>,
+    pub preview: Option<EnvironmentConfig>,
+    pub production: Option<EnvironmentConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct EnvironmentConfig {
+    pub project_id: Option<String>,
+    /// Merged over the top-level `env`
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// Load the config file from `project_dir`, or defaults if there is none.
+    pub fn load(project_dir: &Path) -> anyhow::Result<Self> {
+        let Some(path) = find_config(project_dir) else {
+            return Ok(Self::default());
+        };
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("invalid config: {}", path.display()))
+    }
+
+    /// Parse and validate JSONC config text.
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let config: Self = serde_json::from_str(&strip_jsonc(content))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let project_ids = std::iter::once(&self.project_id)
+            .chain(self.environments().map(|(_, e)| &e.project_id));
+        if project_ids.flatten().any(|id| id.trim().is_empty()) {
+            anyhow::bail!("projectId must not be empty");
+        }
+        if self.dev.port == Some(0) || self.dev.emulator_port == Some(0) {
+            anyhow::bail!("dev.port and dev.emulatorPort must be between 1 and 65535");
+        }
+        if self.dev.port.is_some() && self.dev.port == self.dev.emulator_port {
+            anyhow::bail!("dev.port and dev.emulatorPort must differ");
+        }
+        if self
+            .dev
+            .command
+            .as_deref()
+            .is_some_and(|c| c.trim().is_empty())
+        {
+            anyhow::bail!("dev.command must not be empty");
+        }
+        for (name, env) in
+            std::iter::once(("env", &self.env)).chain(self.environments().map(|(n, e)| (n, &e.env)))
+        {
+            if let Some(key) = env.keys().find(|k| !is_env_name(k)) {
+                anyhow::bail!("{name}: invalid variable name '{key}'");
+            }
+        }
+        for cron in &self.triggers.crons {
+            Cron::parse(cron).map_err(|e| anyhow::anyhow!("triggers.crons: {e}"))?;
+        }
+        Ok(())
+    }
+
+    fn environments(&self) -> impl Iterator<Item = (&'static str, &EnvironmentConfig)> {
+        let e = &self.environments;
+        [
+            ("environments.development", &e.development),
+            ("environments.preview", &e.preview),
+            ("environments.production", &e.production),
+        ]
+        .into_iter()
+        .filter_map(|(name, env)| env.as_ref().map(|env| (name, env)))
+    }
+
+    fn environment(&self, name: &str) -> Option<&EnvironmentConfig> {
+        match name {
+            "development" => self.environments.development.as_ref(),
+            "preview" => self.environments.preview.as_ref(),
+            "production" => self.environments.production.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Project id for an environment, falling back to the top-level one.
+    pub fn project_id(&self, environment: &str) -> Option<&str> {
+        self.environment(environment)
+            .and_then(|e| e.project_id.as_deref())
+            .or(self.project_id.as_deref())
+    }
+
+    /// Variables for an environment: top-level `env` with its overrides.
+    pub fn env(&self, environment: &str) -> BTreeMap<String, String> {
+        let mut vars = self.env.clone();
+        if let Some(overrides) = self.environment(environment) {
+            vars.extend(overrides.env.clone());
+        }
+        vars
+    }
+}
+
+/// Path of the config file in `project_dir`, if any.
+pub fn find_config(project_dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILES
+        .iter()
+        .map(|name| project_dir.join(name))
+        .find(|p| p.is_file())
+}
+
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Turn JSONC into JSON: drop `//` and `/* */` comments and trailing commas.
+///
+/// Comments are replaced by whitespace so serde's line and column numbers
+/// still point at the original text.
+pub fn strip_jsonc(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    // Index in `out` of a comma that may turn out to be trailing
+    let mut pending_comma: Option<usize> = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                pending_comma = None;
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                out.push(' ');
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                    out.push(' ');
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                out.push_str("  ");
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    out.push(if c == '\n' { '\n' } else { ' ' });
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            ',' => {
+                pending_comma = Some(out.len());
+                out.push(c);
+            }
+            '}' | ']' => {
+                if let Some(i) = pending_comma.take() {
+                    out.replace_range(i..i + 1, " ");
+                }
+                out.push(c);
+            }
+            c if c.is_whitespace() => out.push(c),
+            c => {
+                pending_comma = None;
+                out.push(c);
+            }
+        }
+    }
+    out
+}
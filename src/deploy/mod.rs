@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::build;
 use crate::cli::{BuildArgs, DeployArgs};
+use crate::config::Config;
+use crate::dev::workspace::resolve_project_dir;
 
 // --- API response types ---
 
@@ -26,16 +28,17 @@ struct UploadUrls {
 /// Deploy build output to ONREZA platform.
 ///
 /// 1. Run `nrz build` validation
-/// 2. Authenticate (token from --token or NRZ_TOKEN)
+/// 2. Authenticate (token from --token or NRZ_TOKEN) and resolve the project
 /// 3. Create deployment via API
 /// 4. Upload server bundle to S3
 /// 5. Upload static assets to S3
 /// 6. Upload prerendered pages to S3
 /// 7. Finalize deployment (activate routes)
 pub async fn run(args: DeployArgs) -> anyhow::Result<()> {
-    let project_dir = std::path::Path::new(&args.dir)
+    let dir = std::path::Path::new(&args.dir)
         .canonicalize()
         .with_context(|| format!("project directory not found: {}", args.dir))?;
+    let project_dir = resolve_project_dir(&dir, args.app.as_deref())?;
 
     let token = args.token.as_deref().ok_or_else(|| {
         anyhow::anyhow!("deploy token required. Use --token or set NRZ_TOKEN env var")
@@ -48,6 +51,7 @@ pub async fn run(args: DeployArgs) -> anyhow::Result<()> {
     );
     build::run(BuildArgs {
         dir: project_dir.to_string_lossy().into_owned(),
+        app: None,
         skip_validation: false,
     })
     .await?;
@@ -57,7 +61,17 @@ pub async fn run(args: DeployArgs) -> anyhow::Result<()> {
         "  {} creating deployment...",
         console::style("~").cyan().bold(),
     );
-    let _env = if args.prod { "production" } else { "preview" };
+    let env = if args.prod { "production" } else { "preview" };
+    let config = Config::load(&project_dir)?;
+    let _project = args
+        .project
+        .as_deref()
+        .or(config.project_id(env))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "no project to deploy to. Use --project, set NRZ_PROJECT_ID or add projectId to onreza.jsonc"
+            )
+        })?;
     let _token = token;
 
     // TODO: POST /api/projects/:id/deployments
@@ -0,0 +1,300 @@
+//! Cron expressions and UTC calendar arithmetic for scheduled handlers.
+//!
+//! Expressions have the five standard fields — minute, hour, day of
+//! month, month, day of week — and are evaluated in UTC, as on the
+//! platform. Fields take `*`, numbers, ranges (`1-5`), steps (`*/15`,
+//! `10-50/10`), lists (`1,15`) and month or weekday names (`JAN`, `MON`);
+//! Sunday is 0 or 7. When both day fields are restricted, a day matching
+//! either one matches, as in Vixie cron.
+
+use std::fmt;
+
+/// Longest gap between two matches of a valid expression, in days:
+/// February 29 can be eight years apart (2096 to 2104).
+const SEARCH_DAYS: i64 = 366 * 8 + 1;
+
+const MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Parsed cron expression, with one bit per allowed value of each field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    expr: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Day of month or day of week given as `*` (or `*/n`): a day must
+    /// then match both day fields instead of either
+    any_day: bool,
+}
+
+impl Cron {
+    pub fn parse(expr: &str) -> anyhow::Result<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            anyhow::bail!(
+                "invalid cron expression '{expr}': expected 5 fields \
+                 (minute hour day-of-month month day-of-week), got {}",
+                fields.len()
+            );
+        };
+        let invalid = |e: anyhow::Error| anyhow::anyhow!("invalid cron expression '{expr}': {e}");
+        let mut weekdays = field(weekday, "day of week", 0, 7, WEEKDAYS).map_err(invalid)?;
+        // 7 is Sunday too
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        let cron = Self {
+            expr: fields.join(" "),
+            minutes: field(minute, "minute", 0, 59, &[]).map_err(invalid)?,
+            hours: field(hour, "hour", 0, 23, &[]).map_err(invalid)?,
+            days: field(day, "day of month", 1, 31, &[]).map_err(invalid)?,
+            months: field(month, "month", 1, 12, MONTHS).map_err(invalid)?,
+            weekdays,
+            any_day: day.starts_with('*') || weekday.starts_with('*'),
+        };
+        if cron.next_after(0).is_none() {
+            anyhow::bail!("cron expression '{expr}' never matches a date");
+        }
+        Ok(cron)
+    }
+
+    /// The expression, with fields separated by single spaces.
+    pub fn as_str(&self) -> &str {
+        &self.expr
+    }
+
+    /// Whether the expression matches the minute of `secs` (Unix time).
+    pub fn matches(&self, secs: i64) -> bool {
+        let time = UtcTime::from_unix(secs);
+        bit(self.minutes, time.minute) && bit(self.hours, time.hour) && self.matches_day(&time)
+    }
+
+    /// First matching minute strictly after `secs`, as Unix time.
+    pub fn next_after(&self, secs: i64) -> Option<i64> {
+        let start = secs.div_euclid(60) * 60 + 60;
+        let first_day = start.div_euclid(86_400);
+        (first_day..first_day + SEARCH_DAYS).find_map(|day| {
+            let midnight = day * 86_400;
+            if !self.matches_day(&UtcTime::from_unix(midnight)) {
+                return None;
+            }
+            (0..24)
+                .filter(|&h| bit(self.hours, h))
+                .flat_map(|h| (0..60).map(move |m| (h, m)))
+                .filter(|&(_, m)| bit(self.minutes, m))
+                .map(|(h, m)| midnight + i64::from(h) * 3600 + i64::from(m) * 60)
+                .find(|&t| t >= start)
+        })
+    }
+
+    fn matches_day(&self, time: &UtcTime) -> bool {
+        if !bit(self.months, time.month) {
+            return false;
+        }
+        let day = bit(self.days, time.day);
+        let weekday = bit(self.weekdays, time.weekday);
+        if self.any_day {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+}
+
+impl fmt::Display for Cron {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Bits of the values a field allows, between `min` and `max`. `names`
+/// are accepted for the values from `min` on.
+fn field(spec: &str, name: &str, min: u32, max: u32, names: &[&str]) -> anyhow::Result<u64> {
+    let value = |s: &str| -> anyhow::Result<u32> {
+        let n = match names.iter().position(|n| n.eq_ignore_ascii_case(s)) {
+            Some(i) => min + i as u32,
+            None => s
+                .parse()
+                .map_err(|_| anyhow::anyhow!("invalid {name} '{s}'"))?,
+        };
+        if !(min..=max).contains(&n) {
+            anyhow::bail!("{name} {n} is out of range {min}-{max}");
+        }
+        Ok(n)
+    };
+
+    let mut mask = 0;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, Some(step)),
+                _ => anyhow::bail!("invalid step '{step}' in {name} '{part}'"),
+            },
+            None => (part, None),
+        };
+        let (from, to) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((from, to)) => (value(from)?, value(to)?),
+            // `5/15` runs from 5 to the end of the range
+            None if step.is_some() => (value(range)?, max),
+            None => {
+                let n = value(range)?;
+                (n, n)
+            }
+        };
+        if from > to {
+            anyhow::bail!("{name} range '{range}' runs backwards");
+        }
+        for n in (from..=to).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << n;
+        }
+    }
+    Ok(mask)
+}
+
+/// Calendar fields of a UTC time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtcTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    /// 0 is Sunday
+    pub weekday: u32,
+}
+
+impl UtcTime {
+    pub fn from_unix(secs: i64) -> Self {
+        let days = secs.div_euclid(86_400);
+        let rem = secs.rem_euclid(86_400) as u32;
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            year,
+            month,
+            day,
+            hour: rem / 3600,
+            minute: rem / 60 % 60,
+            second: rem % 60,
+            // 1970-01-01 was a Thursday
+            weekday: (days + 4).rem_euclid(7) as u32,
+        }
+    }
+}
+
+/// Parse an RFC 3339 time such as `2026-01-01T09:00:00Z` into Unix
+/// milliseconds. Seconds are optional, and times without an offset are
+/// UTC.
+pub fn parse_time(input: &str) -> anyhow::Result<i64> {
+    let invalid =
+        || anyhow::anyhow!("invalid time '{input}': expected RFC 3339, e.g. 2026-01-01T09:00:00Z");
+    let (date, time) = input
+        .trim()
+        .split_once(['T', 't', ' '])
+        .ok_or_else(invalid)?;
+
+    let (time, offset_secs) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else if let Some(i) = time.rfind(['+', '-']) {
+        let (hours, minutes) = time[i + 1..].split_once(':').ok_or_else(invalid)?;
+        let hours: i64 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i64 = minutes.parse().map_err(|_| invalid())?;
+        if hours > 23 || minutes > 59 {
+            return Err(invalid());
+        }
+        let sign = if time.as_bytes()[i] == b'-' { -1 } else { 1 };
+        (&time[..i], sign * (hours * 3600 + minutes * 60))
+    } else {
+        (time, 0)
+    };
+
+    let numbers =
+        |s: &str, sep: char| -> Option<Vec<u32>> { s.split(sep).map(|n| n.parse().ok()).collect() };
+    let [year, month, day] = numbers(date, '-').ok_or_else(invalid)?[..] else {
+        return Err(invalid());
+    };
+    let (time, millis) = match time.split_once('.') {
+        Some((time, fraction)) => {
+            let digits: String = fraction.chars().chain("000".chars()).take(3).collect();
+            (time, digits.parse::<i64>().map_err(|_| invalid())?)
+        }
+        None => (time, 0),
+    };
+    let (hour, minute, second) = match numbers(time, ':').ok_or_else(invalid)?[..] {
+        [hour, minute] => (hour, minute, 0),
+        [hour, minute, second] => (hour, minute, second),
+        _ => return Err(invalid()),
+    };
+    let year = i64::from(year);
+    if !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(invalid());
+    }
+    let secs = days_from_civil(year, month, day) * 86_400
+        + i64::from(hour * 3600 + minute * 60 + second)
+        - offset_secs;
+    Ok(secs * 1000 + millis)
+}
+
+/// Format Unix milliseconds as RFC 3339 in UTC.
+pub fn format_time(millis: i64) -> String {
+    let t = UtcTime::from_unix(millis.div_euclid(1000));
+    let date = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        t.year, t.month, t.day, t.hour, t.minute, t.second
+    );
+    match millis.rem_euclid(1000) {
+        0 => format!("{date}Z"),
+        ms => format!("{date}.{ms:03}Z"),
+    }
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Conversions between days since 1970-01-01 and the proleptic Gregorian
+// calendar, after Howard Hinnant's `days_from_civil` and `civil_from_days`.
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
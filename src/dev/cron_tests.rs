@@ -0,0 +1,198 @@
+//! Unit tests for cron expressions and UTC time parsing
+
+use super::cron::{Cron, UtcTime, format_time, parse_time};
+
+fn secs(time: &str) -> i64 {
+    parse_time(time).unwrap() / 1000
+}
+
+fn next(expr: &str, after: &str) -> String {
+    let cron = Cron::parse(expr).unwrap();
+    format_time(cron.next_after(secs(after)).unwrap() * 1000)
+}
+
+#[test]
+fn every_minute_matches_the_next_minute() {
+    assert_eq!(
+        next("* * * * *", "2026-03-01T10:15:30Z"),
+        "2026-03-01T10:16:00Z"
+    );
+    // Strictly after, even on a matching minute
+    assert_eq!(
+        next("* * * * *", "2026-03-01T10:15:00Z"),
+        "2026-03-01T10:16:00Z"
+    );
+}
+
+#[test]
+fn steps_ranges_and_lists() {
+    assert_eq!(
+        next("*/15 * * * *", "2026-03-01T10:16:00Z"),
+        "2026-03-01T10:30:00Z"
+    );
+    assert_eq!(
+        next("10-50/20 9,17 * * *", "2026-03-01T09:31:00Z"),
+        "2026-03-01T09:50:00Z"
+    );
+    assert_eq!(
+        next("10-50/20 9,17 * * *", "2026-03-01T09:51:00Z"),
+        "2026-03-01T17:10:00Z"
+    );
+    assert_eq!(
+        next("5/20 * * * *", "2026-03-01T09:46:00Z"),
+        "2026-03-01T10:05:00Z"
+    );
+}
+
+#[test]
+fn names_and_sunday_as_seven() {
+    // 2026-03-01 is a Sunday
+    assert_eq!(
+        next("0 9 * * MON-FRI", "2026-02-28T12:00:00Z"),
+        "2026-03-02T09:00:00Z"
+    );
+    assert_eq!(
+        next("0 0 * * 7", "2026-03-01T00:00:00Z"),
+        "2026-03-08T00:00:00Z"
+    );
+    assert_eq!(
+        Cron::parse("0 0 * * 0").unwrap().next_after(0),
+        Cron::parse("0 0 * * sun").unwrap().next_after(0)
+    );
+    assert_eq!(
+        next("0 0 1 jan,Jul *", "2026-03-01T00:00:00Z"),
+        "2026-07-01T00:00:00Z"
+    );
+}
+
+#[test]
+fn restricted_day_fields_match_either() {
+    // The 13th, or any Friday
+    assert_eq!(
+        next("0 0 13 * 5", "2026-03-01T00:00:00Z"),
+        "2026-03-06T00:00:00Z"
+    );
+    assert_eq!(
+        next("0 0 13 * 5", "2026-03-10T00:00:00Z"),
+        "2026-03-13T00:00:00Z"
+    );
+    // With the other day field as `*`, only the restricted one counts
+    assert_eq!(
+        next("0 0 13 * *", "2026-03-14T00:00:00Z"),
+        "2026-04-13T00:00:00Z"
+    );
+    // `*/2` counts as `*` there: both must match, an odd day on a Friday
+    assert_eq!(
+        next("0 0 */2 * 5", "2026-03-01T00:00:00Z"),
+        "2026-03-13T00:00:00Z"
+    );
+}
+
+#[test]
+fn leap_days() {
+    assert_eq!(
+        next("0 0 29 2 *", "2026-01-01T00:00:00Z"),
+        "2028-02-29T00:00:00Z"
+    );
+    assert_eq!(
+        next("0 0 29 2 *", "2096-03-01T00:00:00Z"),
+        "2104-02-29T00:00:00Z"
+    );
+}
+
+#[test]
+fn matches_checks_the_minute() {
+    let cron = Cron::parse("30 2 * * *").unwrap();
+    assert!(cron.matches(secs("2026-05-05T02:30:59Z")));
+    assert!(!cron.matches(secs("2026-05-05T02:31:00Z")));
+    assert!(!cron.matches(secs("2026-05-05T03:30:00Z")));
+}
+
+#[test]
+fn expression_is_normalized() {
+    let cron = Cron::parse("  0  12 * *   *\t").unwrap();
+    assert_eq!(cron.as_str(), "0 12 * * *");
+    assert_eq!(cron.to_string(), "0 12 * * *");
+}
+
+#[test]
+fn invalid_expressions_are_rejected() {
+    for (expr, message) in [
+        ("* * * *", "expected 5 fields"),
+        ("* * * * * *", "got 6"),
+        ("60 * * * *", "minute 60 is out of range 0-59"),
+        ("* 24 * * *", "hour 24"),
+        ("* * 0 * *", "day of month 0"),
+        ("* * * 13 *", "month 13"),
+        ("* * * * 8", "day of week 8"),
+        ("*/0 * * * *", "invalid step '0'"),
+        ("30-10 * * * *", "runs backwards"),
+        ("* * * foo *", "invalid month 'foo'"),
+        ("0 0 31 2 *", "never matches"),
+        ("0 0 30 feb *", "never matches"),
+    ] {
+        let err = Cron::parse(expr).unwrap_err().to_string();
+        assert!(err.contains(message), "{expr}: {err}");
+    }
+}
+
+#[test]
+fn parse_time_accepts_rfc3339() {
+    assert_eq!(parse_time("1970-01-01T00:00:00Z").unwrap(), 0);
+    assert_eq!(
+        parse_time("2026-01-01T00:00:00Z").unwrap(),
+        1_767_225_600_000
+    );
+    assert_eq!(
+        parse_time("2026-01-01T02:00:00+02:00").unwrap(),
+        1_767_225_600_000
+    );
+    assert_eq!(
+        parse_time("2025-12-31T19:30-04:30").unwrap(),
+        1_767_225_600_000
+    );
+    // No offset means UTC; seconds and fractions are optional
+    assert_eq!(parse_time("2026-01-01 00:00").unwrap(), 1_767_225_600_000);
+    assert_eq!(
+        parse_time("2026-01-01T00:00:00.25Z").unwrap(),
+        1_767_225_600_250
+    );
+}
+
+#[test]
+fn parse_time_rejects_invalid_dates() {
+    for input in [
+        "2026-01-01",
+        "tomorrow",
+        "2026-02-29T00:00:00Z",
+        "2026-13-01T00:00:00Z",
+        "2026-01-01T24:00:00Z",
+        "2026-01-01T00:00:00+25:00",
+    ] {
+        let err = parse_time(input).unwrap_err().to_string();
+        assert!(err.contains("expected RFC 3339"), "{input}: {err}");
+    }
+}
+
+#[test]
+fn format_time_round_trips() {
+    for time in [
+        "1970-01-01T00:00:00Z",
+        "2000-02-29T23:59:59Z",
+        "2026-10-18T08:05:00.123Z",
+        "1969-12-31T23:59:00Z",
+    ] {
+        assert_eq!(format_time(parse_time(time).unwrap()), time);
+    }
+}
+
+#[test]
+fn calendar_fields() {
+    let t = UtcTime::from_unix(secs("2024-02-29T13:45:10Z"));
+    assert_eq!(
+        (
+            t.year, t.month, t.day, t.hour, t.minute, t.second, t.weekday
+        ),
+        (2024, 2, 29, 13, 45, 10, 4)
+    );
+}
@@ -2,22 +2,121 @@ use std::path::Path;
 
 use anyhow::Context;
 
+use super::workspace::root_package_json;
+
 #[derive(Debug, Clone)]
 pub struct Framework {
     pub name: FrameworkName,
     pub dev_command: String,
+    /// Port the dev server listens on when none is given
+    pub default_port: u16,
+    /// Build output directory, relative to the project
+    pub output_dir: &'static str,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FrameworkName {
     Astro,
     Nuxt,
     Nitro,
     SvelteKit,
+    ReactRouter,
+    Remix,
+    SolidStart,
+    QwikCity,
+    Analog,
+    TanStackStart,
+    Hono,
+    ViteSsr,
 }
 
+/// Detection rules in priority order: meta-frameworks come before the
+/// toolchains they are built on (Nitro, Vinxi, Vite), which a project
+/// also has in its dependencies.
+///
+/// (dependencies, framework, dev command, default port, output dir)
+const FRAMEWORKS: &[(&[&str], FrameworkName, &str, u16, &str)] = &[
+    (&["astro"], FrameworkName::Astro, "astro dev", 4321, "dist"),
+    (&["nuxt"], FrameworkName::Nuxt, "nuxt dev", 3000, ".output"),
+    (
+        &["@sveltejs/kit"],
+        FrameworkName::SvelteKit,
+        "vite dev",
+        5173,
+        "build",
+    ),
+    (
+        &["@react-router/dev"],
+        FrameworkName::ReactRouter,
+        "react-router dev",
+        5173,
+        "build",
+    ),
+    (
+        &["@remix-run/dev"],
+        FrameworkName::Remix,
+        "remix vite:dev",
+        5173,
+        "build",
+    ),
+    (
+        &["@solidjs/start"],
+        FrameworkName::SolidStart,
+        "vinxi dev",
+        3000,
+        ".output",
+    ),
+    (
+        &["@builder.io/qwik-city", "@qwik.dev/router"],
+        FrameworkName::QwikCity,
+        "vite --mode ssr",
+        5173,
+        "dist",
+    ),
+    (
+        &["@analogjs/platform"],
+        FrameworkName::Analog,
+        "vite dev",
+        5173,
+        "dist/analog",
+    ),
+    (
+        &["@tanstack/react-start", "@tanstack/solid-start"],
+        FrameworkName::TanStackStart,
+        "vite dev",
+        3000,
+        ".output",
+    ),
+    (
+        &["nitropack", "nitro"],
+        FrameworkName::Nitro,
+        "nitro dev",
+        3000,
+        ".output",
+    ),
+    (
+        &["@hono/vite-dev-server"],
+        FrameworkName::Hono,
+        "vite dev",
+        5173,
+        "dist",
+    ),
+    (&["vite"], FrameworkName::ViteSsr, "vite dev", 5173, "dist"),
+];
+
 /// Auto-detect framework from project dependencies.
+///
+/// In a workspace, dependencies hoisted to the root package.json count too.
 pub fn detect_framework(project_dir: &Path) -> anyhow::Result<Framework> {
+    detect_framework_with(project_dir, root_package_json(project_dir).as_ref())
+}
+
+/// Detect the framework from the package's own dependencies and, if given,
+/// those of the workspace root.
+pub fn detect_framework_with(
+    project_dir: &Path,
+    root_pkg: Option<&serde_json::Value>,
+) -> anyhow::Result<Framework> {
     let pkg_path = project_dir.join("package.json");
     let pkg_content =
         std::fs::read_to_string(&pkg_path).context("package.json not found in project dir")?;
@@ -26,42 +125,36 @@ pub fn detect_framework(project_dir: &Path) -> anyhow::Result<Framework> {
         serde_json::from_str(&pkg_content).context("invalid package.json")?;
 
     let has_dep = |name: &str| -> bool {
-        pkg.get("dependencies").and_then(|d| d.get(name)).is_some()
-            || pkg
-                .get("devDependencies")
-                .and_then(|d| d.get(name))
-                .is_some()
+        std::iter::once(&pkg).chain(root_pkg).any(|pkg| {
+            pkg.get("dependencies").and_then(|d| d.get(name)).is_some()
+                || pkg
+                    .get("devDependencies")
+                    .and_then(|d| d.get(name))
+                    .is_some()
+        })
     };
 
-    if has_dep("astro") {
-        return Ok(Framework {
-            name: FrameworkName::Astro,
-            dev_command: "astro dev".into(),
-        });
-    }
-
-    if has_dep("nuxt") {
-        return Ok(Framework {
-            name: FrameworkName::Nuxt,
-            dev_command: "nuxt dev".into(),
-        });
-    }
-
-    if has_dep("@sveltejs/kit") {
-        return Ok(Framework {
-            name: FrameworkName::SvelteKit,
-            dev_command: "vite dev".into(),
-        });
+    for &(deps, name, dev_command, default_port, output_dir) in FRAMEWORKS {
+        if deps.iter().any(|d| has_dep(d)) {
+            return Ok(Framework {
+                name,
+                dev_command: dev_command.into(),
+                default_port,
+                output_dir,
+            });
+        }
     }
 
-    if has_dep("nitropack") {
-        return Ok(Framework {
-            name: FrameworkName::Nitro,
-            dev_command: "nitro dev".into(),
-        });
+    if has_dep("hono") {
+        anyhow::bail!(
+            "could not detect framework — found hono without @hono/vite-dev-server. \
+             Pass the dev command with --command"
+        );
     }
 
     anyhow::bail!(
-        "could not detect framework — expected astro, nuxt, @sveltejs/kit, or nitropack in dependencies"
+        "could not detect framework — expected astro, nuxt, @sveltejs/kit, @react-router/dev, \
+         @remix-run/dev, @solidjs/start, @builder.io/qwik-city, @analogjs/platform, \
+         @tanstack/react-start, nitropack or vite in dependencies"
     );
 }
@@ -88,3 +88,97 @@ fn detect_priority_astro_over_nitro() {
     let fw = detect_framework(dir.path()).unwrap();
     assert!(matches!(fw.name, FrameworkName::Astro));
 }
+
+#[test]
+fn detect_additional_frameworks() {
+    let cases = [
+        (
+            "@react-router/dev",
+            FrameworkName::ReactRouter,
+            "react-router dev",
+            5173,
+            "build",
+        ),
+        (
+            "@remix-run/dev",
+            FrameworkName::Remix,
+            "remix vite:dev",
+            5173,
+            "build",
+        ),
+        (
+            "@solidjs/start",
+            FrameworkName::SolidStart,
+            "vinxi dev",
+            3000,
+            ".output",
+        ),
+        (
+            "@builder.io/qwik-city",
+            FrameworkName::QwikCity,
+            "vite --mode ssr",
+            5173,
+            "dist",
+        ),
+        (
+            "@analogjs/platform",
+            FrameworkName::Analog,
+            "vite dev",
+            5173,
+            "dist/analog",
+        ),
+        (
+            "@tanstack/react-start",
+            FrameworkName::TanStackStart,
+            "vite dev",
+            3000,
+            ".output",
+        ),
+        (
+            "@hono/vite-dev-server",
+            FrameworkName::Hono,
+            "vite dev",
+            5173,
+            "dist",
+        ),
+        ("vite", FrameworkName::ViteSsr, "vite dev", 5173, "dist"),
+    ];
+    for (dep, name, command, port, output) in cases {
+        let dir = tempfile::tempdir().unwrap();
+        write_pkg(
+            dir.path(),
+            &format!(r#"{{"devDependencies":{{"{dep}":"*"}}}}"#),
+        );
+        let fw = detect_framework(dir.path()).unwrap();
+        assert_eq!(fw.name, name, "{dep}");
+        assert_eq!(fw.dev_command, command, "{dep}");
+        assert_eq!(fw.default_port, port, "{dep}");
+        assert_eq!(fw.output_dir, output, "{dep}");
+    }
+}
+
+#[test]
+fn detect_meta_framework_over_toolchain() {
+    let dir = tempfile::tempdir().unwrap();
+    write_pkg(
+        dir.path(),
+        r#"{"dependencies":{"@solidjs/start":"^1.0","vinxi":"^0.5","nitropack":"^2.0","vite":"^6.0"}}"#,
+    );
+    let fw = detect_framework(dir.path()).unwrap();
+    assert_eq!(fw.name, FrameworkName::SolidStart);
+
+    write_pkg(
+        dir.path(),
+        r#"{"dependencies":{"hono":"^4.0"},"devDependencies":{"@hono/vite-dev-server":"^0.19","vite":"^6.0"}}"#,
+    );
+    let fw = detect_framework(dir.path()).unwrap();
+    assert_eq!(fw.name, FrameworkName::Hono);
+}
+
+#[test]
+fn detect_plain_hono_needs_command() {
+    let dir = tempfile::tempdir().unwrap();
+    write_pkg(dir.path(), r#"{"dependencies":{"hono":"^4.0"}}"#);
+    let err = detect_framework(dir.path()).unwrap_err();
+    assert!(err.to_string().contains("--command"));
+}
@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+
+/// Secrets file for local development. Never committed; its values are
+/// fully masked in output.
+pub const SECRETS_FILE: &str = ".dev.vars";
+
+/// A variable loaded for the dev runtime and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVar {
+    pub value: String,
+    /// File name, or `onreza.jsonc` for config variables
+    pub source: String,
+}
+
+/// Env files in increasing precedence for `mode`.
+pub fn env_files(mode: &str) -> Vec<String> {
+    vec![
+        ".env".into(),
+        ".env.local".into(),
+        format!(".env.{mode}"),
+        format!(".env.{mode}.local"),
+        SECRETS_FILE.into(),
+    ]
+}
+
+/// Load the variables exposed as `ONREZA.env`.
+///
+/// Config variables come first, then each file from [`env_files`]
+/// overrides earlier values. The shell environment is not included, so
+/// the app sees the same set of variables as in production.
+pub fn load_env(
+    project_dir: &Path,
+    mode: &str,
+    config_env: &BTreeMap<String, String>,
+) -> anyhow::Result<BTreeMap<String, EnvVar>> {
+    let mut vars: BTreeMap<String, EnvVar> = config_env
+        .iter()
+        .map(|(k, v)| {
+            let var = EnvVar {
+                value: v.clone(),
+                source: "onreza.jsonc".into(),
+            };
+            (k.clone(), var)
+        })
+        .collect();
+
+    for name in env_files(mode) {
+        let path = project_dir.join(&name);
+        if !path.is_file() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let resolved: BTreeMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.clone(), v.value.clone()))
+            .collect();
+        let parsed =
+            parse_dotenv(&content, &resolved).with_context(|| format!("invalid {name}"))?;
+        for (key, value) in parsed {
+            vars.insert(
+                key,
+                EnvVar {
+                    value,
+                    source: name.clone(),
+                },
+            );
+        }
+    }
+    Ok(vars)
+}
+
+/// Parse dotenv syntax.
+///
+/// Supports `export` prefixes, `#` comments, single-quoted literals,
+/// double-quoted values with escapes and newlines, and `${VAR}` expansion
+/// from earlier lines or `defined` (unquoted and double-quoted values).
+pub fn parse_dotenv(
+    content: &str,
+    defined: &BTreeMap<String, String>,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    let mut lines = content.lines().enumerate();
+
+    while let Some((n, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, raw) = line
+            .split_once('=')
+            .with_context(|| format!("line {}: expected KEY=value", n + 1))?;
+        let key = key.trim();
+        if key.is_empty()
+            || key.starts_with(|c: char| c.is_ascii_digit())
+            || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            anyhow::bail!("line {}: invalid variable name '{key}'", n + 1);
+        }
+
+        let lookup = |name: &str| -> String {
+            vars.iter()
+                .rev()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.clone())
+                .or_else(|| defined.get(name).cloned())
+                .unwrap_or_default()
+        };
+
+        let raw = raw.trim_start();
+        let value = if let Some(rest) = raw.strip_prefix('\'') {
+            let end = rest
+                .find('\'')
+                .with_context(|| format!("line {}: unterminated single quote", n + 1))?;
+            rest[..end].to_string()
+        } else if let Some(rest) = raw.strip_prefix('"') {
+            // Double-quoted values may span lines
+            let mut text = rest.to_string();
+            let start = n;
+            let end = loop {
+                if let Some(end) = closing_quote(&text) {
+                    break end;
+                }
+                let (_, next) = lines
+                    .next()
+                    .with_context(|| format!("line {}: unterminated double quote", start + 1))?;
+                text.push('\n');
+                text.push_str(next);
+            };
+            expand(&unescape(&text[..end]), &lookup)
+        } else {
+            let value = match raw.find(" #") {
+                Some(i) => &raw[..i],
+                None => raw,
+            };
+            expand(value.trim_end(), &lookup)
+        };
+        vars.push((key.to_string(), value));
+    }
+    Ok(vars)
+}
+
+/// Byte offset of the first unescaped `"`.
+fn closing_quote(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            // \$ keeps a literal dollar through expansion
+            Some('$') => out.push_str("\\$"),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Replace `${NAME}` references.
+fn expand(value: &str, lookup: &dyn Fn(&str) -> String) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(i) = rest.find(['$', '\\']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if let Some(escaped) = tail.strip_prefix("\\$") {
+            out.push('$');
+            rest = escaped;
+        } else if let Some(name_end) = tail.strip_prefix("${").and_then(|t| t.find('}')) {
+            out.push_str(&lookup(&tail[2..2 + name_end]));
+            rest = &tail[3 + name_end..];
+        } else {
+            out.push_str(&tail[..1]);
+            rest = &tail[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Mask a value for display: a short prefix for regular variables,
+/// nothing at all for secrets.
+pub fn mask(value: &str, secret: bool) -> String {
+    let visible = if secret || value.chars().count() <= 8 {
+        0
+    } else {
+        3
+    };
+    let prefix: String = value.chars().take(visible).collect();
+    format!("{prefix}********")
+}
+
+/// Print the loaded variables with masked values.
+pub fn print_env(vars: &BTreeMap<String, EnvVar>) {
+    if vars.is_empty() {
+        return;
+    }
+    eprintln!(
+        "  {} env: {} variable(s)",
+        console::style("~").cyan().bold(),
+        vars.len(),
+    );
+    for (key, var) in vars {
+        let value = mask(&var.value, var.source == SECRETS_FILE);
+        eprintln!(
+            "    {key}={value} {}",
+            console::style(format!("({})", var.source)).dim()
+        );
+    }
+}
+
+/// Warn when the secrets file exists but git would commit it.
+pub fn check_secrets_ignored(project_dir: &Path) {
+    if !project_dir.join(SECRETS_FILE).is_file() {
+        return;
+    }
+    // Exit code 1 means "not ignored"; anything else (no git, not a
+    // repository) is not our concern
+    let status = std::process::Command::new("git")
+        .args(["check-ignore", "-q", SECRETS_FILE])
+        .current_dir(project_dir)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    if status.is_ok_and(|s| s.code() == Some(1)) {
+        eprintln!(
+            "  {} {SECRETS_FILE} is not in .gitignore — it holds secrets and must not be committed",
+            console::style("!").yellow().bold(),
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Unit tests for env file loading
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::env::{SECRETS_FILE, load_env, mask, parse_dotenv};
+
+fn parse(content: &str) -> Vec<(String, String)> {
+    parse_dotenv(content, &BTreeMap::new()).unwrap()
+}
+
+fn pairs(list: &[(&str, &str)]) -> Vec<(String, String)> {
+    list.iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn write(dir: &Path, name: &str, content: &str) {
+    std::fs::write(dir.join(name), content).unwrap();
+}
+
+#[test]
+fn parse_basic_syntax() {
+    let vars = parse(
+        "# comment\n\
+         A=1\n\
+         export B = two \n\
+         C=value # trailing comment\n\
+         D=url#fragment\n\
+         EMPTY=\n",
+    );
+    assert_eq!(
+        vars,
+        pairs(&[
+            ("A", "1"),
+            ("B", "two"),
+            ("C", "value"),
+            ("D", "url#fragment"),
+            ("EMPTY", "")
+        ])
+    );
+}
+
+#[test]
+fn parse_quotes() {
+    let vars = parse(
+        "SINGLE='raw ${A} \\n # kept'\n\
+         DOUBLE=\"line1\\nline2 \\\"q\\\"\" # comment\n\
+         MULTI=\"first\nsecond\"\n",
+    );
+    assert_eq!(
+        vars,
+        pairs(&[
+            ("SINGLE", "raw ${A} \\n # kept"),
+            ("DOUBLE", "line1\nline2 \"q\""),
+            ("MULTI", "first\nsecond"),
+        ])
+    );
+}
+
+#[test]
+fn parse_expansion() {
+    let defined = BTreeMap::from([("HOST".to_string(), "example.com".to_string())]);
+    let vars = parse_dotenv(
+        "PORT=8080\nURL=https://${HOST}:${PORT}/\nQUOTED=\"${PORT}\"\nLITERAL=\\${PORT}\nMISSING=${NOPE}x\n",
+        &defined,
+    )
+    .unwrap();
+    assert_eq!(vars[1].1, "https://example.com:8080/");
+    assert_eq!(vars[2].1, "8080");
+    assert_eq!(vars[3].1, "${PORT}");
+    assert_eq!(vars[4].1, "x");
+}
+
+#[test]
+fn parse_errors() {
+    for (content, message) in [
+        ("NOEQUALS\n", "line 1: expected KEY=value"),
+        ("A=1\nBAD-NAME=x\n", "line 2: invalid variable name"),
+        ("A='open\n", "unterminated single quote"),
+        (
+            "A=\"open\nstill open\n",
+            "line 1: unterminated double quote",
+        ),
+    ] {
+        let err = parse_dotenv(content, &BTreeMap::new()).unwrap_err();
+        assert!(err.to_string().contains(message), "{content:?}: {err}");
+    }
+}
+
+#[test]
+fn file_precedence() {
+    let dir = tempfile::tempdir().unwrap();
+    write(dir.path(), ".env", "A=env\nB=env\nC=env\nD=env\nE=env\n");
+    write(
+        dir.path(),
+        ".env.local",
+        "B=local\nC=local\nD=local\nE=local\n",
+    );
+    write(dir.path(), ".env.staging", "C=mode\nD=mode\nE=mode\n");
+    write(
+        dir.path(),
+        ".env.staging.local",
+        "D=mode-local\nE=mode-local\n",
+    );
+    write(dir.path(), SECRETS_FILE, "E=secret\n");
+    write(dir.path(), ".env.production", "A=wrong-mode\n");
+
+    let config = BTreeMap::from([
+        ("A".to_string(), "config".to_string()),
+        ("F".to_string(), "config".to_string()),
+    ]);
+    let vars = load_env(dir.path(), "staging", &config).unwrap();
+    let values: Vec<(&str, &str, &str)> = vars
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.value.as_str(), v.source.as_str()))
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            ("A", "env", ".env"),
+            ("B", "local", ".env.local"),
+            ("C", "mode", ".env.staging"),
+            ("D", "mode-local", ".env.staging.local"),
+            ("E", "secret", ".dev.vars"),
+            ("F", "config", "onreza.jsonc"),
+        ]
+    );
+}
+
+#[test]
+fn later_files_expand_earlier_values() {
+    let dir = tempfile::tempdir().unwrap();
+    write(dir.path(), ".env", "HOST=localhost\n");
+    write(dir.path(), ".env.local", "URL=http://${HOST}\n");
+    let vars = load_env(dir.path(), "development", &BTreeMap::new()).unwrap();
+    assert_eq!(vars["URL"].value, "http://localhost");
+}
+
+#[test]
+fn shell_environment_is_not_included() {
+    let dir = tempfile::tempdir().unwrap();
+    let vars = load_env(dir.path(), "development", &BTreeMap::new()).unwrap();
+    assert!(vars.is_empty());
+}
+
+#[test]
+fn masking() {
+    assert_eq!(mask("short", false), "********");
+    assert_eq!(mask("https://api.example.com", false), "htt********");
+    assert_eq!(mask("sk_live_abcdefgh", true), "********");
+}
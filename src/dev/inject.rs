@@ -1,22 +1,229 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::proxy::CONTEXT_HEADER;
+use super::scheduled::SCHEDULED_PATH;
+use crate::config::Bindings;
+use nrz::emulator::protocol::{CAPABILITIES, HEALTH, PROTOCOL_HEADER, PROTOCOL_VERSION, RPC};
+
+/// What goes into the bootstrap script of a session.
+#[derive(Debug, Clone, Default)]
+pub struct BootstrapOptions {
+    /// Data directory holding the local database
+    pub data_dir: PathBuf,
+    /// Emulator port, for TCP
+    pub port: u16,
+    /// Emulator socket, used where the runtime can
+    pub socket: Option<PathBuf>,
+    /// Bindings of the project config; disabled ones are left out
+    pub bindings: Bindings,
+    /// Variables of `ONREZA.env`
+    pub env: BTreeMap<String, String>,
+    /// Module exporting the app's `scheduled` handler (`dev.scheduledEntry`)
+    pub scheduled_entry: Option<PathBuf>,
+    /// Session token of the emulator
+    pub token: String,
+}
 
 /// Generate a JS bootstrap script that sets up `globalThis.ONREZA`
 /// with local emulator backends (KV, DB, Context).
 ///
-/// The generated script is injected before the framework's dev server starts
-/// via Node.js `--require` / `--import` flag.
-pub fn generate_bootstrap(data_dir: &Path, port: u16) -> anyhow::Result<String> {
+/// The generated script is preloaded into the framework's dev server by
+/// the JS runtime (see [`super::js_runtime`]). Bindings disabled in the
+/// project config are left out of `ONREZA`, and `ONREZA.env` holds `env`,
+/// so write the script readable only by the user. `ONREZA.context` follows
+/// the request being handled, as set by the dev proxy, and scheduled events
+/// sent to the dev server run the `scheduled` handler the app exports from
+/// the dev server's main module or else from `scheduled_entry`, falling
+/// back to one passed to `ONREZA.onScheduled()` (see [`super::scheduled`]).
+/// Calls to the emulator carry the session `token` and the protocol
+/// version, and go through the emulator's Unix `socket` where the runtime
+/// can use it, else TCP on `port`. On load the script checks that the emulator speaks that
+/// version and supports the enabled bindings, and fails with instructions
+/// otherwise.
+pub fn generate_bootstrap(options: &BootstrapOptions) -> anyhow::Result<String> {
+    let BootstrapOptions {
+        data_dir,
+        port,
+        socket,
+        bindings,
+        env,
+        scheduled_entry,
+        token,
+    } = options;
     let db_path = data_dir.join("dev.db");
 
     // The bootstrap connects to nrz's emulator HTTP API
     // running on a local port, providing KV/DB/Context operations.
     let script = format!(
         r#"// Auto-generated by nrz dev — do not edit
+import {{ AsyncLocalStorage }} from "node:async_hooks";
+import fs from "node:fs";
+import http from "node:http";
+import {{ pathToFileURL }} from "node:url";
+
 const NRZ_EMULATOR = "http://127.0.0.1:{port}";
+const NRZ_SOCKET = {socket};
+const NRZ_TOKEN = "{token}";
+const NRZ_PROTOCOL = {protocol};
+const NRZ_CAPABILITIES = {capabilities};
 const DB_PATH = {db_path};
+// Module set as dev.scheduledEntry, exporting the app's scheduled handler
+const NRZ_SCHEDULED_ENTRY = {scheduled_entry};
+// Variables for ONREZA.env. They live in this file, readable only by you,
+// rather than in process.env, where every subprocess would inherit the
+// secrets among them.
+const NRZ_VARS = {vars};
+
+// The nrz dev proxy sends each request's context in a header; run the
+// request handler inside it so ONREZA.context is per request. Shared
+// through globalThis in case a runtime loads this script twice.
+const __nrzContext = (globalThis[Symbol.for("nrz.context")] ??= new AsyncLocalStorage());
+const __nrzDefaultContext = Object.freeze({{
+  clientIp: "127.0.0.1",
+  geo: Object.freeze({{ country: "XX", city: "Local", continent: "XX", region: "local" }}),
+  deploymentId: "dev",
+  projectId: "dev",
+  commitSha: "dev",
+  requestId: null,
+}});
+const __nrzEmit = http.Server.prototype.emit;
+if (!__nrzEmit.__nrz) http.Server.prototype.emit = Object.assign(function (event, req, ...rest) {{
+  if (event !== "request") return __nrzEmit.call(this, event, req, ...rest);
+  const header = req.headers?.["{context_header}"];
+  let context;
+  if (header !== undefined) {{
+    delete req.headers["{context_header}"];
+    const i = req.rawHeaders.findIndex((h, i) => i % 2 === 0 && h.toLowerCase() === "{context_header}");
+    if (i !== -1) req.rawHeaders.splice(i, 2);
+    try {{
+      context = Object.freeze(JSON.parse(header));
+    }} catch {{}}
+  }}
+  if (req.url === "{scheduled_path}") {{
+    __nrzScheduled(req, rest[0], context);
+    return true;
+  }}
+  if (context === undefined) return __nrzEmit.call(this, event, req, ...rest);
+  return __nrzContext.run(context, () => __nrzEmit.call(this, event, req, ...rest));
+}}, {{ __nrz: true }});
+
+// Scheduled events from nrz (cron triggers and `nrz trigger cron`) arrive
+// as requests and run the app's scheduled handler. The answer waits for
+// the handler and what it passed to waitUntil.
+const __nrzScheduledHandler = (globalThis[Symbol.for("nrz.scheduled")] ??= {{ handler: null }});
+
+// `scheduled` as a named export, or a method of the default export
+function __nrzScheduledExport(mod) {{
+  const target = typeof mod?.scheduled === "function" ? mod : mod?.default;
+  return typeof target?.scheduled === "function"
+    ? (...args) => target.scheduled(...args)
+    : null;
+}}
+
+// The handler exported by the dev server's main module, which is already
+// loaded, so importing it again only returns its exports; else the one
+// exported by dev.scheduledEntry; else the one passed to ONREZA.onScheduled()
+async function __nrzFindScheduled() {{
+  let main = null;
+  try {{
+    if (globalThis.Deno) main = await import(Deno.mainModule);
+    else if (globalThis.Bun) main = await import(Bun.main);
+    else if (process.mainModule) main = process.mainModule.exports;
+    else if (process.argv[1]) main = await import(pathToFileURL(fs.realpathSync(process.argv[1])).href);
+  }} catch {{}}
+  const handler = __nrzScheduledExport(main)
+    ?? (NRZ_SCHEDULED_ENTRY && __nrzScheduledExport(await import(pathToFileURL(NRZ_SCHEDULED_ENTRY).href)));
+  return handler ?? __nrzScheduledHandler.handler;
+}}
+
+function __nrzScheduled(req, res, context = __nrzDefaultContext) {{
+  const reply = (status, body) => {{
+    res.writeHead(status, {{ "content-type": "application/json" }});
+    res.end(JSON.stringify(body));
+  }};
+  if (req.method !== "POST" || req.headers.authorization !== `Bearer ${{NRZ_TOKEN}}`) {{
+    req.resume();
+    return reply(401, {{ outcome: "unauthorized", error: "[nrz] scheduled events need the token of this nrz dev session" }});
+  }}
+  const chunks = [];
+  req.on("data", (chunk) => chunks.push(chunk));
+  req.on("end", () => __nrzContext.run(context, async () => {{
+    let handler;
+    try {{
+      handler = await __nrzFindScheduled();
+    }} catch (e) {{
+      console.error("[nrz] failed to load dev.scheduledEntry:", e);
+      return reply(500, {{ outcome: "exception", error: `[nrz] failed to load ${{NRZ_SCHEDULED_ENTRY}}: ${{e?.stack ?? e}}` }});
+    }}
+    if (!handler) {{
+      return reply(404, {{
+        outcome: "noHandler",
+        error: "[nrz] no scheduled handler found. Export `scheduled` from the module set as dev.scheduledEntry in onreza.jsonc, or pass it to ONREZA.onScheduled() in a module the dev server has loaded",
+      }});
+    }}
+    const pending = [];
+    try {{
+      const {{ cron, scheduledTime }} = JSON.parse(Buffer.concat(chunks).toString());
+      const event = Object.freeze({{ type: "scheduled", cron, scheduledTime }});
+      await handler(event, globalThis.ONREZA.env, {{ waitUntil: (promise) => void pending.push(promise) }});
+      await Promise.all(pending);
+      reply(200, {{ outcome: "ok" }});
+    }} catch (e) {{
+      console.error("[nrz] scheduled handler failed:", e);
+      reply(500, {{ outcome: "exception", error: `[nrz] scheduled handler threw: ${{e?.stack ?? e}}` }});
+    }}
+  }}));
+}}
+
+// Requests go through the emulator's Unix socket when there is one: Bun's
+// fetch takes the socket path, Node gets a keep-alive agent on it. Deno,
+// and any runtime that cannot reach the socket, uses fetch over TCP.
+const __nrzTcpFetch = (url, options) => fetch(url, options);
+const __nrzSocketFetch = !NRZ_SOCKET || globalThis.Deno ? null
+  : globalThis.Bun ? (url, options) => fetch(url, {{ ...options, unix: NRZ_SOCKET }})
+  : __nrzNodeSocketFetch;
+let __nrzTransport = __nrzSocketFetch ?? __nrzTcpFetch;
+let __nrzAgent;
+
+function __nrzNodeSocketFetch(url, options) {{
+  __nrzAgent ??= new http.Agent({{ keepAlive: true }});
+  const {{ host, pathname, search }} = new URL(url);
+  return new Promise((resolve, reject) => {{
+    const req = http.request({{
+      socketPath: NRZ_SOCKET,
+      agent: __nrzAgent,
+      method: options.method ?? "GET",
+      path: pathname + search,
+      // The emulator checks Host as for TCP
+      headers: {{ ...options.headers, host }},
+      signal: options.signal,
+    }}, (res) => {{
+      const chunks = [];
+      res.on("data", (chunk) => chunks.push(chunk));
+      res.on("error", reject);
+      res.on("end", () => {{
+        const body = Buffer.concat(chunks).toString();
+        resolve({{
+          ok: res.statusCode >= 200 && res.statusCode < 300,
+          status: res.statusCode,
+          text: async () => body,
+          json: async () => JSON.parse(body),
+        }});
+      }});
+    }});
+    req.on("error", reject);
+    req.end(options.body);
+  }});
+}}
 
 async function __nrzFetch(url, options, operation) {{
-  const res = await fetch(url, options).catch(e => {{
+  options.headers = {{
+    ...options.headers,
+    authorization: `Bearer ${{NRZ_TOKEN}}`,
+    "{protocol_header}": String(NRZ_PROTOCOL),
+  }};
+  const res = await __nrzTransport(url, options).catch(e => {{
     throw new Error(`[nrz] ${{operation}} failed: is nrz dev running? (${{e.message}})`);
   }});
   if (!res.ok) {{
@@ -26,27 +233,101 @@ async function __nrzFetch(url, options, operation) {{
   return res;
 }}
 
+// Calls made in the same tick go out together in one request to the
+// batched endpoint, and run there in order. Connections to the emulator
+// are kept alive between requests.
+let __nrzPending = [];
+let __nrzNextId = 1;
+
+function __nrzCall(method, params, operation = method) {{
+  return new Promise((resolve, reject) => {{
+    if (__nrzPending.length === 0) queueMicrotask(__nrzFlush);
+    __nrzPending.push({{ id: __nrzNextId++, method, params, operation, resolve, reject }});
+  }});
+}}
+
+async function __nrzFlush() {{
+  const calls = __nrzPending;
+  __nrzPending = [];
+  let responses;
+  try {{
+    const res = await __nrzFetch(`${{NRZ_EMULATOR}}{rpc}`, {{
+      method: "POST",
+      headers: {{ "content-type": "application/json" }},
+      body: JSON.stringify(calls.map(({{ id, method, params }}) => ({{ id, method, params }}))),
+    }}, calls.length === 1 ? calls[0].operation : `${{calls.length}} batched calls`);
+    responses = new Map((await res.json()).map((response) => [response.id, response]));
+  }} catch (e) {{
+    for (const call of calls) call.reject(e);
+    return;
+  }}
+  for (const call of calls) {{
+    const response = responses.get(call.id);
+    if (!response) {{
+      call.reject(new Error(`[nrz] ${{call.operation}}: no response from the emulator`));
+    }} else if (response.error) {{
+      const {{ code, message }} = response.error;
+      call.reject(new Error(`[nrz] ${{call.operation}} returned ${{code}}: ${{message}}`));
+    }} else {{
+      call.resolve(response.result ?? null);
+    }}
+  }}
+}}
+
+// Handshake: fail at startup rather than on the first call when this
+// script was generated for another emulator session or nrz version
+await (async () => {{
+  const restart = "Restart the dev command so it loads the bootstrap written by the running emulator.";
+  const health = (transport) => transport(`${{NRZ_EMULATOR}}{health}`, {{
+    headers: {{ authorization: `Bearer ${{NRZ_TOKEN}}` }},
+    signal: AbortSignal.timeout(10000),
+  }}).catch(() => null);
+  let res = await health(__nrzTransport);
+  if (!res && __nrzTransport !== __nrzTcpFetch) {{
+    // Socket gone or unusable here; TCP still serves the same API
+    __nrzTransport = __nrzTcpFetch;
+    res = await health(__nrzTransport);
+  }}
+  if (!res) {{
+    console.warn(`[nrz] emulator not reachable at ${{NRZ_EMULATOR}}; ONREZA.kv and ONREZA.db calls will fail until it runs`);
+    return;
+  }}
+  if (res.status === 401 || res.status === 403) {{
+    throw new Error(`[nrz] the emulator at ${{NRZ_EMULATOR}} rejected this bootstrap, which belongs to another nrz session. ${{restart}}`);
+  }}
+  if (!res.ok) {{
+    throw new Error(`[nrz] emulator health check returned ${{res.status}}: ${{await res.text()}}`);
+  }}
+  const info = await res.json();
+  const protocol = info.protocol ?? 0;
+  if (protocol !== NRZ_PROTOCOL) {{
+    const server = protocol ? `nrz ${{info.version}} (protocol v${{protocol}})` : "an nrz release without protocol versioning";
+    throw new Error(`[nrz] the emulator at ${{NRZ_EMULATOR}} runs ${{server}}, but this bootstrap was generated by nrz {version} for protocol v${{NRZ_PROTOCOL}}. ${{restart}}`);
+  }}
+  const missing = NRZ_CAPABILITIES.filter(c => !info.capabilities?.includes(c));
+  if (missing.length) {{
+    throw new Error(`[nrz] the emulator (nrz ${{info.version}}) does not support ${{missing.join(", ")}}; upgrade nrz`);
+  }}
+}})();
+
 globalThis.ONREZA = {{
-  env: new Map(Object.entries(process.env)),
-  context: {{
-    clientIp: "127.0.0.1",
-    geo: {{ country: "XX", city: "Local", continent: "XX", region: "local" }},
-    deploymentId: "dev",
-    projectId: "dev",
-    commitSha: "dev",
-    requestId: null,
+  // Only variables nrz loaded (config, .env files, .dev.vars), as in production
+  env: new Map(Object.entries(NRZ_VARS)),
+  // Context of the current request; placeholders outside of requests
+  get context() {{
+    return __nrzContext.getStore() ?? __nrzDefaultContext;
+  }},
+  // Handler for cron triggers when the app exports none, called as
+  // handler(event, env, ctx); the last one registered wins, so modules
+  // reloaded by HMR replace it
+  onScheduled(handler) {{
+    if (typeof handler !== "function") throw new TypeError("[nrz] ONREZA.onScheduled() expects a function");
+    __nrzScheduledHandler.handler = handler;
   }},
   // KV and DB are proxied to nrz emulator HTTP API
   kv: new Proxy({{}}, {{
     get(_, method) {{
-      return async (...args) => {{
-        const res = await __nrzFetch(`${{NRZ_EMULATOR}}/__nrz/kv/${{method}}`, {{
-          method: "POST",
-          headers: {{ "content-type": "application/json" }},
-          body: JSON.stringify({{ args }}),
-        }}, `kv.${{method}}`);
-        return res.json();
-      }};
+      return (...args) => __nrzCall(`kv.${{method}}`, {{ args }});
     }},
   }}),
   db: new Proxy({{}}, {{
@@ -54,77 +335,89 @@ globalThis.ONREZA = {{
       if (method === "prepare") {{
         return (sql) => {{
           let bindings = [];
+          const query = (params, operation) =>
+            __nrzCall("db.query", {{ sql, bindings, ...params }}, operation);
           return {{
             bind(...args) {{ bindings = args; return this; }},
-            async all() {{
-              const res = await __nrzFetch(`${{NRZ_EMULATOR}}/__nrz/db/query`, {{
-                method: "POST",
-                headers: {{ "content-type": "application/json" }},
-                body: JSON.stringify({{ sql, bindings, mode: "all" }}),
-              }}, "db.prepare().all");
-              return res.json();
+            // db.batch() sends statements as JSON
+            toJSON() {{ return {{ sql, bindings }}; }},
+            all() {{
+              return query({{ mode: "all" }}, "db.prepare().all");
             }},
             async first(col) {{
-              const res = await __nrzFetch(`${{NRZ_EMULATOR}}/__nrz/db/query`, {{
-                method: "POST",
-                headers: {{ "content-type": "application/json" }},
-                body: JSON.stringify({{ sql, bindings, mode: "first", column: col }}),
-              }}, "db.prepare().first");
-              return res.json();
+              return (await query({{ mode: "first", column: col }}, "db.prepare().first")).results;
             }},
-            async run() {{
-              const res = await __nrzFetch(`${{NRZ_EMULATOR}}/__nrz/db/query`, {{
-                method: "POST",
-                headers: {{ "content-type": "application/json" }},
-                body: JSON.stringify({{ sql, bindings, mode: "run" }}),
-              }}, "db.prepare().run");
-              return res.json();
+            run() {{
+              return query({{ mode: "run" }}, "db.prepare().run");
             }},
             async raw(opts) {{
-              const res = await __nrzFetch(`${{NRZ_EMULATOR}}/__nrz/db/query`, {{
-                method: "POST",
-                headers: {{ "content-type": "application/json" }},
-                body: JSON.stringify({{ sql, bindings, mode: "raw", columnNames: opts?.columnNames }}),
-              }}, "db.prepare().raw");
-              return res.json();
+              return (await query({{ mode: "raw", columnNames: opts?.columnNames }}, "db.prepare().raw")).results;
             }},
           }};
         }};
       }}
       if (method === "batch") {{
-        return async (stmts) => {{
-          const res = await __nrzFetch(`${{NRZ_EMULATOR}}/__nrz/db/batch`, {{
-            method: "POST",
-            headers: {{ "content-type": "application/json" }},
-            body: JSON.stringify({{ statements: stmts }}),
-          }}, "db.batch");
-          return res.json();
-        }};
+        return (stmts) => __nrzCall("db.batch", {{ statements: stmts }});
       }}
       if (method === "exec") {{
-        return async (sql) => {{
-          const res = await __nrzFetch(`${{NRZ_EMULATOR}}/__nrz/db/exec`, {{
-            method: "POST",
-            headers: {{ "content-type": "application/json" }},
-            body: JSON.stringify({{ sql }}),
-          }}, "db.exec");
-          return res.json();
-        }};
+        return (sql) => __nrzCall("db.exec", {{ sql }});
       }}
     }},
   }}),
 }};
 
+{disabled}
 console.log("[nrz] ONREZA runtime emulator injected");
 "#,
         port = port,
-        db_path = serde_json::to_string(db_path.to_str().ok_or_else(|| {
-            anyhow::anyhow!(
-                "project path contains invalid UTF-8: {}. Move project to a UTF-8 path.",
-                db_path.display()
-            )
-        })?)?,
+        token = token,
+        socket = match socket {
+            Some(socket) => serde_json::to_string(utf8_path(socket)?)?,
+            None => "null".to_string(),
+        },
+        protocol = PROTOCOL_VERSION,
+        protocol_header = PROTOCOL_HEADER,
+        health = HEALTH,
+        rpc = RPC,
+        version = env!("CARGO_PKG_VERSION"),
+        capabilities = serde_json::to_string(&required_capabilities(bindings))?,
+        context_header = CONTEXT_HEADER,
+        scheduled_path = SCHEDULED_PATH,
+        disabled = [("kv", bindings.kv.enabled), ("db", bindings.db.enabled)]
+            .iter()
+            .filter(|(_, enabled)| !enabled)
+            .map(|(name, _)| format!("delete globalThis.ONREZA.{name};\n"))
+            .collect::<String>(),
+        db_path = serde_json::to_string(utf8_path(&db_path)?)?,
+        scheduled_entry = match scheduled_entry {
+            Some(entry) => serde_json::to_string(utf8_path(entry)?)?,
+            None => "null".to_string(),
+        },
+        vars = serde_json::to_string(env)?,
     );
 
     Ok(script)
 }
+
+fn utf8_path(path: &Path) -> anyhow::Result<&str> {
+    path.to_str().ok_or_else(|| {
+        anyhow::anyhow!(
+            "project path contains invalid UTF-8: {}. Move project to a UTF-8 path.",
+            path.display()
+        )
+    })
+}
+
+/// Emulator capabilities the bootstrap needs: those of the enabled
+/// bindings.
+fn required_capabilities(bindings: &Bindings) -> Vec<&'static str> {
+    CAPABILITIES
+        .iter()
+        .copied()
+        .filter(|capability| match capability.split('.').next() {
+            Some("kv") => bindings.kv.enabled,
+            Some("db") => bindings.db.enabled,
+            _ => true,
+        })
+        .collect()
+}
@@ -1,57 +1,75 @@
 //! Unit tests for JS bootstrap generation
 
-use super::inject::generate_bootstrap;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use super::inject::{BootstrapOptions, generate_bootstrap};
+use crate::config::Bindings;
+use nrz::emulator::protocol::PROTOCOL_VERSION;
+
+/// Options of a session on port 4322 with the token `tok`.
+fn options() -> BootstrapOptions {
+    BootstrapOptions {
+        data_dir: PathBuf::from("/app/.onreza/data"),
+        port: 4322,
+        token: "tok".to_string(),
+        ..Default::default()
+    }
+}
+
+fn bootstrap(options: BootstrapOptions) -> String {
+    generate_bootstrap(&options).unwrap()
+}
 
 #[test]
 fn bootstrap_contains_port() {
-    let dir = tempfile::tempdir().unwrap();
-    let script = generate_bootstrap(dir.path(), 4322).unwrap();
+    let script = bootstrap(options());
     assert!(script.contains("http://127.0.0.1:4322"));
 }
 
 #[test]
 fn bootstrap_contains_db_path() {
-    let dir = tempfile::tempdir().unwrap();
-    let script = generate_bootstrap(dir.path(), 4322).unwrap();
+    let script = bootstrap(options());
     assert!(script.contains("dev.db"));
 }
 
 #[test]
 fn bootstrap_sets_global() {
-    let dir = tempfile::tempdir().unwrap();
-    let script = generate_bootstrap(dir.path(), 4322).unwrap();
+    let script = bootstrap(options());
     assert!(script.contains("globalThis.ONREZA"));
 }
 
 #[test]
 fn bootstrap_has_kv_proxy() {
-    let dir = tempfile::tempdir().unwrap();
-    let script = generate_bootstrap(dir.path(), 4322).unwrap();
-    assert!(script.contains("/__nrz/kv/"));
+    let script = bootstrap(options());
+    assert!(script.contains("__nrzCall(`kv.${method}`, { args })"));
 }
 
 #[test]
 fn bootstrap_has_db_methods() {
-    let dir = tempfile::tempdir().unwrap();
-    let script = generate_bootstrap(dir.path(), 4322).unwrap();
-    assert!(script.contains("/__nrz/db/query"));
-    assert!(script.contains("/__nrz/db/batch"));
-    assert!(script.contains("/__nrz/db/exec"));
+    let script = bootstrap(options());
+    assert!(script.contains(r#"__nrzCall("db.query""#));
+    assert!(script.contains(r#"__nrzCall("db.batch""#));
+    assert!(script.contains(r#"__nrzCall("db.exec""#));
 }
 
 #[test]
 fn bootstrap_has_context() {
-    let dir = tempfile::tempdir().unwrap();
-    let script = generate_bootstrap(dir.path(), 4322).unwrap();
+    let script = bootstrap(options());
     assert!(script.contains("deploymentId"));
     assert!(script.contains("clientIp"));
 }
 
 #[test]
 fn bootstrap_different_ports() {
-    let dir = tempfile::tempdir().unwrap();
-    let s1 = generate_bootstrap(dir.path(), 3000).unwrap();
-    let s2 = generate_bootstrap(dir.path(), 5000).unwrap();
+    let s1 = bootstrap(BootstrapOptions {
+        port: 3000,
+        ..options()
+    });
+    let s2 = bootstrap(BootstrapOptions {
+        port: 5000,
+        ..options()
+    });
     assert!(s1.contains("http://127.0.0.1:3000"));
     assert!(s2.contains("http://127.0.0.1:5000"));
     assert!(!s1.contains("5000"));
@@ -60,7 +78,88 @@ fn bootstrap_different_ports() {
 
 #[test]
 fn bootstrap_db_path_is_json_string() {
-    let dir = tempfile::tempdir().unwrap();
-    let script = generate_bootstrap(dir.path(), 4322).unwrap();
+    let script = bootstrap(options());
     assert!(script.contains("const DB_PATH = \""));
 }
+
+#[test]
+fn disabled_bindings_are_removed() {
+    let mut bindings = Bindings::default();
+    bindings.kv.enabled = false;
+    let script = bootstrap(BootstrapOptions {
+        bindings,
+        ..options()
+    });
+    assert!(script.contains("delete globalThis.ONREZA.kv;"));
+    assert!(!script.contains("delete globalThis.ONREZA.db;"));
+}
+
+#[test]
+fn env_is_written_into_the_script() {
+    let env = BTreeMap::from([("API_KEY".to_string(), "s3cr\"et".to_string())]);
+    let script = bootstrap(BootstrapOptions { env, ..options() });
+    assert!(script.contains(r#"const NRZ_VARS = {"API_KEY":"s3cr\"et"};"#));
+    assert!(script.contains("new Map(Object.entries(NRZ_VARS))"));
+    assert!(!script.contains("process.env.NRZ_ENV"));
+}
+
+#[test]
+fn scheduled_entry_is_written_into_the_script() {
+    let script = bootstrap(BootstrapOptions {
+        scheduled_entry: Some(PathBuf::from("/app/src/cron.js")),
+        ..options()
+    });
+    assert!(script.contains(r#"const NRZ_SCHEDULED_ENTRY = "/app/src/cron.js";"#));
+    assert!(bootstrap(options()).contains("const NRZ_SCHEDULED_ENTRY = null;"));
+}
+
+#[test]
+fn bootstrap_sends_session_token() {
+    let script = bootstrap(BootstrapOptions {
+        token: "abc123".to_string(),
+        ..options()
+    });
+    assert!(script.contains(r#"const NRZ_TOKEN = "abc123";"#));
+    assert!(script.contains("authorization: `Bearer ${NRZ_TOKEN}`"));
+}
+
+#[test]
+fn bootstrap_speaks_the_protocol_version() {
+    let script = bootstrap(options());
+    assert!(script.contains(&format!("const NRZ_PROTOCOL = {PROTOCOL_VERSION};")));
+    assert!(script.contains(r#""x-nrz-protocol": String(NRZ_PROTOCOL)"#));
+    assert!(script.contains("/__nrz/health"));
+}
+
+#[test]
+fn bootstrap_requires_capabilities_of_enabled_bindings() {
+    let mut bindings = Bindings::default();
+    bindings.db.enabled = false;
+    let script = bootstrap(BootstrapOptions {
+        bindings,
+        ..options()
+    });
+    assert!(script.contains(r#"const NRZ_CAPABILITIES = ["kv","kv.metadata","rpc"];"#));
+}
+
+#[test]
+fn bootstrap_batches_calls_through_rpc() {
+    let script = bootstrap(options());
+    assert!(script.contains("`${NRZ_EMULATOR}/__nrz/rpc`"));
+    assert!(script.contains("queueMicrotask(__nrzFlush)"));
+}
+
+#[test]
+fn bootstrap_connects_through_the_socket() {
+    let socket = PathBuf::from("/app/.onreza/data/emulator.sock");
+    let script = bootstrap(BootstrapOptions {
+        socket: Some(socket.clone()),
+        ..options()
+    });
+    let expected = serde_json::to_string(socket.to_str().unwrap()).unwrap();
+    assert!(script.contains(&format!("const NRZ_SOCKET = {expected};")));
+    assert!(script.contains("socketPath: NRZ_SOCKET"));
+
+    let script = bootstrap(options());
+    assert!(script.contains("const NRZ_SOCKET = null;"));
+}
@@ -0,0 +1,205 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::package_manager::PackageManager;
+
+/// JavaScript runtime that executes the dev server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsRuntime {
+    Node,
+    Bun,
+    Deno,
+}
+
+impl JsRuntime {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Node => "node",
+            Self::Bun => "bun",
+            Self::Deno => "deno",
+        }
+    }
+}
+
+/// Detect the runtime a dev command runs on.
+///
+/// `bun ...` and `--bun` (which makes bunx run Node CLIs on Bun) mean Bun,
+/// `deno ...` means Deno. Everything else goes through the package manager
+/// and Node.js.
+pub fn detect_runtime(dev_command: &str) -> JsRuntime {
+    match dev_command.split_whitespace().next() {
+        Some("bun") => JsRuntime::Bun,
+        Some("deno") => JsRuntime::Deno,
+        _ if dev_command.split_whitespace().any(|w| w == "--bun") => JsRuntime::Bun,
+        _ => JsRuntime::Node,
+    }
+}
+
+/// A dev command prepared to load the bootstrap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Launch {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Extra environment variables
+    pub env: Vec<(String, String)>,
+    /// Command line for display, with the bootstrap path shortened
+    pub display: String,
+}
+
+/// Build the command line that runs `dev_command` with the bootstrap
+/// preloaded, the way `runtime` supports it:
+///
+/// - Node.js: the package manager's runner with `NODE_OPTIONS=--import`
+/// - Bun: `bun --preload`, plus `BUN_OPTIONS` for Bun processes it starts
+/// - Deno: `deno run|serve --preload`; `deno task` is expanded from
+///   deno.json first, since tasks cannot take extra flags
+pub fn launch(
+    runtime: JsRuntime,
+    dev_command: &str,
+    bootstrap_path: &Path,
+    package_manager: PackageManager,
+    project_dir: &Path,
+) -> anyhow::Result<Launch> {
+    let parts: Vec<String> = dev_command.split_whitespace().map(String::from).collect();
+    anyhow::ensure!(!parts.is_empty(), "empty dev command");
+
+    // Convert bootstrap path to a file:// URL to avoid issues with spaces/special chars
+    let bootstrap_url = url::Url::from_file_path(bootstrap_path)
+        .map_err(|_| anyhow::anyhow!("invalid bootstrap path: {}", bootstrap_path.display()))?
+        .to_string();
+    let shown = bootstrap_path
+        .strip_prefix(project_dir)
+        .unwrap_or(bootstrap_path)
+        .display()
+        .to_string();
+
+    let (program, args, env) = match runtime {
+        JsRuntime::Node => {
+            let (runner, runner_args) = package_manager.runner();
+            let args: Vec<String> = runner_args
+                .iter()
+                .map(|a| a.to_string())
+                .chain(parts)
+                .collect();
+            let node_options = append_option("NODE_OPTIONS", &format!("--import {bootstrap_url}"));
+            let launch = Launch {
+                display: std::iter::once(runner.to_string())
+                    .chain(args.iter().cloned())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                program: package_manager.program(),
+                args,
+                env: vec![("NODE_OPTIONS".into(), node_options)],
+            };
+            return Ok(launch);
+        }
+        JsRuntime::Bun => {
+            let path = bootstrap_path.to_string_lossy().into_owned();
+            let env = vec![(
+                "BUN_OPTIONS".to_string(),
+                append_option("BUN_OPTIONS", &format!("--preload {path}")),
+            )];
+            let binary = match parts.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                ["bunx", ..] => Some(&parts[1..]),
+                ["bun", "x", ..] => Some(&parts[2..]),
+                ["bun", ..] => None,
+                _ => Some(&parts[..]),
+            };
+            match binary {
+                None => {
+                    let args = preload_args(&["--preload", &path], &parts[1..]);
+                    ("bun".to_string(), args, env)
+                }
+                // `vite dev`, `bunx --bun vite dev` and the like: only
+                // BUN_OPTIONS reaches Bun, and only with `--bun`, which
+                // keeps the binary off Node
+                Some(binary) => {
+                    let args = std::iter::once("--bun".to_string())
+                        .chain(binary.iter().filter(|a| *a != "--bun").cloned())
+                        .collect();
+                    (PackageManager::Bun.program(), args, env)
+                }
+            }
+        }
+        JsRuntime::Deno => {
+            let parts = if parts.get(1).map(String::as_str) == Some("task") {
+                let name = parts.get(2).context("`deno task` needs a task name")?;
+                let task = deno_task(project_dir, name)?;
+                task.split_whitespace()
+                    .map(String::from)
+                    .chain(parts[3..].iter().cloned())
+                    .collect()
+            } else {
+                parts
+            };
+            match parts.get(1).map(String::as_str) {
+                Some("run" | "serve") if parts[0] == "deno" => {
+                    let args = preload_args(&[&parts[1], "--preload", &bootstrap_url], &parts[2..]);
+                    ("deno".to_string(), args, Vec::new())
+                }
+                _ => anyhow::bail!(
+                    "cannot preload the ONREZA bootstrap into `{}`. \
+                     Use `deno run` or `deno serve` as the dev command",
+                    parts.join(" ")
+                ),
+            }
+        }
+    };
+
+    let display = std::iter::once(program.as_str())
+        .chain(args.iter().map(String::as_str))
+        .map(|a| {
+            if a == bootstrap_url || a == bootstrap_path.to_string_lossy() {
+                shown.as_str()
+            } else {
+                a
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(Launch {
+        program,
+        args,
+        env,
+        display,
+    })
+}
+
+fn preload_args(preload: &[&str], rest: &[String]) -> Vec<String> {
+    preload
+        .iter()
+        .map(|a| a.to_string())
+        .chain(rest.iter().cloned())
+        .collect()
+}
+
+/// Append to an options variable from our own environment.
+fn append_option(var: &str, option: &str) -> String {
+    match std::env::var(var) {
+        Ok(existing) if !existing.is_empty() => format!("{existing} {option}"),
+        _ => option.to_string(),
+    }
+}
+
+/// Command of a task in deno.json / deno.jsonc (string or `{ "command" }`).
+pub fn deno_task(project_dir: &Path, name: &str) -> anyhow::Result<String> {
+    let path = ["deno.json", "deno.jsonc"]
+        .iter()
+        .map(|f| project_dir.join(f))
+        .find(|p| p.is_file())
+        .context("`deno task` used, but no deno.json or deno.jsonc found")?;
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let json: serde_json::Value = serde_json::from_str(&crate::config::strip_jsonc(&content))
+        .with_context(|| format!("invalid {}", path.display()))?;
+    let task = json
+        .get("tasks")
+        .and_then(|t| t.get(name))
+        .with_context(|| format!("task '{name}' not found in {}", path.display()))?;
+    task.as_str()
+        .or_else(|| task.get("command").and_then(|c| c.as_str()))
+        .map(String::from)
+        .with_context(|| format!("task '{name}' in {} has no command", path.display()))
+}
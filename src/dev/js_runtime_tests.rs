@@ -0,0 +1,143 @@
+//! Unit tests for JS runtime detection and bootstrap preloading
+
+use std::path::Path;
+
+use super::js_runtime::{JsRuntime, deno_task, detect_runtime, launch};
+use super::package_manager::PackageManager;
+
+fn launch_in(
+    dir: &Path,
+    runtime: JsRuntime,
+    command: &str,
+) -> anyhow::Result<super::js_runtime::Launch> {
+    let bootstrap = dir.join(".nrz").join("bootstrap.mjs");
+    launch(runtime, command, &bootstrap, PackageManager::Npm, dir)
+}
+
+#[test]
+fn detect_runtime_from_command() {
+    assert_eq!(detect_runtime("vite dev"), JsRuntime::Node);
+    assert_eq!(detect_runtime("next dev --turbo"), JsRuntime::Node);
+    assert_eq!(detect_runtime("bun run server.ts"), JsRuntime::Bun);
+    assert_eq!(detect_runtime("bunx --bun vite dev"), JsRuntime::Bun);
+    assert_eq!(detect_runtime("vite dev --bun"), JsRuntime::Bun);
+    assert_eq!(detect_runtime("deno task dev"), JsRuntime::Deno);
+    assert_eq!(detect_runtime("deno run -A main.ts"), JsRuntime::Deno);
+}
+
+#[test]
+fn node_uses_package_manager_and_node_options() {
+    let dir = tempfile::tempdir().unwrap();
+    let launch = launch_in(dir.path(), JsRuntime::Node, "vite dev").unwrap();
+    assert_eq!(launch.program, PackageManager::Npm.program());
+    assert_eq!(launch.args, ["--no", "vite", "dev"]);
+    assert_eq!(launch.display, "npx --no vite dev");
+    let (name, value) = &launch.env[0];
+    assert_eq!(name, "NODE_OPTIONS");
+    assert!(value.ends_with("bootstrap.mjs"), "{value}");
+    assert!(value.contains("--import file://"), "{value}");
+}
+
+#[test]
+fn bun_preloads_bootstrap() {
+    let dir = tempfile::tempdir().unwrap();
+    let bootstrap = dir.path().join(".nrz").join("bootstrap.mjs");
+    let launch = launch_in(dir.path(), JsRuntime::Bun, "bun run server.ts").unwrap();
+    assert_eq!(launch.program, "bun");
+    assert_eq!(
+        launch.args,
+        ["--preload", bootstrap.to_str().unwrap(), "run", "server.ts"]
+    );
+    assert_eq!(
+        launch.display,
+        "bun --preload .nrz/bootstrap.mjs run server.ts"
+    );
+    let (name, value) = &launch.env[0];
+    assert_eq!(name, "BUN_OPTIONS");
+    assert!(value.ends_with(bootstrap.to_str().unwrap()), "{value}");
+}
+
+#[test]
+fn bun_runs_package_binaries_through_bunx() {
+    let dir = tempfile::tempdir().unwrap();
+    for command in [
+        "vite dev",
+        "vite dev --bun",
+        "bunx --bun vite dev",
+        "bun x vite dev",
+    ] {
+        let launch = launch_in(dir.path(), JsRuntime::Bun, command).unwrap();
+        assert_eq!(launch.program, "bunx", "{command}");
+        assert_eq!(launch.args, ["--bun", "vite", "dev"], "{command}");
+        assert_eq!(launch.display, "bunx --bun vite dev", "{command}");
+        assert_eq!(launch.env[0].0, "BUN_OPTIONS");
+    }
+}
+
+#[test]
+fn deno_preloads_after_subcommand() {
+    let dir = tempfile::tempdir().unwrap();
+    let launch = launch_in(dir.path(), JsRuntime::Deno, "deno run -A main.ts").unwrap();
+    assert_eq!(launch.program, "deno");
+    assert_eq!(launch.args[0], "run");
+    assert_eq!(launch.args[1], "--preload");
+    assert!(launch.args[2].starts_with("file://"), "{}", launch.args[2]);
+    assert_eq!(&launch.args[3..], ["-A", "main.ts"]);
+    assert_eq!(
+        launch.display,
+        "deno run --preload .nrz/bootstrap.mjs -A main.ts"
+    );
+    assert!(launch.env.is_empty());
+}
+
+#[test]
+fn deno_task_is_expanded_from_config() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("deno.jsonc"),
+        r#"{
+          // tasks can be strings or objects
+          "tasks": {
+            "dev": "deno serve --watch main.ts",
+            "start": { "command": "deno run -A main.ts" }
+          }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        deno_task(dir.path(), "start").unwrap(),
+        "deno run -A main.ts"
+    );
+
+    let launch = launch_in(dir.path(), JsRuntime::Deno, "deno task dev --port 3000").unwrap();
+    assert_eq!(launch.args[0], "serve");
+    assert_eq!(&launch.args[3..], ["--watch", "main.ts", "--port", "3000"]);
+
+    let err = launch_in(dir.path(), JsRuntime::Deno, "deno task missing").unwrap_err();
+    assert!(
+        err.to_string().contains("task 'missing' not found"),
+        "{err}"
+    );
+}
+
+#[test]
+fn deno_rejects_commands_without_preload() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("deno.json"),
+        r#"{ "tasks": { "dev": "vite dev" } }"#,
+    )
+    .unwrap();
+    let err = launch_in(dir.path(), JsRuntime::Deno, "deno task dev").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("cannot preload the ONREZA bootstrap into `vite dev`"),
+        "{err}"
+    );
+
+    let err = launch_in(dir.path(), JsRuntime::Deno, "deno test").unwrap_err();
+    assert!(
+        err.to_string().contains("Use `deno run` or `deno serve`"),
+        "{err}"
+    );
+}
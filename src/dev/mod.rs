@@ -1,35 +1,92 @@
+pub mod cron;
 pub mod detect;
+pub mod env;
 pub mod inject;
-mod process;
+pub mod js_runtime;
+pub mod package_manager;
+pub mod ports;
+pub mod process;
+pub mod proxy;
+pub mod scheduled;
+mod supervisor;
+pub mod workspace;
+
+#[cfg(test)]
+mod cron_tests;
 
 #[cfg(test)]
 mod detect_tests;
 
+#[cfg(test)]
+mod env_tests;
+
 #[cfg(test)]
 mod inject_tests;
 
+#[cfg(test)]
+mod js_runtime_tests;
+
+#[cfg(test)]
+mod package_manager_tests;
+
+#[cfg(test)]
+mod ports_tests;
+
+#[cfg(test)]
+mod process_tests;
+
+#[cfg(test)]
+mod proxy_tests;
+
+#[cfg(test)]
+mod scheduled_tests;
+
+#[cfg(test)]
+mod supervisor_tests;
+
+#[cfg(test)]
+mod workspace_tests;
+
+use std::path::{Path, PathBuf};
+
 use anyhow::Context;
 
 use crate::cli::DevArgs;
+use crate::config::Config;
+use crate::types;
 use nrz::emulator;
+use nrz::emulator::activity::ActivityLog;
+use nrz::emulator::auth;
 use nrz::emulator::kv::KvStore;
-use nrz::emulator::server::EmulatorServer;
+use nrz::emulator::runtime::RuntimeInfo;
+use nrz::emulator::server::{DevInfo, EmulatorServer, SOCKET_FILE};
+
+/// Dev server port assumed for custom `--command`s.
+const DEFAULT_PORT: u16 = 4321;
 
 /// Start local dev server with platform emulation.
 ///
-/// 1. Detect framework (Astro, Nuxt, Nitro, SvelteKit)
+/// 1. Detect framework (see [`detect::detect_framework`])
 /// 2. Start emulator (KV, DB, Context)
 /// 3. Generate JS bootstrap that sets globalThis.ONREZA
-/// 4. Spawn framework dev command as child process
-/// 5. Forward signals, handle graceful shutdown
+/// 4. Start the context proxy (see [`proxy::Proxy`]) on the dev port
+/// 5. Spawn framework dev command as child process behind it, restarted
+///    on crashes and config changes (see [`supervisor::supervise`])
+/// 6. Fire cron triggers at the scheduled handler (see [`scheduled`])
+/// 7. Forward signals, handle graceful shutdown
 pub async fn run(args: DevArgs) -> anyhow::Result<()> {
-    let project_dir = std::path::Path::new(&args.dir)
+    let dir = Path::new(&args.dir)
         .canonicalize()
         .with_context(|| format!("project directory not found: {}", args.dir))?;
+    let project_dir = workspace::resolve_project_dir(&dir, args.app.as_deref())?;
+    let config = Config::load(&project_dir)?;
 
     // 1. Detect framework or use custom command
-    let dev_command = if let Some(ref cmd) = args.command {
-        cmd.clone()
+    let command = args.command.or(config.dev.command.clone());
+    let port = args.port.or(config.dev.port);
+    let explicit_port = port.is_some();
+    let (dev_command, detected, port) = if let Some(cmd) = command {
+        (cmd, false, port.unwrap_or(DEFAULT_PORT))
     } else {
         let framework = detect::detect_framework(&project_dir)?;
         eprintln!(
@@ -37,59 +94,325 @@ pub async fn run(args: DevArgs) -> anyhow::Result<()> {
             console::style("~").cyan().bold(),
             framework.name,
         );
-        framework.dev_command
+        (
+            framework.dev_command,
+            true,
+            port.unwrap_or(framework.default_port),
+        )
+    };
+    let port = ports::resolve_port(port, explicit_port, "--port", &[])?;
+    // Behind the proxy the framework listens on a free port; custom
+    // commands are told through PORT
+    let upstream_port = if args.no_proxy {
+        port
+    } else {
+        ports::free_port()?
+    };
+    let emulator_port = args.emulator_port.or(config.dev.emulator_port);
+    let emulator_port = ports::resolve_port(
+        emulator_port.unwrap_or(port.saturating_add(1)),
+        emulator_port.is_some(),
+        "--emulator-port",
+        &[port, upstream_port],
+    )?;
+    let dev_command = if detected && (explicit_port || !args.no_proxy) {
+        // Every supported framework CLI accepts --port
+        format!("{dev_command} --port {upstream_port}")
+    } else {
+        dev_command
     };
 
+    let activity = ActivityLog::new();
+    let proxy = std::sync::Arc::new(proxy::Proxy {
+        upstream_port,
+        geo: proxy::Geo::from_pairs(&args.geo)?.or(&config.dev.geo),
+        project_id: config.project_id(&args.mode).unwrap_or("dev").to_string(),
+        activity: activity.clone(),
+    });
+    let mut context = proxy.context(
+        &Default::default(),
+        std::net::Ipv4Addr::LOCALHOST.into(),
+        "",
+    );
+    context["requestId"] = serde_json::Value::Null;
+
     // 2. Ensure data directory
     let data_dir = emulator::ensure_data_dir(&project_dir)?;
     let db_path = data_dir.join("dev.db");
 
-    // 3. Generate bootstrap script
-    let emulator_port = args.port + 1;
-    let bootstrap = inject::generate_bootstrap(&data_dir, emulator_port)?;
-    let bootstrap_path = data_dir.join("bootstrap.mjs");
-    std::fs::write(&bootstrap_path, &bootstrap)?;
-
-    // 4. Create KV store + emulator server
+    // 3. Create KV store + emulator server, with a secret only the
+    // bootstrap and the dashboard get
+    let token = auth::session_token()?;
     let kv = KvStore::new();
-    let server = EmulatorServer::new(kv, db_path, emulator_port);
+    let mut server = EmulatorServer::new(kv, db_path, emulator_port);
+    server.d1_compat = args.d1_compat.or(config.dev.d1_compat).unwrap_or_default();
+    server.activity = activity;
+    server.token = token.clone();
+    server.socket = Some(data_dir.join(SOCKET_FILE));
+    let dev_info = server.dev_info.clone();
 
-    // 5. Start emulator server in background
-    let server_handle = tokio::spawn(async move {
-        if let Err(e) = server.start().await {
-            tracing::error!(%e, "emulator server error");
-        }
-    });
+    // 4. Start emulator server in background
+    let emulator = server
+        .bind()
+        .await
+        .with_context(|| format!("emulator failed to start on port {emulator_port}"))?;
+    let socket = emulator.socket().map(Path::to_path_buf);
 
-    // 6. Wait for emulator to be ready
-    wait_for_emulator(emulator_port).await?;
+    // 5. Load ONREZA.env and generate the bootstrap script, which
+    // connects through the socket when there is one
+    let session = Session {
+        project_dir: &project_dir,
+        mode: &args.mode,
+        context,
+        dev_info,
+        bootstrap: inject::BootstrapOptions {
+            data_dir: data_dir.clone(),
+            port: emulator_port,
+            socket: socket.clone(),
+            token: token.clone(),
+            ..Default::default()
+        },
+        bootstrap_path: data_dir.join(emulator::BOOTSTRAP_FILE),
+        // Keep onreza.d.ts in step with the config and env files in
+        // TypeScript projects, or where the file already exists
+        types: project_dir.join("tsconfig.json").is_file()
+            || project_dir.join(types::TYPES_FILE).is_file(),
+    };
+    session.load(&config)?;
+    env::check_secrets_ignored(&project_dir);
 
     eprintln!(
         "  {} emulator ready on port {emulator_port}",
         console::style("~").cyan().bold(),
     );
     eprintln!(
-        "  {} starting: {dev_command}",
-        console::style(">").green().bold(),
+        "  {} dashboard: http://localhost:{emulator_port}/__nrz/ui?{}={token}",
+        console::style("~").cyan().bold(),
+        auth::TOKEN_PARAM,
     );
-
-    // 7. Spawn framework dev server (blocks until exit or Ctrl+C)
-    let result = process::spawn_dev_server(&project_dir, &dev_command, &bootstrap_path).await;
+    let proxy_handle = if args.no_proxy {
+        None
+    } else {
+        let handle = start_proxy(proxy, port).await?;
+        eprintln!(
+            "  {} dev server: http://localhost:{port}",
+            console::style("~").cyan().bold(),
+        );
+        Some(handle)
+    };
+    // Let other tools find this session's emulator
+    let mut runtime_info = RuntimeInfo::new(port, upstream_port, emulator_port, &token);
+    runtime_info.emulator_socket = socket.clone();
+    runtime_info.write(&data_dir)?;
+    // 6. Fire cron triggers through the dev port, like requests
+    let crons = load_triggers(&project_dir, &config);
+    for cron in &crons {
+        print_trigger(cron);
+    }
+    let (crons_tx, crons_rx) = tokio::sync::watch::channel(crons);
+    let scheduler = scheduled::schedule(port, token.clone(), crons_rx);
+    let package_manager = args
+        .package_manager
+        .or(config.dev.package_manager)
+        .unwrap_or_else(|| package_manager::detect_package_manager(&project_dir));
+    let runtime = args
+        .runtime
+        .or(config.dev.runtime)
+        .unwrap_or_else(|| js_runtime::detect_runtime(&dev_command));
+    if runtime != js_runtime::JsRuntime::Node {
+        eprintln!(
+            "  {} runtime: {}",
+            console::style("~").cyan().bold(),
+            runtime.as_str()
+        );
+    }
+    let result = match js_runtime::launch(
+        runtime,
+        &dev_command,
+        &session.bootstrap_path,
+        package_manager,
+        &project_dir,
+    ) {
+        Ok(launch) => {
+            eprintln!(
+                "  {} starting: {}",
+                console::style(">").green().bold(),
+                launch.display
+            );
+            // 7. Run framework dev server, restarting it on crashes and
+            // config changes (blocks until exit or Ctrl+C)
+            let reload = || {
+                let config = Config::load(&project_dir)?;
+                session.load(&config)?;
+                let crons = load_triggers(&project_dir, &config);
+                crons_tx.send_if_modified(|current| {
+                    let changed = *current != crons;
+                    if changed {
+                        crons.iter().for_each(print_trigger);
+                        *current = crons;
+                    }
+                    changed
+                });
+                Ok(())
+            };
+            supervisor::supervise(
+                &project_dir,
+                &args.mode,
+                &launch,
+                upstream_port,
+                !args.no_restart,
+                reload,
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
 
     // 8. Cleanup
-    server_handle.abort();
-    let _ = std::fs::remove_file(&bootstrap_path);
+    scheduler.abort();
+    if let Err(e) = emulator.shutdown().await {
+        tracing::warn!(%e, "emulator did not shut down cleanly");
+    }
+    if let Some(handle) = proxy_handle {
+        handle.abort();
+    }
+    let _ = std::fs::remove_file(&session.bootstrap_path);
+    RuntimeInfo::remove(&data_dir);
 
     result
 }
 
-async fn wait_for_emulator(port: u16) -> anyhow::Result<()> {
-    let url = format!("http://127.0.0.1:{port}/__nrz/health");
-    for _ in 0..50 {
-        match reqwest::get(&url).await {
-            Ok(resp) if resp.status().is_success() => return Ok(()),
-            _ => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+/// Parts of a dev session that follow the config and env files.
+struct Session<'a> {
+    project_dir: &'a Path,
+    mode: &'a str,
+    /// Default `ONREZA.context`, shown on the dashboard
+    context: serde_json::Value,
+    dev_info: DevInfo,
+    /// Bootstrap settings that do not come from the config
+    bootstrap: inject::BootstrapOptions,
+    bootstrap_path: PathBuf,
+    /// Whether to update onreza.d.ts as well
+    types: bool,
+}
+
+impl Session<'_> {
+    /// Load `ONREZA.env` for `config`, print it and show it on the
+    /// dashboard, and write the bootstrap script (and onreza.d.ts) with it.
+    /// Run on start and again whenever the config or env files change.
+    fn load(&self, config: &Config) -> anyhow::Result<()> {
+        // Variables for ONREZA.env: config, env files and secrets
+        let vars = env::load_env(self.project_dir, self.mode, &config.env(self.mode))?;
+        env::print_env(&vars);
+        let env_info: Vec<serde_json::Value> = vars
+            .iter()
+            .map(|(name, var)| {
+                serde_json::json!({
+                    "name": name,
+                    "value": env::mask(&var.value, var.source == env::SECRETS_FILE),
+                    "source": var.source,
+                })
+            })
+            .collect();
+        let bootstrap = inject::generate_bootstrap(&inject::BootstrapOptions {
+            bindings: config.bindings.clone(),
+            env: vars.into_iter().map(|(k, v)| (k, v.value)).collect(),
+            scheduled_entry: scheduled_entry(self.project_dir, config),
+            ..self.bootstrap.clone()
+        })?;
+        emulator::write_private(&self.bootstrap_path, bootstrap)?;
+        self.dev_info.set(serde_json::json!({
+            "mode": self.mode,
+            "env": env_info,
+            "context": self.context,
+        }));
+        if self.types {
+            match types::update(self.project_dir, config, self.mode) {
+                Ok(true) => eprintln!(
+                    "  {} updated {}",
+                    console::style("~").cyan().bold(),
+                    types::TYPES_FILE,
+                ),
+                Ok(false) => {}
+                Err(e) => eprintln!(
+                    "  {} {} not updated: {e:#}",
+                    console::style("!").yellow().bold(),
+                    types::TYPES_FILE,
+                ),
+            }
         }
+        Ok(())
     }
-    anyhow::bail!("emulator server failed to start on port {port}")
+}
+
+/// Cron triggers of the app, or none (with a warning) when they cannot
+/// be read.
+fn load_triggers(project_dir: &Path, config: &Config) -> Vec<cron::Cron> {
+    scheduled::triggers(project_dir, config).unwrap_or_else(|e| {
+        eprintln!(
+            "  {} cron triggers ignored: {e:#}",
+            console::style("!").yellow().bold(),
+        );
+        Vec::new()
+    })
+}
+
+/// Module set as `dev.scheduledEntry`, or none (with a warning) when it
+/// does not exist.
+fn scheduled_entry(project_dir: &Path, config: &Config) -> Option<PathBuf> {
+    let entry = project_dir.join(config.dev.scheduled_entry.as_ref()?);
+    if !entry.is_file() {
+        eprintln!(
+            "  {} dev.scheduledEntry ignored: {} does not exist",
+            console::style("!").yellow().bold(),
+            entry.display(),
+        );
+        return None;
+    }
+    Some(entry)
+}
+
+fn print_trigger(cron: &cron::Cron) {
+    let next = cron
+        .next_after(scheduled::now_millis() / 1000)
+        .map(|t| cron::format_time(t * 1000))
+        .unwrap_or_default();
+    eprintln!(
+        "  {} cron {cron}, next at {next}",
+        console::style("~").cyan().bold(),
+    );
+}
+
+/// Listen on `port` on IPv4 loopback, and IPv6 loopback where available,
+/// since browsers may resolve `localhost` to either.
+async fn start_proxy(
+    proxy: std::sync::Arc<proxy::Proxy>,
+    port: u16,
+) -> anyhow::Result<tokio::task::JoinHandle<()>> {
+    let v4 = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, port))
+        .await
+        .with_context(|| {
+            format!(
+                "{}. Pick another with --port",
+                ports::describe_conflict(port)
+            )
+        })?;
+    let v6 = tokio::net::TcpListener::bind((std::net::Ipv6Addr::LOCALHOST, port))
+        .await
+        .ok();
+    Ok(tokio::spawn(async move {
+        let v6 = async {
+            match v6 {
+                Some(listener) => proxy.clone().serve(listener).await,
+                None => std::future::pending().await,
+            }
+        };
+        let result = tokio::select! {
+            r = proxy.clone().serve(v4) => r,
+            r = v6 => r,
+        };
+        if let Err(e) = result {
+            tracing::error!(%e, "dev proxy error");
+        }
+    }))
 }
@@ -0,0 +1,96 @@
+use std::path::Path;
+
+/// Package manager used to run the project's binaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PackageManager {
+    Npm,
+    Pnpm,
+    /// Yarn 1.x
+    Yarn,
+    /// Yarn 2+ (Plug'n'Play or node_modules)
+    YarnBerry,
+    Bun,
+}
+
+impl PackageManager {
+    /// Program and leading arguments that run a local package binary.
+    pub fn runner(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            // --no: fail instead of prompting to install a missing binary
+            Self::Npm => ("npx", &["--no"]),
+            Self::Pnpm => ("pnpm", &["exec"]),
+            Self::Yarn => ("yarn", &["run"]),
+            Self::YarnBerry => ("yarn", &["exec"]),
+            Self::Bun => ("bunx", &[]),
+        }
+    }
+
+    /// Program name to spawn. On Windows npm, pnpm and yarn are `.cmd`
+    /// shims, which `CreateProcess` does not resolve on its own.
+    pub fn program(self) -> String {
+        let (bin, _) = self.runner();
+        if cfg!(windows) && self != Self::Bun {
+            format!("{bin}.cmd")
+        } else {
+            bin.to_string()
+        }
+    }
+}
+
+/// Detect the package manager of the project in `project_dir`.
+///
+/// The `packageManager` field of package.json wins; otherwise the nearest
+/// lockfile in `project_dir` or its ancestors (workspace roots) decides.
+/// Falls back to npm, which ships with Node.js.
+pub fn detect_package_manager(project_dir: &Path) -> PackageManager {
+    if let Some(pm) = from_package_json(project_dir) {
+        return pm;
+    }
+    for dir in project_dir.ancestors() {
+        if let Some(pm) = from_lockfile(dir) {
+            return pm;
+        }
+    }
+    PackageManager::Npm
+}
+
+/// Parse `"packageManager": "<name>@<version>"` (corepack format).
+fn from_package_json(project_dir: &Path) -> Option<PackageManager> {
+    let content = std::fs::read_to_string(project_dir.join("package.json")).ok()?;
+    let pkg: serde_json::Value = serde_json::from_str(&content).ok()?;
+    let field = pkg.get("packageManager")?.as_str()?;
+    let (name, version) = field.split_once('@').unwrap_or((field, ""));
+    match name {
+        "npm" => Some(PackageManager::Npm),
+        "pnpm" => Some(PackageManager::Pnpm),
+        "bun" => Some(PackageManager::Bun),
+        "yarn" if version.starts_with("1.") => Some(PackageManager::Yarn),
+        "yarn" => Some(PackageManager::YarnBerry),
+        _ => None,
+    }
+}
+
+fn from_lockfile(dir: &Path) -> Option<PackageManager> {
+    if dir.join("bun.lock").is_file() || dir.join("bun.lockb").is_file() {
+        return Some(PackageManager::Bun);
+    }
+    if dir.join("pnpm-lock.yaml").is_file() {
+        return Some(PackageManager::Pnpm);
+    }
+    let yarn_lock = dir.join("yarn.lock");
+    if yarn_lock.is_file() {
+        // Berry lockfiles are YAML with a __metadata section
+        let berry = dir.join(".yarnrc.yml").is_file()
+            || std::fs::read_to_string(&yarn_lock).is_ok_and(|s| s.contains("__metadata:"));
+        return Some(if berry {
+            PackageManager::YarnBerry
+        } else {
+            PackageManager::Yarn
+        });
+    }
+    if dir.join("package-lock.json").is_file() || dir.join("npm-shrinkwrap.json").is_file() {
+        return Some(PackageManager::Npm);
+    }
+    None
+}
@@ -0,0 +1,106 @@
+//! Unit tests for package manager detection
+
+use std::path::Path;
+
+use super::package_manager::{PackageManager, detect_package_manager};
+
+fn write(dir: &Path, name: &str, content: &str) {
+    std::fs::write(dir.join(name), content).unwrap();
+}
+
+#[test]
+fn detect_from_lockfiles() {
+    let cases = [
+        ("package-lock.json", PackageManager::Npm),
+        ("npm-shrinkwrap.json", PackageManager::Npm),
+        ("pnpm-lock.yaml", PackageManager::Pnpm),
+        ("bun.lock", PackageManager::Bun),
+        ("bun.lockb", PackageManager::Bun),
+    ];
+    for (lockfile, expected) in cases {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "package.json", "{}");
+        write(dir.path(), lockfile, "");
+        assert_eq!(detect_package_manager(dir.path()), expected, "{lockfile}");
+    }
+}
+
+#[test]
+fn detect_yarn_classic_and_berry() {
+    let dir = tempfile::tempdir().unwrap();
+    write(
+        dir.path(),
+        "yarn.lock",
+        "# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.\n# yarn lockfile v1\n",
+    );
+    assert_eq!(detect_package_manager(dir.path()), PackageManager::Yarn);
+
+    write(
+        dir.path(),
+        "yarn.lock",
+        "__metadata:\n  version: 8\n  cacheKey: 10c0\n",
+    );
+    assert_eq!(
+        detect_package_manager(dir.path()),
+        PackageManager::YarnBerry
+    );
+}
+
+#[test]
+fn package_manager_field_wins_over_lockfile() {
+    let dir = tempfile::tempdir().unwrap();
+    write(dir.path(), "package-lock.json", "{}");
+    write(
+        dir.path(),
+        "package.json",
+        r#"{"packageManager":"pnpm@9.12.0+sha512.abc"}"#,
+    );
+    assert_eq!(detect_package_manager(dir.path()), PackageManager::Pnpm);
+
+    write(
+        dir.path(),
+        "package.json",
+        r#"{"packageManager":"yarn@1.22.22"}"#,
+    );
+    assert_eq!(detect_package_manager(dir.path()), PackageManager::Yarn);
+
+    write(
+        dir.path(),
+        "package.json",
+        r#"{"packageManager":"yarn@4.5.0"}"#,
+    );
+    assert_eq!(
+        detect_package_manager(dir.path()),
+        PackageManager::YarnBerry
+    );
+}
+
+#[test]
+fn lockfile_in_workspace_root() {
+    let root = tempfile::tempdir().unwrap();
+    write(root.path(), "pnpm-lock.yaml", "");
+    let app = root.path().join("apps/web");
+    std::fs::create_dir_all(&app).unwrap();
+    write(&app, "package.json", "{}");
+    assert_eq!(detect_package_manager(&app), PackageManager::Pnpm);
+}
+
+#[test]
+fn default_is_npm() {
+    let dir = tempfile::tempdir().unwrap();
+    write(
+        dir.path(),
+        "package.json",
+        r#"{"packageManager":"unknown@1.0"}"#,
+    );
+    assert_eq!(detect_package_manager(dir.path()), PackageManager::Npm);
+}
+
+#[test]
+fn runners() {
+    assert_eq!(PackageManager::Npm.runner(), ("npx", &["--no"][..]));
+    assert_eq!(PackageManager::Pnpm.runner(), ("pnpm", &["exec"][..]));
+    assert_eq!(PackageManager::Yarn.runner(), ("yarn", &["run"][..]));
+    assert_eq!(PackageManager::YarnBerry.runner(), ("yarn", &["exec"][..]));
+    assert_eq!(PackageManager::Bun.runner(), ("bunx", &[][..]));
+}
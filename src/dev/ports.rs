@@ -0,0 +1,150 @@
+use std::net::{Ipv4Addr, Ipv6Addr, TcpListener};
+
+/// How many ports after the preferred one to try before asking the OS.
+const SEARCH_RANGE: u16 = 20;
+
+/// Whether `port` can be bound on the loopback interfaces. Dev servers
+/// that resolve `localhost` to `::1` only hold the IPv6 one.
+pub fn is_free(port: u16) -> bool {
+    let v6_in_use = TcpListener::bind((Ipv6Addr::LOCALHOST, port))
+        .is_err_and(|e| e.kind() == std::io::ErrorKind::AddrInUse);
+    !v6_in_use && TcpListener::bind((Ipv4Addr::LOCALHOST, port)).is_ok()
+}
+
+/// Any free local port, chosen by the OS.
+pub fn free_port() -> anyhow::Result<u16> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// Resolve a port for a dev service.
+///
+/// An explicit port (flag or config) must be free, or this fails naming the
+/// process that holds it. A default port moves to the next free one, so
+/// several projects can run side by side; `taken` lists ports already
+/// assigned to other services.
+pub fn resolve_port(
+    preferred: u16,
+    explicit: bool,
+    flag: &str,
+    taken: &[u16],
+) -> anyhow::Result<u16> {
+    if !taken.contains(&preferred) && is_free(preferred) {
+        return Ok(preferred);
+    }
+    if explicit {
+        anyhow::bail!(
+            "{}. Stop it or pick another with {flag}",
+            describe_conflict(preferred)
+        );
+    }
+    let port = (1..=SEARCH_RANGE)
+        .filter_map(|offset| preferred.checked_add(offset))
+        .find(|p| !taken.contains(p) && is_free(*p));
+    let port = match port {
+        Some(port) => port,
+        None => free_port()?,
+    };
+    if !taken.contains(&preferred) {
+        eprintln!(
+            "  {} {}, using {port}",
+            console::style("!").yellow().bold(),
+            describe_conflict(preferred),
+        );
+    }
+    Ok(port)
+}
+
+/// "port N is in use by NAME (pid P)", or without the owner when it cannot
+/// be determined.
+pub fn describe_conflict(port: u16) -> String {
+    match port_owner(port) {
+        Some((pid, name)) => format!("port {port} is in use by {name} (pid {pid})"),
+        None => format!("port {port} is already in use"),
+    }
+}
+
+/// Process listening on TCP `port`: pid and command name.
+pub fn port_owner(port: u16) -> Option<(u32, String)> {
+    #[cfg(target_os = "linux")]
+    if let Some(owner) = proc_owner(port) {
+        return Some(owner);
+    }
+    lsof_owner(port)
+}
+
+/// Ask `lsof` (macOS, most Linux distributions).
+fn lsof_owner(port: u16) -> Option<(u32, String)> {
+    let output = std::process::Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{port}"), "-sTCP:LISTEN", "-Fpc"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    parse_lsof(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `lsof -F pc` output: `p<pid>` and `c<command>` lines.
+pub fn parse_lsof(output: &str) -> Option<(u32, String)> {
+    let mut pid = None;
+    for line in output.lines() {
+        if let Some(p) = line.strip_prefix('p') {
+            pid = p.parse().ok();
+        } else if let (Some(name), Some(pid)) = (line.strip_prefix('c'), pid) {
+            return Some((pid, name.to_string()));
+        }
+    }
+    None
+}
+
+/// Find the socket inode in /proc/net/tcp{,6}, then the process with a
+/// file descriptor for it. Only sees processes of the same user.
+#[cfg(target_os = "linux")]
+fn proc_owner(port: u16) -> Option<(u32, String)> {
+    let inodes: Vec<String> = ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .flat_map(|table| listening_inodes(&table, port))
+        .collect();
+    if inodes.is_empty() {
+        return None;
+    }
+    let targets: Vec<String> = inodes.iter().map(|i| format!("socket:[{i}]")).collect();
+
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|n| n.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let owns = fds.flatten().any(|fd| {
+            std::fs::read_link(fd.path())
+                .is_ok_and(|link| targets.iter().any(|t| link.as_os_str() == t.as_str()))
+        });
+        if owns {
+            let name = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+            return Some((pid, name.trim().to_string()));
+        }
+    }
+    None
+}
+
+/// Inodes of sockets listening on `port` in a /proc/net/tcp table.
+pub fn listening_inodes(table: &str, port: u16) -> Vec<String> {
+    const LISTEN: &str = "0A";
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local_port = fields.get(1)?.rsplit_once(':')?.1;
+            let listening = *fields.get(3)? == LISTEN;
+            (listening && u16::from_str_radix(local_port, 16).ok()? == port)
+                .then(|| fields.get(9).map(|s| s.to_string()))?
+        })
+        .collect()
+}
@@ -0,0 +1,72 @@
+//! Unit tests for dev port selection
+
+use std::net::{Ipv4Addr, TcpListener};
+
+use super::ports::{describe_conflict, is_free, listening_inodes, parse_lsof, resolve_port};
+
+fn occupied() -> (TcpListener, u16) {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    (listener, port)
+}
+
+#[test]
+fn occupied_port_is_not_free() {
+    let (_listener, port) = occupied();
+    assert!(!is_free(port));
+}
+
+#[test]
+fn default_port_moves_to_next_free() {
+    let (_listener, port) = occupied();
+    let chosen = resolve_port(port, false, "--port", &[]).unwrap();
+    assert_ne!(chosen, port);
+    assert!(is_free(chosen));
+}
+
+#[test]
+fn default_port_skips_taken_ports() {
+    let (listener, port) = occupied();
+    drop(listener);
+    let chosen = resolve_port(port, false, "--emulator-port", &[port]).unwrap();
+    assert_ne!(chosen, port);
+}
+
+#[test]
+fn explicit_port_conflict_fails() {
+    let (_listener, port) = occupied();
+    let err = resolve_port(port, true, "--emulator-port", &[]).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains(&format!("port {port}")), "{message}");
+    assert!(message.contains("--emulator-port"), "{message}");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn conflict_names_owning_process() {
+    let (_listener, port) = occupied();
+    let message = describe_conflict(port);
+    let pid = std::process::id();
+    assert!(message.contains(&format!("(pid {pid})")), "{message}");
+}
+
+#[test]
+fn parse_lsof_output() {
+    assert_eq!(
+        parse_lsof("p4242\ncnode\nf23\n"),
+        Some((4242, "node".to_string()))
+    );
+    assert_eq!(parse_lsof(""), None);
+}
+
+#[test]
+fn listening_inodes_from_proc_table() {
+    let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:10E1 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 12345 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:10E1 0100007F:9C40 01 00000000:00000000 00:00000000 00000000  1000        0 67890 1 0000000000000000 20 4 30 10 -1
+   2: 0100007F:10E2 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 11111 1 0000000000000000 100 0 0 10 0
+";
+    // 0x10E1 = 4321; the established connection is not a listener
+    assert_eq!(listening_inodes(table, 4321), vec!["12345".to_string()]);
+    assert!(listening_inodes(table, 80).is_empty());
+}
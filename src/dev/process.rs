@@ -1,77 +1,186 @@
 use std::path::Path;
-use std::process::Stdio;
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
 
 use anyhow::Context;
-use tokio::process::Command;
-use tokio::signal;
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+
+use super::js_runtime::Launch;
+use super::workspace::bin_dirs;
 
 /// Spawn the framework dev server as a child process.
 ///
-/// Injects the ONREZA bootstrap script via `NODE_OPTIONS=--import`.
-/// Forwards stdout/stderr to the terminal.
-/// Handles SIGINT/SIGTERM for graceful shutdown.
-pub async fn spawn_dev_server(
+/// Runs the command prepared by [`super::js_runtime::launch`], which
+/// preloads the ONREZA bootstrap; the variables for `ONREZA.env` are in the
+/// bootstrap, not in the environment. `PORT` tells custom commands where to
+/// listen. Forwards stdout/stderr to the terminal, but not stdin: see
+/// [`DevServer`].
+pub fn spawn_dev_server(
     project_dir: &Path,
-    dev_command: &str,
-    bootstrap_path: &Path,
-) -> anyhow::Result<()> {
-    let parts: Vec<&str> = dev_command.split_whitespace().collect();
-    let (bin, args) = parts.split_first().context("empty dev command")?;
-
-    // Convert bootstrap path to a file:// URL to avoid issues with spaces/special chars
-    let bootstrap_url = url::Url::from_file_path(bootstrap_path)
-        .map_err(|_| anyhow::anyhow!("invalid bootstrap path: {}", bootstrap_path.display()))?;
-    let existing = std::env::var("NODE_OPTIONS").unwrap_or_default();
-    let node_options = if existing.is_empty() {
-        format!("--import {bootstrap_url}")
-    } else {
-        format!("{existing} --import {bootstrap_url}")
-    };
-
-    // Use npx/bunx to resolve the binary
-    let mut cmd = Command::new("bunx");
-    cmd.arg(bin)
-        .args(args)
+    launch: &Launch,
+    port: u16,
+) -> anyhow::Result<DevServer> {
+    // Resolve binaries hoisted to the workspace root as well as local ones
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let path = std::env::join_paths(
+        bin_dirs(project_dir)
+            .into_iter()
+            .chain(std::env::split_paths(&path)),
+    )?;
+
+    let mut cmd = Command::new(&launch.program);
+    cmd.args(&launch.args)
         .current_dir(project_dir)
+        .stdin(Stdio::null())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
-        .env("NODE_OPTIONS", node_options);
+        .env("PORT", port.to_string())
+        .env("PATH", path)
+        .envs(launch.env.iter().map(|(k, v)| (k, v)));
 
-    let mut child = cmd.spawn().context("failed to start dev server")?;
+    #[cfg(unix)]
+    cmd.process_group(0);
 
-    // Wait for either the child to exit or a shutdown signal
-    tokio::select! {
-        status = child.wait() => {
-            let status = status?;
-            if !status.success() {
-                anyhow::bail!("dev server exited with {status}");
-            }
+    let child = cmd.spawn().with_context(|| {
+        format!(
+            "failed to start dev server with {}. Is it installed? \
+             Use --package-manager or --runtime to pick another",
+            launch.program
+        )
+    })?;
+    Ok(DevServer {
+        #[cfg(unix)]
+        pgid: child.id().context("dev server exited at once")? as i32,
+        child,
+    })
+}
+
+/// How long the dev server gets to exit before it is killed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Signal that ends the session. It is forwarded to the dev server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shutdown {
+    /// SIGINT, Ctrl+C
+    Interrupt,
+    /// SIGTERM, also used to stop the dev server for a restart
+    Terminate,
+    /// SIGHUP, the terminal was closed
+    Hangup,
+}
+
+impl Shutdown {
+    #[cfg(unix)]
+    fn signo(self) -> i32 {
+        match self {
+            Self::Interrupt => libc::SIGINT,
+            Self::Terminate => libc::SIGTERM,
+            Self::Hangup => libc::SIGHUP,
         }
-        _ = signal::ctrl_c() => {
-            tracing::info!("shutting down dev server...");
-            // Graceful shutdown: SIGTERM on Unix, kill on Windows
-            #[cfg(unix)]
-            if let Some(pid) = child.id() {
-                unsafe { libc::kill(pid as i32, libc::SIGTERM); }
-                match tokio::time::timeout(
-                    std::time::Duration::from_secs(5),
-                    child.wait()
-                ).await {
-                    Ok(_) => {},
-                    Err(_) => {
-                        tracing::warn!("dev server did not exit after 5s, force killing");
-                        let _ = child.kill().await;
-                    }
-                }
-            } else {
-                let _ = child.kill().await;
+    }
+}
+
+/// A running dev server.
+///
+/// On Unix the dev command runs in its own process group, so the servers
+/// that `npx`, `bunx` and the framework CLIs start are stopped with it, and
+/// Ctrl+C in the terminal reaches only nrz, which forwards it. A background
+/// group reading the terminal would be stopped by SIGTTIN, so stdin is
+/// `/dev/null`: the framework's keyboard shortcuts are not available.
+pub struct DevServer {
+    child: Child,
+    /// Process group id, the same as the dev command's pid
+    #[cfg(unix)]
+    pgid: i32,
+}
+
+impl DevServer {
+    pub async fn wait(&mut self) -> std::io::Result<ExitStatus> {
+        self.child.wait().await
+    }
+
+    /// Send `signal` to the whole process group, wait for it to exit and
+    /// SIGKILL whatever is left after 5s. Also cleans up processes that
+    /// outlived an exited dev command. Windows has no signals, so the dev
+    /// command is killed at once.
+    pub async fn stop(&mut self, signal: Shutdown) {
+        #[cfg(unix)]
+        {
+            signal_group(self.pgid, signal.signo());
+            let deadline = tokio::time::Instant::now() + STOP_TIMEOUT;
+            // The dev command first, then children that outlive it
+            let _ = tokio::time::timeout_at(deadline, self.child.wait()).await;
+            while group_alive(self.pgid) && tokio::time::Instant::now() < deadline {
+                tokio::time::sleep(Duration::from_millis(50)).await;
             }
-            #[cfg(not(unix))]
-            {
-                let _ = child.kill().await;
+            if group_alive(self.pgid) {
+                tracing::warn!("dev server did not exit after 5s, force killing");
+                signal_group(self.pgid, libc::SIGKILL);
             }
+            let _ = self.child.wait().await;
         }
+        #[cfg(not(unix))]
+        {
+            let _ = signal;
+            let _ = self.child.kill().await;
+        }
+    }
+}
+
+#[cfg(unix)]
+fn signal_group(pgid: i32, signo: i32) {
+    unsafe {
+        libc::kill(-pgid, signo);
     }
+}
+
+/// Whether any process of the group is still running (or unreaped).
+#[cfg(unix)]
+fn group_alive(pgid: i32) -> bool {
+    unsafe { libc::kill(-pgid, 0) == 0 }
+}
 
-    Ok(())
+/// Resolves to the first SIGINT, SIGTERM or SIGHUP nrz receives. Unlike a
+/// fresh `ctrl_c()` future in each `select!`, the signal is not lost when
+/// it arrives between them. Handlers are installed before this returns.
+pub fn shutdown_signal() -> watch::Receiver<Option<Shutdown>> {
+    let (tx, rx) = watch::channel(None);
+    let signal = wait_for_signal();
+    tokio::spawn(async move {
+        let _ = tx.send(Some(signal.await));
+    });
+    rx
+}
+
+#[cfg(unix)]
+fn wait_for_signal() -> impl Future<Output = Shutdown> + Send + 'static {
+    use tokio::signal::unix::{SignalKind, signal};
+    let handlers = (
+        signal(SignalKind::interrupt()),
+        signal(SignalKind::terminate()),
+        signal(SignalKind::hangup()),
+    );
+    async move {
+        let (Ok(mut int), Ok(mut term), Ok(mut hup)) = handlers else {
+            tracing::warn!("failed to install signal handlers");
+            return std::future::pending().await;
+        };
+        tokio::select! {
+            _ = int.recv() => Shutdown::Interrupt,
+            _ = term.recv() => Shutdown::Terminate,
+            _ = hup.recv() => Shutdown::Hangup,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn wait_for_signal() -> impl Future<Output = Shutdown> + Send + 'static {
+    async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            tracing::warn!("failed to install Ctrl+C handler");
+            std::future::pending::<()>().await;
+        }
+        Shutdown::Interrupt
+    }
 }
@@ -0,0 +1,67 @@
+//! Unit tests for running and stopping the dev server
+
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use super::js_runtime::Launch;
+use super::process::{Shutdown, spawn_dev_server};
+
+fn alive(pid: i32) -> bool {
+    unsafe { libc::kill(pid, 0) == 0 }
+}
+
+#[tokio::test]
+async fn stop_signals_whole_process_group() {
+    let dir = tempfile::tempdir().unwrap();
+    let pid_file = dir.path().join("child.pid");
+    // Like npx: the real server is a grandchild that outlives a plain SIGTERM
+    let launch = Launch {
+        program: "sh".into(),
+        args: vec!["-c".into(), "sleep 30 & echo $! > child.pid; wait".into()],
+        env: Vec::new(),
+        display: String::new(),
+    };
+    let mut server = spawn_dev_server(dir.path(), &launch, 0).unwrap();
+
+    let mut grandchild = None;
+    for _ in 0..50 {
+        if let Ok(pid) = std::fs::read_to_string(&pid_file)
+            && let Ok(pid) = pid.trim().parse::<i32>()
+        {
+            grandchild = Some(pid);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let grandchild = grandchild.expect("dev command did not start");
+    assert!(alive(grandchild));
+
+    server.stop(Shutdown::Terminate).await;
+    assert!(!alive(grandchild));
+}
+
+#[tokio::test]
+async fn dev_server_reading_stdin_gets_eof() {
+    let dir = tempfile::tempdir().unwrap();
+    // Stopped by SIGTTIN, or blocked forever, if it could read the terminal
+    let launch = Launch {
+        program: "sh".into(),
+        args: vec![
+            "-c".into(),
+            "if test -t 0; then echo tty; else read line; echo eof $?; fi > stdin".into(),
+        ],
+        env: Vec::new(),
+        display: String::new(),
+    };
+    let mut server = spawn_dev_server(dir.path(), &launch, 0).unwrap();
+    let status = tokio::time::timeout(Duration::from_secs(5), server.wait())
+        .await
+        .expect("dev command blocked on stdin")
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("stdin")).unwrap(),
+        "eof 1\n"
+    );
+}
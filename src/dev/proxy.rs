@@ -0,0 +1,290 @@
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{HeaderMap, HeaderValue};
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+
+use nrz::emulator::activity::{ActivityKind, ActivityLog};
+
+/// Header carrying the request context (JSON) to the bootstrap.
+pub const CONTEXT_HEADER: &str = "x-nrz-context";
+/// Response header with the id of the proxied request.
+pub const REQUEST_ID_HEADER: &str = "x-nrz-request-id";
+/// Request header overriding the client IP.
+pub const CLIENT_IP_HEADER: &str = "x-nrz-client-ip";
+/// Request headers overriding geo fields: `x-nrz-geo-<field>`.
+pub const GEO_HEADER_PREFIX: &str = "x-nrz-geo-";
+
+type Body = BoxBody<Bytes, hyper::Error>;
+
+/// Geo location reported in `ONREZA.context.geo`. Unset fields fall back
+/// to the next source, then to placeholder values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Geo {
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub continent: Option<String>,
+    pub region: Option<String>,
+}
+
+impl Geo {
+    pub const FIELDS: &[&str] = &["country", "city", "continent", "region"];
+
+    /// Parse `--geo` values such as `country=DE`.
+    pub fn from_pairs(pairs: &[String]) -> anyhow::Result<Self> {
+        let mut geo = Self::default();
+        for pair in pairs {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("invalid --geo '{pair}': expected KEY=VALUE"))?;
+            geo.set(key.trim(), value.trim()).map_err(|_| {
+                anyhow::anyhow!(
+                    "invalid --geo key '{key}'. Expected one of: {}",
+                    Self::FIELDS.join(", ")
+                )
+            })?;
+        }
+        Ok(geo)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), ()> {
+        let field = match key {
+            "country" => &mut self.country,
+            "city" => &mut self.city,
+            "continent" => &mut self.continent,
+            "region" => &mut self.region,
+            _ => return Err(()),
+        };
+        *field = Some(value.to_string());
+        Ok(())
+    }
+
+    /// Fields from `self`, missing ones from `fallback`.
+    pub fn or(self, fallback: &Geo) -> Geo {
+        Geo {
+            country: self.country.or_else(|| fallback.country.clone()),
+            city: self.city.or_else(|| fallback.city.clone()),
+            continent: self.continent.or_else(|| fallback.continent.clone()),
+            region: self.region.or_else(|| fallback.region.clone()),
+        }
+    }
+
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let mut geo = Self::default();
+        for field in Self::FIELDS {
+            let name = format!("{GEO_HEADER_PREFIX}{field}");
+            if let Some(value) = headers.get(&name).and_then(|v| v.to_str().ok()) {
+                let _ = geo.set(field, value);
+            }
+        }
+        geo
+    }
+}
+
+/// Reverse proxy in front of the framework dev server.
+///
+/// Every request gets a fresh `ONREZA.context`: a request id, the client
+/// IP of the connection and geo from `x-nrz-geo-*` headers, `--geo` flags
+/// or `dev.geo` in the config. The context travels to the app in the
+/// [`CONTEXT_HEADER`] header, which the bootstrap reads and removes.
+/// Upgrades (Vite HMR websockets) are tunnelled through.
+pub struct Proxy {
+    /// Port the framework dev server listens on
+    pub upstream_port: u16,
+    /// Geo from flags and config, used when headers leave fields unset
+    pub geo: Geo,
+    pub project_id: String,
+    /// Requests are logged here for the emulator dashboard
+    pub activity: ActivityLog,
+}
+
+impl Proxy {
+    /// Accept connections on `listener` until the task is dropped.
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> anyhow::Result<()> {
+        loop {
+            let (stream, remote) = listener.accept().await?;
+            let proxy = self.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |req| proxy.clone().forward(req, remote));
+                if let Err(e) = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .with_upgrades()
+                    .await
+                {
+                    tracing::debug!(%e, "dev proxy connection closed");
+                }
+            });
+        }
+    }
+
+    async fn forward(
+        self: Arc<Self>,
+        mut req: Request<Incoming>,
+        remote: SocketAddr,
+    ) -> Result<Response<Body>, Infallible> {
+        let start = std::time::Instant::now();
+        let request_id = next_request_id();
+        let context = self.context(req.headers(), remote.ip(), &request_id);
+        let summary = format!("{} {}", req.method(), req.uri().path());
+
+        let headers = req.headers_mut();
+        let overrides: Vec<_> = headers
+            .keys()
+            .filter(|name| name.as_str().starts_with("x-nrz-"))
+            .cloned()
+            .collect();
+        for name in overrides {
+            headers.remove(name);
+        }
+        let context_header = HeaderValue::from_str(&ascii_json(&context))
+            .expect("ASCII JSON is a valid header value");
+        headers.insert(CONTEXT_HEADER, context_header);
+        if let Ok(ip) = HeaderValue::from_str(&remote.ip().to_string()) {
+            headers.append("x-forwarded-for", ip);
+        }
+        headers.insert("x-forwarded-proto", HeaderValue::from_static("http"));
+
+        let client_upgrade = req
+            .headers()
+            .contains_key(hyper::header::UPGRADE)
+            .then(|| hyper::upgrade::on(&mut req));
+
+        let mut resp = match self.send(req).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                let body = format!(
+                    "nrz: dev server on port {} is not reachable ({e}). \
+                     It may still be starting.\n",
+                    self.upstream_port
+                );
+                self.activity.record(
+                    ActivityKind::Request,
+                    format!("{summary} 502"),
+                    start.elapsed(),
+                    Some(e.to_string()),
+                    Some(context),
+                );
+                let mut resp = Response::new(full(body));
+                *resp.status_mut() = StatusCode::BAD_GATEWAY;
+                return Ok(resp);
+            }
+        };
+
+        if resp.status() == StatusCode::SWITCHING_PROTOCOLS
+            && let Some(client_upgrade) = client_upgrade
+        {
+            let upstream_upgrade = hyper::upgrade::on(&mut resp);
+            tokio::spawn(async move {
+                if let (Ok(client), Ok(upstream)) = tokio::join!(client_upgrade, upstream_upgrade) {
+                    let _ = tokio::io::copy_bidirectional(
+                        &mut TokioIo::new(client),
+                        &mut TokioIo::new(upstream),
+                    )
+                    .await;
+                }
+            });
+        }
+
+        self.activity.record(
+            ActivityKind::Request,
+            format!("{summary} {}", resp.status().as_u16()),
+            start.elapsed(),
+            None,
+            Some(context),
+        );
+        if let Ok(id) = HeaderValue::from_str(&request_id) {
+            resp.headers_mut().insert(REQUEST_ID_HEADER, id);
+        }
+        Ok(resp.map(BodyExt::boxed))
+    }
+
+    /// One connection per request; the upstream may listen on IPv4 or IPv6
+    /// loopback depending on how it resolves `localhost`.
+    async fn send(&self, req: Request<Incoming>) -> anyhow::Result<Response<Incoming>> {
+        let stream = match TcpStream::connect((Ipv4Addr::LOCALHOST, self.upstream_port)).await {
+            Ok(stream) => stream,
+            Err(_) => TcpStream::connect((Ipv6Addr::LOCALHOST, self.upstream_port)).await?,
+        };
+        let (mut sender, conn) =
+            hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+        tokio::spawn(async move {
+            if let Err(e) = conn.with_upgrades().await {
+                tracing::debug!(%e, "dev proxy upstream connection closed");
+            }
+        });
+        Ok(sender.send_request(req).await?)
+    }
+
+    /// Build `ONREZA.context` for a request.
+    pub fn context(
+        &self,
+        headers: &HeaderMap,
+        remote: IpAddr,
+        request_id: &str,
+    ) -> serde_json::Value {
+        let client_ip = headers
+            .get(CLIENT_IP_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+            .unwrap_or_else(|| remote.to_canonical().to_string());
+        let geo = Geo::from_headers(headers).or(&self.geo);
+        serde_json::json!({
+            "clientIp": client_ip,
+            "geo": {
+                "country": geo.country.as_deref().unwrap_or("XX"),
+                "city": geo.city.as_deref().unwrap_or("Local"),
+                "continent": geo.continent.as_deref().unwrap_or("XX"),
+                "region": geo.region.as_deref().unwrap_or("local"),
+            },
+            "deploymentId": "dev",
+            "projectId": self.project_id,
+            "commitSha": "dev",
+            "requestId": request_id,
+        })
+    }
+}
+
+/// Unique id per request: milliseconds since the epoch and a counter.
+fn next_request_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("dev-{millis:x}-{n:06x}")
+}
+
+/// Serialize JSON with non-ASCII characters as `\uXXXX` escapes, so it
+/// fits in a header value.
+pub fn ascii_json(value: &serde_json::Value) -> String {
+    let json = value.to_string();
+    let mut out = String::with_capacity(json.len());
+    for c in json.chars() {
+        if c.is_ascii() {
+            out.push(c);
+        } else {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                out.push_str(&format!("\\u{unit:04x}"));
+            }
+        }
+    }
+    out
+}
+
+fn full(body: String) -> Body {
+    Full::new(Bytes::from(body))
+        .map_err(|never| match never {})
+        .boxed()
+}
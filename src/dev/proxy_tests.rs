@@ -0,0 +1,190 @@
+//! Unit tests for the dev context proxy
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+
+use axum::Router;
+use axum::http::HeaderMap as AxumHeaders;
+use axum::routing::get;
+use hyper::header::{HeaderMap, HeaderValue};
+
+use nrz::emulator::activity::ActivityLog;
+
+use super::proxy::{CONTEXT_HEADER, Geo, Proxy, REQUEST_ID_HEADER, ascii_json};
+
+fn proxy(geo: Geo) -> Proxy {
+    Proxy {
+        upstream_port: 0,
+        geo,
+        project_id: "prj_1".into(),
+        activity: ActivityLog::new(),
+    }
+}
+
+#[test]
+fn context_uses_connection_ip_and_defaults() {
+    let ctx = proxy(Geo::default()).context(
+        &HeaderMap::new(),
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7)),
+        "req-1",
+    );
+    assert_eq!(ctx["clientIp"], "10.0.0.7");
+    assert_eq!(ctx["requestId"], "req-1");
+    assert_eq!(ctx["projectId"], "prj_1");
+    assert_eq!(ctx["geo"]["country"], "XX");
+    assert_eq!(ctx["geo"]["city"], "Local");
+}
+
+#[test]
+fn context_unwraps_ipv4_mapped_addresses() {
+    let mapped = Ipv4Addr::LOCALHOST.to_ipv6_mapped();
+    let ctx = proxy(Geo::default()).context(&HeaderMap::new(), IpAddr::V6(mapped), "r");
+    assert_eq!(ctx["clientIp"], "127.0.0.1");
+
+    let ctx =
+        proxy(Geo::default()).context(&HeaderMap::new(), IpAddr::V6(Ipv6Addr::LOCALHOST), "r");
+    assert_eq!(ctx["clientIp"], "::1");
+}
+
+#[test]
+fn headers_override_flags_and_config() {
+    let configured = Geo {
+        country: Some("DE".into()),
+        city: Some("Berlin".into()),
+        ..Default::default()
+    };
+    let mut headers = HeaderMap::new();
+    headers.insert("x-nrz-geo-city", HeaderValue::from_static("Hamburg"));
+    headers.insert("x-nrz-client-ip", HeaderValue::from_static("203.0.113.9"));
+
+    let ctx = proxy(configured).context(&headers, IpAddr::V4(Ipv4Addr::LOCALHOST), "r");
+    assert_eq!(ctx["clientIp"], "203.0.113.9");
+    assert_eq!(ctx["geo"]["country"], "DE");
+    assert_eq!(ctx["geo"]["city"], "Hamburg");
+    assert_eq!(ctx["geo"]["region"], "local");
+}
+
+#[test]
+fn geo_from_pairs() {
+    let geo = Geo::from_pairs(&["country=RU".into(), "city = Moscow".into()]).unwrap();
+    assert_eq!(geo.country.as_deref(), Some("RU"));
+    assert_eq!(geo.city.as_deref(), Some("Moscow"));
+    assert_eq!(geo.continent, None);
+}
+
+#[test]
+fn geo_from_pairs_rejects_bad_input() {
+    let err = Geo::from_pairs(&["country".into()]).unwrap_err();
+    assert!(err.to_string().contains("KEY=VALUE"), "{err}");
+    let err = Geo::from_pairs(&["planet=Mars".into()]).unwrap_err();
+    assert!(err.to_string().contains("country, city"), "{err}");
+}
+
+#[test]
+fn geo_or_fills_missing_fields() {
+    let flags = Geo {
+        country: Some("FR".into()),
+        ..Default::default()
+    };
+    let config = Geo {
+        country: Some("DE".into()),
+        region: Some("BE".into()),
+        ..Default::default()
+    };
+    let geo = flags.or(&config);
+    assert_eq!(geo.country.as_deref(), Some("FR"));
+    assert_eq!(geo.region.as_deref(), Some("BE"));
+    assert_eq!(geo.city, None);
+}
+
+#[test]
+fn ascii_json_escapes_non_ascii() {
+    let value = serde_json::json!({ "city": "Москва", "emoji": "😀" });
+    let json = ascii_json(&value);
+    assert!(json.is_ascii(), "{json}");
+    let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, value);
+}
+
+/// Upstream that echoes the context header it received
+async fn echo_upstream() -> u16 {
+    let app = Router::new().route(
+        "/",
+        get(|headers: AxumHeaders| async move {
+            let context = headers
+                .get(CONTEXT_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("null")
+                .to_string();
+            let spoofed = headers.contains_key("x-nrz-geo-country");
+            format!(r#"{{"context":{context},"spoofed":{spoofed}}}"#)
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    port
+}
+
+async fn start(proxy: Proxy) -> u16 {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(Arc::new(proxy).serve(listener));
+    port
+}
+
+#[tokio::test]
+async fn proxy_sends_fresh_context_per_request() {
+    let upstream_port = echo_upstream().await;
+    let port = start(Proxy {
+        upstream_port,
+        ..proxy(Geo::from_pairs(&["country=DE".into()]).unwrap())
+    })
+    .await;
+
+    let client = reqwest::Client::new();
+    let url = format!("http://127.0.0.1:{port}/");
+    let first = client.get(&url).send().await.unwrap();
+    let header_id = first.headers()[REQUEST_ID_HEADER]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let first: serde_json::Value = first.json().await.unwrap();
+    let second: serde_json::Value = client
+        .get(&url)
+        .header("x-nrz-geo-country", "JP")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(first["context"]["requestId"], header_id.as_str());
+    assert_ne!(
+        first["context"]["requestId"],
+        second["context"]["requestId"]
+    );
+    assert_eq!(first["context"]["clientIp"], "127.0.0.1");
+    assert_eq!(first["context"]["geo"]["country"], "DE");
+    assert_eq!(second["context"]["geo"]["country"], "JP");
+    // Override headers are consumed by the proxy
+    assert_eq!(second["spoofed"], false);
+}
+
+#[tokio::test]
+async fn proxy_reports_unreachable_upstream() {
+    let upstream_port = super::ports::free_port().unwrap();
+    let port = start(Proxy {
+        upstream_port,
+        ..proxy(Geo::default())
+    })
+    .await;
+
+    let resp = reqwest::get(format!("http://127.0.0.1:{port}/"))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 502);
+    let body = resp.text().await.unwrap();
+    assert!(body.contains(&format!("port {upstream_port}")), "{body}");
+}
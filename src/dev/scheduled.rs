@@ -0,0 +1,170 @@
+//! Scheduled (cron) handlers.
+//!
+//! Cron triggers come from `triggers.crons` in the project config, or else
+//! from the manifest of the last build. When one matches, nrz sends a
+//! [`ScheduledEvent`] to the dev server as a request to [`SCHEDULED_PATH`],
+//! through the context proxy like any other request. The bootstrap answers
+//! it by running the `scheduled` handler the app exports, as on the
+//! platform: from the dev server's main module, or else from the module
+//! set as `dev.scheduledEntry`. A handler registered with
+//! `ONREZA.onScheduled()` is the fallback for apps whose dev server loads
+//! their code some other way. Build output is never imported, since it
+//! lags behind the code being edited. `nrz trigger cron` sends the same request on
+//! demand.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use super::cron::{Cron, format_time};
+use crate::build;
+use crate::config::Config;
+
+/// Dev server path the bootstrap answers scheduled events on. Requests
+/// must carry the session token.
+pub const SCHEDULED_PATH: &str = "/__nrz/scheduled";
+
+/// Event passed to the scheduled handler, as the platform delivers it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledEvent {
+    /// Always `"scheduled"`
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Expression of the trigger that fired
+    pub cron: String,
+    /// Minute the trigger was due, in milliseconds since the Unix epoch
+    pub scheduled_time: i64,
+}
+
+impl ScheduledEvent {
+    pub fn new(cron: &Cron, scheduled_time: i64) -> Self {
+        Self {
+            kind: "scheduled".into(),
+            cron: cron.as_str().to_string(),
+            scheduled_time,
+        }
+    }
+}
+
+/// Body of the bootstrap's answer to a scheduled event.
+#[derive(Debug, Deserialize)]
+struct Outcome {
+    /// `ok`, `exception`, `noHandler` or `unauthorized`
+    outcome: String,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Cron triggers of the app: from the config, or else from the build
+/// manifest.
+pub fn triggers(project_dir: &Path, config: &Config) -> anyhow::Result<Vec<Cron>> {
+    let crons = if config.triggers.crons.is_empty() {
+        build::manifest_crons(project_dir, config)?
+    } else {
+        config.triggers.crons.clone()
+    };
+    crons.iter().map(|cron| Cron::parse(cron)).collect()
+}
+
+/// Send `event` to the dev server on `port` and wait for the scheduled
+/// handler to finish, including what it passed to `waitUntil`.
+pub async fn deliver(port: u16, token: &str, event: &ScheduledEvent) -> anyhow::Result<()> {
+    let resp = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{port}{SCHEDULED_PATH}"))
+        .bearer_auth(token)
+        .json(event)
+        .send()
+        .await
+        .with_context(|| format!("dev server not reachable on port {port}"))?;
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    match serde_json::from_str::<Outcome>(&body) {
+        Ok(outcome) if outcome.outcome == "ok" => Ok(()),
+        Ok(outcome) => anyhow::bail!(
+            "{}",
+            outcome
+                .error
+                .unwrap_or_else(|| format!("scheduled handler failed: {}", outcome.outcome))
+        ),
+        // The proxy's answer when the dev server is down
+        Err(_) if status == reqwest::StatusCode::BAD_GATEWAY => anyhow::bail!("{}", body.trim()),
+        Err(_) => anyhow::bail!(
+            "the dev server answered {status} instead of running the scheduled handler. \
+             Is the nrz bootstrap loaded into it? Restart `nrz dev` if it predates this nrz version"
+        ),
+    }
+}
+
+/// Deliver an event whenever one of `crons` matches, until the task is
+/// aborted or the sender dropped. The sender replaces the triggers, e.g.
+/// after a config change.
+pub fn schedule(
+    port: u16,
+    token: String,
+    mut crons: watch::Receiver<Vec<Cron>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        // Never fire a minute twice, even when the timer wakes up a little
+        // before the wall clock reaches it
+        let mut last = 0;
+        loop {
+            let now = now_millis();
+            let after = (now / 1000).max(last);
+            let next = crons
+                .borrow()
+                .iter()
+                .filter_map(|c| c.next_after(after))
+                .min();
+            let wait = match next {
+                Some(next) => Duration::from_millis((next * 1000 - now).max(0) as u64),
+                None => Duration::MAX,
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                changed = crons.changed() => match changed {
+                    Ok(()) => continue,
+                    Err(_) => return,
+                },
+            }
+            let Some(next) = next else { continue };
+            last = next;
+            let due: Vec<Cron> = crons
+                .borrow()
+                .iter()
+                .filter(|c| c.matches(next))
+                .cloned()
+                .collect();
+            for cron in due {
+                let token = token.clone();
+                tokio::spawn(async move {
+                    let start = Instant::now();
+                    let event = ScheduledEvent::new(&cron, next * 1000);
+                    match deliver(port, &token, &event).await {
+                        Ok(()) => eprintln!(
+                            "  {} cron {cron} ran ({}ms)",
+                            console::style("~").cyan().bold(),
+                            start.elapsed().as_millis(),
+                        ),
+                        Err(e) => eprintln!(
+                            "  {} cron {cron} at {} failed: {e:#}",
+                            console::style("!").yellow().bold(),
+                            format_time(event.scheduled_time),
+                        ),
+                    }
+                });
+            }
+        }
+    })
+}
+
+/// Current time in milliseconds since the Unix epoch.
+pub fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
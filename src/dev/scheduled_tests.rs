@@ -0,0 +1,107 @@
+//! Unit tests for scheduled event delivery
+
+use axum::Router;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+
+use super::cron::Cron;
+use super::scheduled::{SCHEDULED_PATH, ScheduledEvent, deliver, triggers};
+use crate::config::Config;
+
+const MANIFEST: &str = r#"{
+    "version": 1,
+    "adapter": { "name": "@onreza/adapter-node", "version": "0.1.0" },
+    "framework": { "name": "hono", "version": "4.0.0" },
+    "server": { "entry": "server.mjs", "export": "fetch" },
+    "assets": { "directory": "client", "prefix": "/" },
+    "routes": [{ "pattern": "/*", "type": "ssr" }],
+    "triggers": { "crons": ["0 * * * *"] }
+}"#;
+
+fn event() -> ScheduledEvent {
+    ScheduledEvent::new(&Cron::parse("*/5  * * * *").unwrap(), 1_767_225_600_000)
+}
+
+/// Dev server answering scheduled events with `status` and `body`, after
+/// checking the request
+async fn upstream(status: StatusCode, body: &'static str) -> u16 {
+    let app = Router::new().route(
+        SCHEDULED_PATH,
+        post(
+            move |headers: HeaderMap, event: axum::Json<ScheduledEvent>| async move {
+                assert_eq!(headers["authorization"], "Bearer secret");
+                assert_eq!(event.0, self::event());
+                (status, body)
+            },
+        ),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    port
+}
+
+#[test]
+fn event_has_the_platform_shape() {
+    assert_eq!(
+        serde_json::to_value(event()).unwrap(),
+        serde_json::json!({
+            "type": "scheduled",
+            "cron": "*/5 * * * *",
+            "scheduledTime": 1_767_225_600_000_i64,
+        })
+    );
+}
+
+#[tokio::test]
+async fn deliver_waits_for_the_handler() {
+    let port = upstream(StatusCode::OK, r#"{"outcome":"ok"}"#).await;
+    deliver(port, "secret", &event()).await.unwrap();
+}
+
+#[tokio::test]
+async fn deliver_reports_handler_errors() {
+    let port = upstream(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        r#"{"outcome":"exception","error":"[nrz] scheduled handler threw: boom"}"#,
+    )
+    .await;
+    let err = deliver(port, "secret", &event()).await.unwrap_err();
+    assert_eq!(err.to_string(), "[nrz] scheduled handler threw: boom");
+}
+
+#[tokio::test]
+async fn deliver_explains_a_dev_server_without_the_bootstrap() {
+    let port = upstream(StatusCode::NOT_FOUND, "<h1>Not found</h1>").await;
+    let err = deliver(port, "secret", &event()).await.unwrap_err();
+    assert!(err.to_string().contains("answered 404"), "{err}");
+    assert!(err.to_string().contains("bootstrap"), "{err}");
+
+    let port = super::ports::free_port().unwrap();
+    let err = deliver(port, "secret", &event()).await.unwrap_err();
+    assert!(err.to_string().contains("not reachable"), "{err}");
+}
+
+#[test]
+fn config_triggers_take_precedence_over_the_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("out/.onreza")).unwrap();
+    std::fs::write(dir.path().join("out/.onreza/manifest.json"), MANIFEST).unwrap();
+
+    let config = Config::parse(r#"{ "build": { "outputDir": "out" } }"#).unwrap();
+    let crons = triggers(dir.path(), &config).unwrap();
+    assert_eq!(crons, [Cron::parse("0 * * * *").unwrap()]);
+
+    let config = Config::parse(
+        r#"{ "build": { "outputDir": "out" }, "triggers": { "crons": ["0 9 * * *"] } }"#,
+    )
+    .unwrap();
+    let crons = triggers(dir.path(), &config).unwrap();
+    assert_eq!(crons, [Cron::parse("0 9 * * *").unwrap()]);
+}
+
+#[test]
+fn no_triggers_without_a_build() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(triggers(dir.path(), &Config::default()).unwrap().is_empty());
+}
@@ -0,0 +1,215 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::env::env_files;
+use super::js_runtime::Launch;
+use super::process::{Shutdown, shutdown_signal, spawn_dev_server};
+use crate::config::CONFIG_FILES;
+
+/// First restart delay after a crash; doubles with each crash in a row.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// A dev server that ran this long before crashing restarts without delay
+/// growth, as if it was its first crash.
+const STABLE_AFTER: Duration = Duration::from_secs(10);
+/// How often config and env files are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Restart delays after crashes in a row.
+#[derive(Debug, Default)]
+pub struct Backoff {
+    crashes: u32,
+}
+
+impl Backoff {
+    /// Delay before the next restart; counts the crash.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = INITIAL_BACKOFF
+            .saturating_mul(2u32.saturating_pow(self.crashes))
+            .min(MAX_BACKOFF);
+        self.crashes += 1;
+        delay
+    }
+
+    /// Crashes in a row so far.
+    pub fn crashes(&self) -> u32 {
+        self.crashes
+    }
+
+    pub fn reset(&mut self) {
+        self.crashes = 0;
+    }
+}
+
+/// Files that change `ONREZA.env` or the bootstrap: the project config,
+/// env files for `mode` and `.dev.vars`.
+pub fn watched_files(project_dir: &Path, mode: &str) -> Vec<PathBuf> {
+    CONFIG_FILES
+        .iter()
+        .map(|f| f.to_string())
+        .chain(env_files(mode))
+        .map(|f| project_dir.join(f))
+        .collect()
+}
+
+/// Modification time and size of each file; `None` for missing ones.
+type Snapshot = Vec<Option<(SystemTime, u64)>>;
+
+/// Polls [`watched_files`] for changes.
+pub struct ConfigWatcher {
+    files: Vec<PathBuf>,
+    snapshot: Snapshot,
+}
+
+impl ConfigWatcher {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        let snapshot = snapshot(&files);
+        Self { files, snapshot }
+    }
+
+    /// Name of the first file that changed since the last check, if any.
+    pub fn check(&mut self) -> Option<String> {
+        let current = snapshot(&self.files);
+        let changed = self
+            .files
+            .iter()
+            .zip(current.iter().zip(&self.snapshot))
+            .find(|(_, (now, before))| now != before)
+            .map(|(path, _)| {
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            });
+        self.snapshot = current;
+        changed
+    }
+
+    /// Wait until a file changes and return its name.
+    pub async fn changed(&mut self) -> String {
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+            if let Some(file) = self.check() {
+                return file;
+            }
+        }
+    }
+}
+
+fn snapshot(files: &[PathBuf]) -> Snapshot {
+    files
+        .iter()
+        .map(|path| {
+            let meta = std::fs::metadata(path).ok()?;
+            Some((meta.modified().ok()?, meta.len()))
+        })
+        .collect()
+}
+
+/// What ended a dev server run.
+enum Event {
+    Exited(ExitStatus),
+    Changed(String),
+    Shutdown(Shutdown),
+}
+
+/// Run the dev server until nrz gets a shutdown signal, keeping the
+/// emulator alive across restarts.
+///
+/// Crashes restart it with exponential backoff, or end the session when
+/// `restart` is off. Changes to config and env files restart it at once,
+/// with the bootstrap (and `ONREZA.env` in it) rebuilt by `reload`; if
+/// `reload` fails, the previous bootstrap is kept. A clean exit ends the
+/// session.
+pub async fn supervise(
+    project_dir: &Path,
+    mode: &str,
+    launch: &Launch,
+    port: u16,
+    restart: bool,
+    mut reload: impl FnMut() -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut shutdown = shutdown_signal();
+    let mut watcher = ConfigWatcher::new(watched_files(project_dir, mode));
+    let mut backoff = Backoff::default();
+
+    loop {
+        let started = Instant::now();
+        let mut server = spawn_dev_server(project_dir, launch, port)?;
+        let event = tokio::select! {
+            status = server.wait() => Event::Exited(status?),
+            file = watcher.changed() => Event::Changed(file),
+            _ = shutdown.changed() => {
+                Event::Shutdown(shutdown.borrow().unwrap_or(Shutdown::Interrupt))
+            }
+        };
+
+        let changed = match event {
+            Event::Shutdown(signal) => {
+                tracing::info!(?signal, "shutting down dev server...");
+                server.stop(signal).await;
+                return Ok(());
+            }
+            Event::Changed(file) => {
+                eprintln!(
+                    "  {} {file} changed, restarting dev server",
+                    console::style("~").cyan().bold(),
+                );
+                server.stop(Shutdown::Terminate).await;
+                backoff.reset();
+                Some(file)
+            }
+            Event::Exited(status) => {
+                // Processes the dev command left behind
+                server.stop(Shutdown::Terminate).await;
+                if status.success() {
+                    return Ok(());
+                }
+                // On Windows Ctrl+C reaches the dev server too, which may
+                // exit before our own handler runs
+                if shutdown.borrow().is_some()
+                    || tokio::time::timeout(Duration::from_millis(200), shutdown.changed())
+                        .await
+                        .is_ok()
+                {
+                    return Ok(());
+                }
+                if !restart {
+                    anyhow::bail!("dev server exited with {status}");
+                }
+                if started.elapsed() >= STABLE_AFTER {
+                    backoff.reset();
+                }
+                let delay = backoff.next_delay();
+                eprintln!(
+                    "  {} dev server exited with {status}, restarting in {:.1}s (crash {} in a row)",
+                    console::style("!").yellow().bold(),
+                    delay.as_secs_f64(),
+                    backoff.crashes(),
+                );
+                tokio::select! {
+                    () = tokio::time::sleep(delay) => None,
+                    file = watcher.changed() => {
+                        eprintln!(
+                            "  {} {file} changed, restarting dev server",
+                            console::style("~").cyan().bold(),
+                        );
+                        backoff.reset();
+                        Some(file)
+                    }
+                    _ = shutdown.changed() => return Ok(()),
+                }
+            }
+        };
+
+        if changed.is_some()
+            && let Err(e) = reload()
+        {
+            eprintln!(
+                "  {} {e:#}. Keeping the previous config",
+                console::style("!").yellow().bold(),
+            );
+        }
+    }
+}
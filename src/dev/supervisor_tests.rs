@@ -0,0 +1,153 @@
+//! Unit tests for dev server supervision
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::env::load_env;
+use super::js_runtime::Launch;
+use super::supervisor::{Backoff, ConfigWatcher, supervise, watched_files};
+
+#[test]
+fn backoff_doubles_up_to_limit() {
+    let mut backoff = Backoff::default();
+    let delays: Vec<_> = (0..8).map(|_| backoff.next_delay()).collect();
+    assert_eq!(delays[0], Duration::from_millis(500));
+    assert_eq!(delays[1], Duration::from_secs(1));
+    assert_eq!(delays[2], Duration::from_secs(2));
+    assert_eq!(delays[7], Duration::from_secs(30));
+    assert_eq!(backoff.crashes(), 8);
+
+    backoff.reset();
+    assert_eq!(backoff.next_delay(), Duration::from_millis(500));
+}
+
+#[test]
+fn watched_files_cover_config_and_env() {
+    let dir = tempfile::tempdir().unwrap();
+    let names: Vec<String> = watched_files(dir.path(), "staging")
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    for name in [
+        "onreza.jsonc",
+        "onreza.json",
+        ".env",
+        ".env.staging.local",
+        ".dev.vars",
+    ] {
+        assert!(names.iter().any(|n| n == name), "{name} in {names:?}");
+    }
+}
+
+#[test]
+fn watcher_reports_created_changed_and_removed_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = dir.path().join(".env");
+    let mut watcher = ConfigWatcher::new(watched_files(dir.path(), "development"));
+    assert_eq!(watcher.check(), None);
+
+    std::fs::write(&env, "A=1\n").unwrap();
+    assert_eq!(watcher.check().as_deref(), Some(".env"));
+    assert_eq!(watcher.check(), None);
+
+    // Same mtime granularity, different size
+    std::fs::write(&env, "A=12\n").unwrap();
+    assert_eq!(watcher.check().as_deref(), Some(".env"));
+
+    std::fs::remove_file(&env).unwrap();
+    assert_eq!(watcher.check().as_deref(), Some(".env"));
+}
+
+#[cfg(unix)]
+fn sh(script: &str) -> Launch {
+    Launch {
+        program: "sh".into(),
+        args: vec!["-c".into(), script.into()],
+        env: Vec::new(),
+        display: script.into(),
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn crash_without_restart_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let err = supervise(
+        dir.path(),
+        "development",
+        &sh("exit 3"),
+        0,
+        false,
+        || unreachable!(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("dev server exited with"), "{err}");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn crash_is_restarted_until_clean_exit() {
+    let dir = tempfile::tempdir().unwrap();
+    // Fails on the first run, succeeds on the second
+    let launch = sh("test -f started && exit 0; touch started; exit 1");
+    supervise(
+        dir.path(),
+        "development",
+        &launch,
+        0,
+        true,
+        || unreachable!(),
+    )
+    .await
+    .unwrap();
+    assert!(dir.path().join("started").exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn env_change_restarts_with_the_reloaded_vars() {
+    let dir = tempfile::tempdir().unwrap();
+    let write_vars = |dir: &std::path::Path| -> anyhow::Result<()> {
+        let vars = load_env(dir, "development", &BTreeMap::new())?;
+        let line: Vec<String> = vars
+            .iter()
+            .map(|(k, v)| format!("{k}={}", v.value))
+            .collect();
+        std::fs::write(dir.join("vars"), line.join(" ") + "\n")?;
+        Ok(())
+    };
+    std::fs::write(dir.path().join(".env"), "GREETING=hello\n").unwrap();
+    write_vars(dir.path()).unwrap();
+
+    // Records the vars it starts with, and keeps running until it gets
+    // the edited ones
+    let launch = sh("cat vars >> runs; grep -q howdy vars && exit 0; exec sleep 30");
+    let edit = {
+        let dir = dir.path().to_path_buf();
+        tokio::spawn(async move {
+            while !dir.join("runs").exists() {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            std::fs::write(dir.join(".env"), "GREETING=howdy\nNAME=nrz\n").unwrap();
+        })
+    };
+    let mut reloads = 0;
+    tokio::time::timeout(
+        Duration::from_secs(20),
+        supervise(dir.path(), "development", &launch, 0, false, || {
+            reloads += 1;
+            write_vars(dir.path())
+        }),
+    )
+    .await
+    .expect("dev server was not restarted with the new vars")
+    .unwrap();
+    edit.await.unwrap();
+
+    assert_eq!(reloads, 1);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("runs")).unwrap(),
+        "GREETING=hello\nGREETING=howdy NAME=nrz\n"
+    );
+}
@@ -0,0 +1,364 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use super::detect::{detect_framework, detect_framework_with};
+
+/// A monorepo: the root directory and its member packages.
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub packages: Vec<Package>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    /// `name` from package.json, or the directory name
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+impl Workspace {
+    /// Member packages with a detectable framework in their own
+    /// dependencies. Root dependencies are ignored here, or every library
+    /// would look like an app.
+    pub fn apps(&self) -> Vec<&Package> {
+        self.packages
+            .iter()
+            .filter(|p| detect_framework_with(&p.dir, None).is_ok())
+            .collect()
+    }
+
+    /// Find a package by name, path relative to the root, or directory name.
+    pub fn find(&self, app: &str) -> Option<&Package> {
+        let app = app.trim_end_matches('/');
+        self.packages
+            .iter()
+            .find(|p| p.name == app)
+            .or_else(|| {
+                self.packages.iter().find(|p| {
+                    p.dir
+                        .strip_prefix(&self.root)
+                        .is_ok_and(|rel| rel == Path::new(app))
+                })
+            })
+            .or_else(|| {
+                self.packages
+                    .iter()
+                    .find(|p| p.dir.file_name().is_some_and(|n| n == app))
+            })
+    }
+
+    fn describe(&self, package: &Package) -> String {
+        let rel = package.dir.strip_prefix(&self.root).unwrap_or(&package.dir);
+        format!("{} ({})", package.name, rel.display())
+    }
+}
+
+/// Find the workspace that contains `start`, looking in `start` and its
+/// ancestors.
+///
+/// A workspace root has a `pnpm-workspace.yaml`, a package.json with
+/// `workspaces` (npm, yarn, bun), or a `turbo.json` / `nx.json`. Turborepo
+/// builds on the package manager's workspaces; Nx layouts without them
+/// default to `apps/*`, `packages/*` and `libs/*`.
+pub fn find_workspace(start: &Path) -> Option<Workspace> {
+    start.ancestors().find_map(|dir| {
+        let patterns = workspace_patterns(dir)?;
+        Some(Workspace {
+            root: dir.to_path_buf(),
+            packages: expand_patterns(dir, &patterns),
+        })
+    })
+}
+
+/// Like [`find_workspace`], without listing the packages.
+fn find_root(start: &Path) -> Option<&Path> {
+    start
+        .ancestors()
+        .find(|dir| workspace_patterns(dir).is_some())
+}
+
+/// Resolve the project directory for a command run in `dir`.
+///
+/// With `--app`, the app is looked up in the enclosing workspace. Without
+/// it, `dir` is used unless it is a workspace root that is not an app
+/// itself; then the only app is picked, or the user is asked to choose.
+pub fn resolve_project_dir(dir: &Path, app: Option<&str>) -> anyhow::Result<PathBuf> {
+    let workspace = find_workspace(dir);
+
+    if let Some(app) = app {
+        let workspace = workspace.with_context(|| {
+            format!(
+                "--app requires a workspace, but none was found at or above {}. \
+                 Expected pnpm-workspace.yaml or package.json \"workspaces\"",
+                dir.display()
+            )
+        })?;
+        let package = workspace.find(app).with_context(|| {
+            let names: Vec<String> = workspace.packages.iter().map(|p| p.name.clone()).collect();
+            format!(
+                "app '{app}' not found in workspace {}. Available: {}",
+                workspace.root.display(),
+                if names.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    names.join(", ")
+                }
+            )
+        })?;
+        announce(&workspace, package);
+        return Ok(package.dir.clone());
+    }
+
+    let Some(workspace) = workspace.filter(|w| w.root == dir) else {
+        return Ok(dir.to_path_buf());
+    };
+    // A single-package repo can declare workspaces and still be the app
+    if detect_framework(dir).is_ok() {
+        return Ok(dir.to_path_buf());
+    }
+
+    let apps = workspace.apps();
+    let package = match apps.as_slice() {
+        [] => return Ok(dir.to_path_buf()),
+        [only] => *only,
+        _ if std::io::stdin().is_terminal() && std::io::stderr().is_terminal() => {
+            choose(&workspace, &apps)?
+        }
+        _ => {
+            let names: Vec<&str> = apps.iter().map(|p| p.name.as_str()).collect();
+            anyhow::bail!(
+                "{} is a workspace with several apps: {}. Pick one with --app <name>",
+                dir.display(),
+                names.join(", ")
+            );
+        }
+    };
+    announce(&workspace, package);
+    Ok(package.dir.clone())
+}
+
+fn announce(workspace: &Workspace, package: &Package) {
+    eprintln!(
+        "  {} app {}",
+        console::style("~").cyan().bold(),
+        workspace.describe(package),
+    );
+}
+
+/// Ask which app to use on the terminal.
+fn choose<'a>(workspace: &Workspace, apps: &[&'a Package]) -> anyhow::Result<&'a Package> {
+    eprintln!("  Several apps found in {}:", workspace.root.display());
+    for (i, app) in apps.iter().enumerate() {
+        eprintln!("    {}) {}", i + 1, workspace.describe(app));
+    }
+    loop {
+        eprint!("  Select an app [1-{}]: ", apps.len());
+        std::io::stderr().flush()?;
+        let mut line = String::new();
+        if std::io::stdin().lock().read_line(&mut line)? == 0 {
+            anyhow::bail!("no app selected. Pick one with --app <name>");
+        }
+        let choice = line.trim();
+        if let Some(app) = choice
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| apps.get(i))
+            .or_else(|| apps.iter().find(|p| p.name == choice))
+        {
+            return Ok(app);
+        }
+    }
+}
+
+/// `node_modules/.bin` directories from `project_dir` up to the workspace
+/// root, nearest first. Hoisted binaries live in the root's directory.
+pub fn bin_dirs(project_dir: &Path) -> Vec<PathBuf> {
+    let root = find_root(project_dir);
+    let mut dirs = Vec::new();
+    for dir in project_dir.ancestors() {
+        let bin = dir.join("node_modules").join(".bin");
+        if bin.is_dir() {
+            dirs.push(bin);
+        }
+        if root.is_none_or(|r| r == dir) {
+            break;
+        }
+    }
+    dirs
+}
+
+/// package.json of the enclosing workspace root, if `project_dir` is a
+/// member package. Used to find hoisted dependencies.
+pub fn root_package_json(project_dir: &Path) -> Option<serde_json::Value> {
+    let root = find_root(project_dir)?;
+    if root == project_dir {
+        return None;
+    }
+    let content = std::fs::read_to_string(root.join("package.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn workspace_patterns(dir: &Path) -> Option<Vec<String>> {
+    if let Ok(yaml) = std::fs::read_to_string(dir.join("pnpm-workspace.yaml")) {
+        return Some(parse_pnpm_workspace(&yaml));
+    }
+
+    let pkg = std::fs::read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
+    if let Some(workspaces) = pkg.as_ref().and_then(|p| p.get("workspaces")) {
+        // Either ["apps/*"] or { "packages": ["apps/*"] } (yarn classic)
+        let list = workspaces.get("packages").unwrap_or(workspaces);
+        let patterns = list
+            .as_array()
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        return Some(patterns);
+    }
+
+    if dir.join("nx.json").is_file() || dir.join("turbo.json").is_file() {
+        return Some(vec!["apps/*".into(), "packages/*".into(), "libs/*".into()]);
+    }
+    None
+}
+
+/// Read the `packages:` list of a pnpm-workspace.yaml.
+///
+/// Only handles the block-list form pnpm documents; other keys are skipped.
+pub fn parse_pnpm_workspace(yaml: &str) -> Vec<String> {
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in yaml.lines() {
+        let content = line.split(" #").next().unwrap_or("").trim_end();
+        if content.trim().is_empty() || content.trim_start().starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '\t', '-']) {
+            in_packages = content.trim() == "packages:";
+            continue;
+        }
+        if let Some(item) = content.trim().strip_prefix('-').filter(|_| in_packages) {
+            let item = item.trim().trim_matches(|c| c == '"' || c == '\'');
+            if !item.is_empty() {
+                patterns.push(item.to_string());
+            }
+        }
+    }
+    patterns
+}
+
+/// Expand workspace globs (`*`, `**`, `!exclude`) to package directories.
+fn expand_patterns(root: &Path, patterns: &[String]) -> Vec<Package> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for pattern in patterns.iter().filter(|p| !p.starts_with('!')) {
+        let segments: Vec<&str> = pattern
+            .trim_start_matches("./")
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        walk(root, &segments, &mut dirs);
+    }
+    for pattern in patterns.iter().filter_map(|p| p.strip_prefix('!')) {
+        let segments: Vec<&str> = pattern
+            .trim_start_matches("./")
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        let mut excluded = Vec::new();
+        walk(root, &segments, &mut excluded);
+        dirs.retain(|d| !excluded.contains(d));
+    }
+    dirs.sort();
+    dirs.dedup();
+
+    dirs.into_iter()
+        .filter(|d| d.join("package.json").is_file())
+        .map(|dir| {
+            let name = std::fs::read_to_string(dir.join("package.json"))
+                .ok()
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                .and_then(|p| p.get("name")?.as_str().map(String::from))
+                .unwrap_or_else(|| {
+                    dir.file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                });
+            Package { name, dir }
+        })
+        .collect()
+}
+
+fn walk(dir: &Path, segments: &[&str], out: &mut Vec<PathBuf>) {
+    let Some((first, rest)) = segments.split_first() else {
+        out.push(dir.to_path_buf());
+        return;
+    };
+    if *first == "**" {
+        // Zero directories, or one more and stay on `**`
+        walk(dir, rest, out);
+        for child in subdirs(dir) {
+            walk(&child, segments, out);
+        }
+        return;
+    }
+    if !first.contains('*') {
+        let child = dir.join(first);
+        if child.is_dir() {
+            walk(&child, rest, out);
+        }
+        return;
+    }
+    for child in subdirs(dir) {
+        let name = child.file_name().map(|n| n.to_string_lossy().into_owned());
+        if name.is_some_and(|n| wildcard_match(first, &n)) {
+            walk(&child, rest, out);
+        }
+    }
+}
+
+/// Child directories, skipping hidden ones and node_modules.
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_dir())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| !n.starts_with('.') && n != "node_modules")
+        })
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Match a single path segment against a pattern where `*` matches any run
+/// of characters.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !name.starts_with(first) || name.len() < first.len() + last.len() {
+        return false;
+    }
+    if parts.len() == 1 {
+        return name == pattern;
+    }
+    let mut rest = &name[first.len()..];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
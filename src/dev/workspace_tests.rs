@@ -0,0 +1,199 @@
+//! Unit tests for workspace discovery
+
+use std::path::Path;
+
+use super::detect::{FrameworkName, detect_framework};
+use super::workspace::{bin_dirs, find_workspace, parse_pnpm_workspace, resolve_project_dir};
+
+fn write(path: &Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+/// apps/web (astro), apps/admin (nuxt), packages/ui (library)
+fn monorepo(root: &Path) {
+    write(
+        &root.join("apps/web/package.json"),
+        r#"{"name":"@acme/web","dependencies":{"astro":"^4.0"}}"#,
+    );
+    write(
+        &root.join("apps/admin/package.json"),
+        r#"{"name":"@acme/admin","dependencies":{"nuxt":"^3.0"}}"#,
+    );
+    write(
+        &root.join("packages/ui/package.json"),
+        r#"{"name":"@acme/ui","devDependencies":{"typescript":"^5.0"}}"#,
+    );
+}
+
+#[test]
+fn parse_pnpm_workspace_yaml() {
+    let yaml = "# workspace\npackages:\n  - 'apps/*'\n  - \"packages/**\" # libs\n  - '!**/test/**'\ncatalog:\n  react: ^19\n";
+    assert_eq!(
+        parse_pnpm_workspace(yaml),
+        vec!["apps/*", "packages/**", "!**/test/**"]
+    );
+}
+
+#[test]
+fn pnpm_workspace_packages() {
+    let root = tempfile::tempdir().unwrap();
+    monorepo(root.path());
+    write(
+        &root.path().join("pnpm-workspace.yaml"),
+        "packages:\n  - apps/*\n  - packages/*\n",
+    );
+
+    let ws = find_workspace(&root.path().join("apps/web")).unwrap();
+    assert_eq!(ws.root, root.path());
+    let names: Vec<&str> = ws.packages.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["@acme/admin", "@acme/web", "@acme/ui"]);
+}
+
+#[test]
+fn package_json_workspaces() {
+    for workspaces in [r#"["apps/*"]"#, r#"{"packages":["apps/*"]}"#] {
+        let root = tempfile::tempdir().unwrap();
+        monorepo(root.path());
+        write(
+            &root.path().join("package.json"),
+            &format!(r#"{{"private":true,"workspaces":{workspaces}}}"#),
+        );
+        let ws = find_workspace(root.path()).unwrap();
+        assert_eq!(ws.packages.len(), 2, "{workspaces}");
+    }
+}
+
+#[test]
+fn recursive_globs_and_exclusions() {
+    let root = tempfile::tempdir().unwrap();
+    monorepo(root.path());
+    write(
+        &root
+            .path()
+            .join("packages/ui/node_modules/dep/package.json"),
+        "{}",
+    );
+    write(
+        &root.path().join("package.json"),
+        r#"{"workspaces":["**","!apps/admin"]}"#,
+    );
+    let ws = find_workspace(root.path()).unwrap();
+    let names: Vec<&str> = ws.packages.iter().map(|p| p.name.as_str()).collect();
+    // The root itself matches `**`; node_modules is never searched
+    assert_eq!(names.len(), 3, "{names:?}");
+    assert!(names.contains(&"@acme/web"));
+    assert!(names.contains(&"@acme/ui"));
+    assert!(!names.contains(&"@acme/admin"));
+}
+
+#[test]
+fn nx_layout_without_workspaces() {
+    let root = tempfile::tempdir().unwrap();
+    monorepo(root.path());
+    write(&root.path().join("nx.json"), "{}");
+    let ws = find_workspace(root.path()).unwrap();
+    assert_eq!(ws.packages.len(), 3);
+}
+
+#[test]
+fn apps_are_packages_with_a_framework() {
+    let root = tempfile::tempdir().unwrap();
+    monorepo(root.path());
+    write(
+        &root.path().join("package.json"),
+        r#"{"workspaces":["apps/*"],"devDependencies":{"vite":"^6.0"}}"#,
+    );
+    write(
+        &root.path().join("apps/docs/package.json"),
+        r#"{"name":"docs"}"#,
+    );
+    let ws = find_workspace(root.path()).unwrap();
+    let apps: Vec<&str> = ws.apps().iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(apps, vec!["@acme/admin", "@acme/web"]);
+}
+
+#[test]
+fn find_app_by_name_path_or_directory() {
+    let root = tempfile::tempdir().unwrap();
+    monorepo(root.path());
+    write(
+        &root.path().join("pnpm-workspace.yaml"),
+        "packages:\n  - apps/*\n",
+    );
+    let web = root.path().join("apps/web");
+
+    for app in ["@acme/web", "apps/web", "apps/web/", "web"] {
+        assert_eq!(
+            resolve_project_dir(root.path(), Some(app)).unwrap(),
+            web,
+            "{app}"
+        );
+    }
+
+    let err = resolve_project_dir(root.path(), Some("nope")).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Available: @acme/admin, @acme/web")
+    );
+}
+
+#[test]
+fn single_app_is_picked_from_root() {
+    let root = tempfile::tempdir().unwrap();
+    monorepo(root.path());
+    write(
+        &root.path().join("package.json"),
+        r#"{"workspaces":["apps/web","packages/*"]}"#,
+    );
+    assert_eq!(
+        resolve_project_dir(root.path(), None).unwrap(),
+        root.path().join("apps/web")
+    );
+}
+
+#[test]
+fn non_workspace_dir_is_used_as_is() {
+    let dir = tempfile::tempdir().unwrap();
+    write(&dir.path().join("package.json"), r#"{"name":"site"}"#);
+    assert_eq!(resolve_project_dir(dir.path(), None).unwrap(), dir.path());
+
+    let err = resolve_project_dir(dir.path(), Some("web")).unwrap_err();
+    assert!(err.to_string().contains("--app requires a workspace"));
+}
+
+#[test]
+fn hoisted_framework_dependency() {
+    let root = tempfile::tempdir().unwrap();
+    write(
+        &root.path().join("package.json"),
+        r#"{"workspaces":["apps/*"],"devDependencies":{"astro":"^4.0"}}"#,
+    );
+    write(
+        &root.path().join("apps/site/package.json"),
+        r#"{"name":"site"}"#,
+    );
+    let fw = detect_framework(&root.path().join("apps/site")).unwrap();
+    assert_eq!(fw.name, FrameworkName::Astro);
+}
+
+#[test]
+fn bin_dirs_include_workspace_root() {
+    let root = tempfile::tempdir().unwrap();
+    monorepo(root.path());
+    write(
+        &root.path().join("pnpm-workspace.yaml"),
+        "packages:\n  - apps/*\n",
+    );
+    let web = root.path().join("apps/web");
+    std::fs::create_dir_all(web.join("node_modules/.bin")).unwrap();
+    std::fs::create_dir_all(root.path().join("node_modules/.bin")).unwrap();
+
+    assert_eq!(
+        bin_dirs(&web),
+        vec![
+            web.join("node_modules/.bin"),
+            root.path().join("node_modules/.bin")
+        ]
+    );
+}
@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+/// Entries kept in the log; older ones are dropped.
+pub const CAPACITY: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityKind {
+    /// App request through the dev proxy
+    Request,
+    Kv,
+    Db,
+}
+
+/// One logged request, KV operation or SQL query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Activity {
+    /// Increasing id, for polling with [`ActivityLog::since`]
+    pub id: u64,
+    /// Unix time in milliseconds
+    pub time: u64,
+    pub kind: ActivityKind,
+    /// `GET /path 200`, `kv.get key` or the SQL text
+    pub summary: String,
+    pub duration_ms: f64,
+    pub error: Option<String>,
+    /// Extra data: the request's `ONREZA.context`, query meta
+    pub detail: Option<serde_json::Value>,
+}
+
+/// Bounded in-memory log of recent emulator activity, shared between the
+/// emulator server and the dev proxy.
+#[derive(Clone, Default)]
+pub struct ActivityLog {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    entries: VecDeque<Activity>,
+}
+
+impl ActivityLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(
+        &self,
+        kind: ActivityKind,
+        summary: impl Into<String>,
+        duration: std::time::Duration,
+        error: Option<String>,
+        detail: Option<serde_json::Value>,
+    ) {
+        let time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let mut inner = self.inner.lock().unwrap_or_else(|poisoned| {
+            tracing::warn!("activity log mutex was poisoned, recovering");
+            poisoned.into_inner()
+        });
+        inner.next_id += 1;
+        let activity = Activity {
+            id: inner.next_id,
+            time,
+            kind,
+            summary: summary.into(),
+            duration_ms: duration.as_secs_f64() * 1000.0,
+            error,
+            detail,
+        };
+        if inner.entries.len() == CAPACITY {
+            inner.entries.pop_front();
+        }
+        inner.entries.push_back(activity);
+    }
+
+    /// Entries with an id greater than `id`, oldest first.
+    pub fn since(&self, id: u64) -> Vec<Activity> {
+        let inner = self.inner.lock().unwrap_or_else(|poisoned| {
+            tracing::warn!("activity log mutex was poisoned, recovering");
+            poisoned.into_inner()
+        });
+        inner
+            .entries
+            .iter()
+            .filter(|a| a.id > id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| {
+                tracing::warn!("activity log mutex was poisoned, recovering");
+                poisoned.into_inner()
+            })
+            .entries
+            .clear();
+    }
+}
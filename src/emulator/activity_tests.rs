@@ -0,0 +1,73 @@
+//! Unit tests for the emulator activity log
+
+use std::time::Duration;
+
+use super::activity::{ActivityKind, ActivityLog, CAPACITY};
+
+#[test]
+fn since_returns_newer_entries() {
+    let log = ActivityLog::new();
+    log.record(ActivityKind::Kv, "kv.get a", Duration::ZERO, None, None);
+    log.record(
+        ActivityKind::Db,
+        "SELECT 1",
+        Duration::from_millis(2),
+        None,
+        None,
+    );
+
+    let all = log.since(0);
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0].summary, "kv.get a");
+    assert_eq!(all[1].kind, ActivityKind::Db);
+    assert_eq!(all[1].duration_ms, 2.0);
+
+    let newer = log.since(all[0].id);
+    assert_eq!(newer.len(), 1);
+    assert_eq!(newer[0].summary, "SELECT 1");
+}
+
+#[test]
+fn log_is_bounded() {
+    let log = ActivityLog::new();
+    for i in 0..CAPACITY + 10 {
+        log.record(
+            ActivityKind::Kv,
+            format!("kv.get {i}"),
+            Duration::ZERO,
+            None,
+            None,
+        );
+    }
+    let all = log.since(0);
+    assert_eq!(all.len(), CAPACITY);
+    assert_eq!(all[0].summary, "kv.get 10");
+}
+
+#[test]
+fn clear_keeps_ids_increasing() {
+    let log = ActivityLog::new();
+    log.record(ActivityKind::Kv, "kv.get a", Duration::ZERO, None, None);
+    let first = log.since(0)[0].id;
+    log.clear();
+    assert!(log.since(0).is_empty());
+    log.record(ActivityKind::Kv, "kv.get b", Duration::ZERO, None, None);
+    assert!(log.since(first).len() == 1);
+}
+
+#[test]
+fn serializes_camel_case() {
+    let log = ActivityLog::new();
+    log.record(
+        ActivityKind::Request,
+        "GET / 200",
+        Duration::ZERO,
+        Some("boom".into()),
+        Some(serde_json::json!({ "requestId": "r" })),
+    );
+    let json = serde_json::to_value(&log.since(0)[0]).unwrap();
+    assert_eq!(json["kind"], "request");
+    assert_eq!(json["error"], "boom");
+    assert_eq!(json["detail"]["requestId"], "r");
+    assert!(json.get("durationMs").is_some());
+}
@@ -0,0 +1,98 @@
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+/// Query parameter carrying the token in the dashboard URL, where a
+/// browser cannot set headers.
+pub const TOKEN_PARAM: &str = "token";
+
+/// Random secret for one emulator session, as 64 hex characters.
+pub fn session_token() -> anyhow::Result<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes)
+        .map_err(|e| anyhow::anyhow!("failed to generate session token: {e}"))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Access rules for the emulator API.
+///
+/// Every request needs the session token as `Authorization: Bearer`.
+/// The `Host` header must name the loopback address the emulator listens
+/// on, which defeats DNS rebinding, and browser requests from other
+/// origins are rejected.
+#[derive(Debug, Clone)]
+pub struct Guard {
+    pub token: String,
+    pub port: u16,
+}
+
+impl Guard {
+    /// Check a request. `query_token` is accepted in place of the header
+    /// for the dashboard page.
+    pub fn check(
+        &self,
+        headers: &HeaderMap,
+        query_token: Option<&str>,
+    ) -> Result<(), (StatusCode, &'static str)> {
+        let host = headers.get(header::HOST).and_then(|h| h.to_str().ok());
+        if !host.is_some_and(|h| self.is_local_host(h)) {
+            return Err((StatusCode::FORBIDDEN, "nrz: invalid Host header"));
+        }
+        if let Some(origin) = headers.get(header::ORIGIN) {
+            let same_origin = origin
+                .to_str()
+                .ok()
+                .and_then(|o| o.strip_prefix("http://"))
+                .is_some_and(|o| self.is_local_host(o));
+            if !same_origin {
+                return Err((StatusCode::FORBIDDEN, "nrz: cross-origin request rejected"));
+            }
+        }
+        // Set by browsers even where `Origin` is not, e.g. on plain GETs
+        let fetch_site = headers.get("sec-fetch-site").and_then(|h| h.to_str().ok());
+        if matches!(fetch_site, Some("cross-site" | "same-site")) {
+            return Err((StatusCode::FORBIDDEN, "nrz: cross-origin request rejected"));
+        }
+
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "));
+        match bearer.or(query_token) {
+            Some(token) if constant_time_eq(token.as_bytes(), self.token.as_bytes()) => Ok(()),
+            _ => Err((
+                StatusCode::UNAUTHORIZED,
+                "nrz: missing or invalid emulator token",
+            )),
+        }
+    }
+
+    fn is_local_host(&self, host: &str) -> bool {
+        let port = self.port.to_string();
+        ["127.0.0.1", "localhost", "[::1]"]
+            .iter()
+            .any(|name| host.strip_prefix(name).and_then(|p| p.strip_prefix(':')) == Some(&port))
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Middleware applying [`Guard::check`] to every request.
+pub async fn require_session(State(guard): State<Guard>, req: Request, next: Next) -> Response {
+    // Only the dashboard page itself takes the token from the URL
+    let query_token = (req.uri().path() == "/__nrz/ui")
+        .then(|| req.uri().query())
+        .flatten()
+        .and_then(|q| {
+            url::form_urlencoded::parse(q.as_bytes())
+                .find(|(k, _)| k == TOKEN_PARAM)
+                .map(|(_, v)| v.into_owned())
+        });
+    match guard.check(req.headers(), query_token.as_deref()) {
+        Ok(()) => next.run(req).await,
+        Err(rejection) => rejection.into_response(),
+    }
+}
@@ -0,0 +1,116 @@
+//! Unit tests for emulator API access rules
+
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+
+use super::auth::{Guard, session_token};
+
+fn guard() -> Guard {
+    Guard {
+        token: "secret".into(),
+        port: 4322,
+    }
+}
+
+fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    for (name, value) in pairs {
+        map.insert(*name, HeaderValue::from_static(value));
+    }
+    map
+}
+
+fn status(result: Result<(), (StatusCode, &'static str)>) -> Option<StatusCode> {
+    result.err().map(|(status, _)| status)
+}
+
+#[test]
+fn session_tokens_are_random_hex() {
+    let a = session_token().unwrap();
+    let b = session_token().unwrap();
+    assert_eq!(a.len(), 64);
+    assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(a, b);
+}
+
+#[test]
+fn accepts_bearer_token_on_loopback_hosts() {
+    for host in ["127.0.0.1:4322", "localhost:4322", "[::1]:4322"] {
+        let mut map = headers(&[(header::AUTHORIZATION.as_str(), "Bearer secret")]);
+        map.insert(header::HOST, HeaderValue::from_str(host).unwrap());
+        assert_eq!(guard().check(&map, None), Ok(()), "{host}");
+    }
+}
+
+#[test]
+fn rejects_missing_or_wrong_token() {
+    let guard = guard();
+    let map = headers(&[("host", "127.0.0.1:4322")]);
+    assert_eq!(
+        status(guard.check(&map, None)),
+        Some(StatusCode::UNAUTHORIZED)
+    );
+
+    let map = headers(&[
+        ("host", "127.0.0.1:4322"),
+        ("authorization", "Bearer secreT"),
+    ]);
+    assert_eq!(
+        status(guard.check(&map, None)),
+        Some(StatusCode::UNAUTHORIZED)
+    );
+
+    // The dashboard passes the token in the URL
+    let map = headers(&[("host", "localhost:4322")]);
+    assert_eq!(guard.check(&map, Some("secret")), Ok(()));
+}
+
+#[test]
+fn rejects_foreign_host_headers() {
+    let guard = guard();
+    for host in [
+        "evil.example:4322",
+        "127.0.0.1:4321",
+        "localhost",
+        "localhost.evil:4322",
+    ] {
+        let mut map = headers(&[("authorization", "Bearer secret")]);
+        map.insert(header::HOST, HeaderValue::from_str(host).unwrap());
+        assert_eq!(
+            status(guard.check(&map, None)),
+            Some(StatusCode::FORBIDDEN),
+            "{host}"
+        );
+    }
+    let map = headers(&[("authorization", "Bearer secret")]);
+    assert_eq!(status(guard.check(&map, None)), Some(StatusCode::FORBIDDEN));
+}
+
+#[test]
+fn rejects_cross_origin_browser_requests() {
+    let guard = guard();
+    let base = [
+        ("host", "127.0.0.1:4322"),
+        ("authorization", "Bearer secret"),
+    ];
+
+    // The dashboard itself
+    let map = headers(&[base[0], base[1], ("origin", "http://localhost:4322")]);
+    assert_eq!(guard.check(&map, None), Ok(()));
+    let map = headers(&[base[0], base[1], ("sec-fetch-site", "same-origin")]);
+    assert_eq!(guard.check(&map, None), Ok(()));
+
+    for (name, value) in [
+        ("origin", "http://localhost:4321"),
+        ("origin", "https://evil.example"),
+        ("origin", "null"),
+        ("sec-fetch-site", "cross-site"),
+        ("sec-fetch-site", "same-site"),
+    ] {
+        let map = headers(&[base[0], base[1], (name, value)]);
+        assert_eq!(
+            status(guard.check(&map, None)),
+            Some(StatusCode::FORBIDDEN),
+            "{name}: {value}"
+        );
+    }
+}
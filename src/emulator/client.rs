@@ -0,0 +1,198 @@
+//! Typed client for the emulator HTTP API.
+
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use super::protocol::{
+    self, D1Response, DbBatchRequest, DbBatchStatement, DbExecRequest, DbQueryRequest,
+    HealthResponse, KvRequest, KvSetOptions, KvValueWithMetadata, PROTOCOL_HEADER,
+    PROTOCOL_VERSION, RpcCall, RpcResponse,
+};
+use super::runtime::RuntimeInfo;
+use super::server::RunningEmulator;
+
+/// Client for a running emulator, from Rust tests and tools.
+///
+/// Failed requests return the server's error message, e.g. the SQL error
+/// or a D1 compatibility violation. Call [`Self::handshake`] first to fail
+/// early on an emulator from another nrz version.
+#[derive(Debug, Clone)]
+pub struct EmulatorClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl EmulatorClient {
+    /// Client for the API at `base_url` (e.g. `http://127.0.0.1:4322`),
+    /// authenticated with the session token.
+    pub fn new(base_url: impl Into<String>, token: &str) -> anyhow::Result<Self> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            reqwest::header::AUTHORIZATION,
+            format!("Bearer {token}")
+                .parse()
+                .context("invalid emulator token")?,
+        );
+        headers.insert(PROTOCOL_HEADER, PROTOCOL_VERSION.into());
+        let http = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()?;
+        Ok(Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http,
+        })
+    }
+
+    /// Client for an emulator started in this process.
+    pub fn connect(emulator: &RunningEmulator) -> anyhow::Result<Self> {
+        Self::new(emulator.url(), emulator.token())
+    }
+
+    /// Client for the emulator of the `nrz dev` session using `data_dir`,
+    /// found through its runtime file.
+    pub fn from_runtime(data_dir: &Path) -> anyhow::Result<Self> {
+        let info = RuntimeInfo::read(data_dir).with_context(|| {
+            format!(
+                "no running `nrz dev` found in {} — start it first",
+                data_dir.display()
+            )
+        })?;
+        Self::new(info.emulator_url, &info.token)
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub async fn health(&self) -> anyhow::Result<HealthResponse> {
+        let resp = self
+            .http
+            .get(self.url(protocol::HEALTH))
+            .send()
+            .await
+            .with_context(|| format!("emulator not reachable at {}", self.base_url))?;
+        parse(protocol::HEALTH, resp).await
+    }
+
+    /// Check that the emulator speaks this client's protocol version and
+    /// supports KV and DB.
+    pub async fn handshake(&self) -> anyhow::Result<HealthResponse> {
+        let health = self.health().await?;
+        health
+            .check_compatible(protocol::CAPABILITIES)
+            .map_err(|e| anyhow::anyhow!("{e} ({})", self.base_url))?;
+        Ok(health)
+    }
+
+    // --- KV ---
+
+    pub async fn kv_get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        self.kv(protocol::KV_GET, vec![key.into()]).await
+    }
+
+    pub async fn kv_get_with_metadata(&self, key: &str) -> anyhow::Result<KvValueWithMetadata> {
+        self.kv(protocol::KV_GET_WITH_METADATA, vec![key.into()])
+            .await
+    }
+
+    pub async fn kv_set(
+        &self,
+        key: &str,
+        value: &str,
+        options: &KvSetOptions,
+    ) -> anyhow::Result<()> {
+        let args = vec![key.into(), value.into(), serde_json::to_value(options)?];
+        let _: String = self.kv(protocol::KV_SET, args).await?;
+        Ok(())
+    }
+
+    /// Returns whether the key existed.
+    pub async fn kv_delete(&self, key: &str) -> anyhow::Result<bool> {
+        self.kv(protocol::KV_DELETE, vec![key.into()]).await
+    }
+
+    pub async fn kv_has(&self, key: &str) -> anyhow::Result<bool> {
+        self.kv(protocol::KV_HAS, vec![key.into()]).await
+    }
+
+    /// Keys starting with `prefix`, sorted, at most `limit` (1000 by
+    /// default).
+    pub async fn kv_list(
+        &self,
+        prefix: Option<&str>,
+        limit: Option<usize>,
+    ) -> anyhow::Result<Vec<String>> {
+        let args = vec![prefix.into(), limit.into()];
+        self.kv(protocol::KV_LIST, args).await
+    }
+
+    // --- DB ---
+
+    pub async fn db_query(&self, request: &DbQueryRequest) -> anyhow::Result<D1Response> {
+        self.post(protocol::DB_QUERY, request).await
+    }
+
+    /// Run statements in order, returning rows as objects for each.
+    pub async fn db_batch(
+        &self,
+        statements: Vec<DbBatchStatement>,
+    ) -> anyhow::Result<Vec<D1Response>> {
+        self.post(protocol::DB_BATCH, &DbBatchRequest { statements })
+            .await
+    }
+
+    /// Run a script of statements without bindings, e.g. a migration.
+    pub async fn db_exec(&self, sql: &str) -> anyhow::Result<D1Response> {
+        let request = DbExecRequest {
+            sql: sql.to_string(),
+        };
+        self.post(protocol::DB_EXEC, &request).await
+    }
+
+    /// Run calls in one request, in order. A failed call only sets the
+    /// `error` of its own response.
+    pub async fn rpc(&self, calls: &[RpcCall]) -> anyhow::Result<Vec<RpcResponse>> {
+        self.post(protocol::RPC, &calls).await
+    }
+
+    async fn kv<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        args: Vec<serde_json::Value>,
+    ) -> anyhow::Result<T> {
+        self.post(path, &KvRequest { args }).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> anyhow::Result<T> {
+        let resp = self
+            .http
+            .post(self.url(path))
+            .json(body)
+            .send()
+            .await
+            .with_context(|| format!("emulator not reachable at {}", self.base_url))?;
+        parse(path, resp).await
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+}
+
+async fn parse<T: DeserializeOwned>(path: &str, resp: reqwest::Response) -> anyhow::Result<T> {
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("{path} failed ({status}): {}", body.trim());
+    }
+    resp.json()
+        .await
+        .with_context(|| format!("invalid response from {path}"))
+}
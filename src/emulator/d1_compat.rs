@@ -0,0 +1,324 @@
+//! D1 compatibility rules for the local emulator.
+//!
+//! The emulator runs plain SQLite, which accepts statements and sizes that
+//! the platform's D1-compatible database rejects. These checks surface such
+//! problems locally, either as warnings or as hard errors.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Maximum number of bound parameters in a single query.
+pub const MAX_BOUND_PARAMETERS: usize = 100;
+
+/// Maximum length of a single SQL statement in bytes.
+pub const MAX_STATEMENT_BYTES: usize = 100_000;
+
+/// Maximum size of a single string or BLOB value in bytes.
+pub const MAX_VALUE_BYTES: usize = 2_000_000;
+
+/// PRAGMAs supported by D1. Everything else is rejected on the platform.
+const ALLOWED_PRAGMAS: &[&str] = &[
+    "case_sensitive_like",
+    "defer_foreign_keys",
+    "foreign_key_check",
+    "foreign_key_list",
+    "foreign_keys",
+    "ignore_check_constraints",
+    "index_info",
+    "index_list",
+    "index_xinfo",
+    "legacy_alter_table",
+    "optimize",
+    "quick_check",
+    "recursive_triggers",
+    "reverse_unordered_selects",
+    "table_info",
+    "table_list",
+    "table_xinfo",
+];
+
+/// How the emulator reacts to D1 incompatibilities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompatMode {
+    /// Reject the statement with an error, like the platform would.
+    Strict,
+    /// Run the statement but print a warning.
+    #[default]
+    Warn,
+    /// Disable all checks.
+    Off,
+}
+
+impl FromStr for CompatMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Self::Strict),
+            "warn" => Ok(Self::Warn),
+            "off" => Ok(Self::Off),
+            other => Err(format!(
+                "unknown D1 compat mode: {other} (expected strict, warn or off)"
+            )),
+        }
+    }
+}
+
+/// A statement or value that would fail on the platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// `ATTACH` / `DETACH DATABASE`
+    Attach,
+    /// PRAGMA outside the D1 allowlist
+    Pragma(String),
+    /// Explicit transaction control (`BEGIN`, `COMMIT`, `SAVEPOINT`, ...)
+    Transaction(String),
+    /// More than [`MAX_BOUND_PARAMETERS`] parameters
+    TooManyParameters(usize),
+    /// Statement longer than [`MAX_STATEMENT_BYTES`]
+    StatementTooLong(usize),
+    /// Bound string/BLOB larger than [`MAX_VALUE_BYTES`]
+    ValueTooLarge { index: usize, bytes: usize },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Attach => write!(f, "ATTACH/DETACH is not supported"),
+            Self::Pragma(name) => write!(f, "PRAGMA {name} is not supported"),
+            Self::Transaction(kw) => write!(
+                f,
+                "{kw} is not supported — use db.batch() for atomic statements"
+            ),
+            Self::TooManyParameters(n) => write!(
+                f,
+                "{n} bound parameters exceed the limit of {MAX_BOUND_PARAMETERS}"
+            ),
+            Self::StatementTooLong(n) => write!(
+                f,
+                "statement of {n} bytes exceeds the limit of {MAX_STATEMENT_BYTES}"
+            ),
+            Self::ValueTooLarge { index, bytes } => write!(
+                f,
+                "binding {index} is {bytes} bytes, exceeding the limit of {MAX_VALUE_BYTES}"
+            ),
+        }
+    }
+}
+
+/// Check a single prepared statement and its bindings.
+pub fn check_statement(sql: &str, bindings: &[serde_json::Value]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let statements = split_statements(sql);
+    for stmt in &statements {
+        violations.extend(check_one(stmt));
+    }
+    if statements.is_empty() {
+        violations.extend(check_length(sql));
+    }
+
+    for (i, val) in bindings.iter().enumerate() {
+        let bytes = match val {
+            serde_json::Value::String(s) => s.len(),
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => val.to_string().len(),
+            _ => 0,
+        };
+        if bytes > MAX_VALUE_BYTES {
+            violations.push(Violation::ValueTooLarge {
+                index: i + 1,
+                bytes,
+            });
+        }
+    }
+    violations
+}
+
+/// Check a script of one or more `;`-separated statements (`db.exec`).
+pub fn check_script(sql: &str) -> Vec<Violation> {
+    split_statements(sql).iter().flat_map(check_one).collect()
+}
+
+/// Apply `mode` to a list of violations.
+///
+/// Returns an error message in strict mode; logs warnings (`tracing`, at
+/// warn level) in warn mode.
+pub fn enforce(mode: CompatMode, violations: &[Violation]) -> Result<(), String> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    match mode {
+        CompatMode::Off => Ok(()),
+        CompatMode::Warn => {
+            for v in violations {
+                tracing::warn!("D1 compat: {v} (this will fail when deployed)");
+            }
+            Ok(())
+        }
+        CompatMode::Strict => {
+            let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            Err(format!("D1 compatibility error: {}", messages.join("; ")))
+        }
+    }
+}
+
+fn check_length(sql: &str) -> Option<Violation> {
+    (sql.len() > MAX_STATEMENT_BYTES).then_some(Violation::StatementTooLong(sql.len()))
+}
+
+fn check_one(stmt: &Statement) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    violations.extend(check_length(&stmt.sql));
+
+    let first = stmt.words.first().map(String::as_str).unwrap_or("");
+    match first {
+        "ATTACH" | "DETACH" => violations.push(Violation::Attach),
+        "PRAGMA" => {
+            // PRAGMA [schema.]name [= value | (value)]
+            let name = stmt
+                .words
+                .get(1)
+                .and_then(|w| w.rsplit('.').next())
+                .unwrap_or("")
+                .to_ascii_lowercase();
+            if !ALLOWED_PRAGMAS.contains(&name.as_str()) {
+                violations.push(Violation::Pragma(name));
+            }
+        }
+        "BEGIN" | "COMMIT" | "END" | "ROLLBACK" | "SAVEPOINT" | "RELEASE" => {
+            violations.push(Violation::Transaction(first.to_string()));
+        }
+        _ => {}
+    }
+
+    if stmt.parameters > MAX_BOUND_PARAMETERS {
+        violations.push(Violation::TooManyParameters(stmt.parameters));
+    }
+    violations
+}
+
+/// A statement as seen by the lexer.
+#[derive(Debug, Default)]
+struct Statement {
+    sql: String,
+    /// Uppercased keywords/identifiers outside strings and comments
+    words: Vec<String>,
+    /// Number of bound parameters (highest parameter index)
+    parameters: usize,
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+/// Split SQL into statements, skipping strings, quoted identifiers and
+/// comments. Semicolons inside `CREATE TRIGGER ... BEGIN ... END` bodies
+/// do not end the statement.
+///
+/// Works on bytes: every token SQLite treats specially is ASCII, so
+/// multi-byte UTF-8 sequences are always part of identifiers or strings.
+fn split_statements(sql: &str) -> Vec<Statement> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut current = Statement::default();
+    let mut named = HashSet::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    let mut finish = |current: &mut Statement, named: &mut HashSet<String>, text: &str| {
+        let mut stmt = std::mem::take(current);
+        named.clear();
+        if !stmt.words.is_empty() {
+            stmt.sql = text.trim().to_string();
+            statements.push(stmt);
+        }
+    };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`' | b'[') => {
+                let close = if quote == b'[' { b']' } else { quote };
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == close {
+                        // A doubled quote is an escaped quote
+                        if close != b']' && bytes.get(i + 1) == Some(&close) {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                i += 1;
+                // Quoted identifiers still occupy a word position
+                if quote != b'\'' {
+                    current.words.push(String::new());
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 2;
+            }
+            b'?' => {
+                i += 1;
+                let digits = i;
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+                current.parameters = if i > digits {
+                    current.parameters.max(sql[digits..i].parse().unwrap_or(0))
+                } else {
+                    current.parameters + 1
+                };
+            }
+            b':' | b'@' | b'$' if bytes.get(i + 1).is_some_and(|b| is_ident_byte(*b)) => {
+                let begin = i;
+                i += 1;
+                while i < bytes.len() && is_ident_byte(bytes[i]) {
+                    i += 1;
+                }
+                if named.insert(sql[begin..i].to_string()) {
+                    current.parameters += 1;
+                }
+            }
+            b';' if depth == 0 => {
+                finish(&mut current, &mut named, &sql[start..i]);
+                i += 1;
+                start = i;
+            }
+            b if is_ident_byte(b) => {
+                let begin = i;
+                while i < bytes.len() && (is_ident_byte(bytes[i]) || bytes[i] == b'.') {
+                    i += 1;
+                }
+                let word = sql[begin..i].to_ascii_uppercase();
+
+                let in_trigger = current.words.first().is_some_and(|w| w == "CREATE")
+                    && current.words.iter().take(4).any(|w| w == "TRIGGER");
+                if in_trigger || depth > 0 {
+                    match word.as_str() {
+                        "BEGIN" | "CASE" => depth += 1,
+                        "END" => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                }
+                current.words.push(word);
+            }
+            _ => i += 1,
+        }
+    }
+    let rest = sql.get(start..).unwrap_or("");
+    finish(&mut current, &mut named, rest);
+    statements
+}
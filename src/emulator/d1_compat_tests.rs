@@ -0,0 +1,197 @@
+//! Unit tests for D1 compatibility rules
+//!
+//! Each test documents one rule the platform enforces and the local
+//! emulator would otherwise silently accept.
+
+use super::d1_compat::{
+    CompatMode, MAX_BOUND_PARAMETERS, MAX_STATEMENT_BYTES, MAX_VALUE_BYTES, Violation,
+    check_script, check_statement, enforce,
+};
+
+// --- Rule: no ATTACH / DETACH ---
+
+#[test]
+fn attach_is_rejected() {
+    let v = check_statement("ATTACH DATABASE 'other.db' AS other", &[]);
+    assert_eq!(v, vec![Violation::Attach]);
+}
+
+#[test]
+fn detach_is_rejected() {
+    let v = check_statement("detach database other", &[]);
+    assert_eq!(v, vec![Violation::Attach]);
+}
+
+// --- Rule: only allowlisted PRAGMAs ---
+
+#[test]
+fn allowed_pragmas_pass() {
+    assert!(check_statement("PRAGMA table_info(users)", &[]).is_empty());
+    assert!(check_statement("PRAGMA foreign_keys = ON", &[]).is_empty());
+    assert!(check_statement("PRAGMA main.index_list('users')", &[]).is_empty());
+}
+
+#[test]
+fn other_pragmas_are_rejected() {
+    assert_eq!(
+        check_statement("PRAGMA journal_mode = WAL", &[]),
+        vec![Violation::Pragma("journal_mode".into())]
+    );
+    assert_eq!(
+        check_statement("PRAGMA main.synchronous=OFF", &[]),
+        vec![Violation::Pragma("synchronous".into())]
+    );
+}
+
+#[test]
+fn pragma_table_functions_in_select_pass() {
+    assert!(check_statement("SELECT name FROM pragma_table_info('users')", &[]).is_empty());
+}
+
+// --- Rule: no explicit transactions ---
+
+#[test]
+fn transaction_statements_are_rejected() {
+    for (sql, kw) in [
+        ("BEGIN TRANSACTION", "BEGIN"),
+        ("COMMIT", "COMMIT"),
+        ("END TRANSACTION", "END"),
+        ("ROLLBACK", "ROLLBACK"),
+        ("SAVEPOINT sp1", "SAVEPOINT"),
+        ("RELEASE sp1", "RELEASE"),
+    ] {
+        assert_eq!(
+            check_statement(sql, &[]),
+            vec![Violation::Transaction(kw.into())],
+            "{sql}"
+        );
+    }
+}
+
+#[test]
+fn trigger_body_is_not_a_transaction() {
+    let sql = "CREATE TRIGGER touch AFTER UPDATE ON users BEGIN
+                 UPDATE users SET n = CASE WHEN n IS NULL THEN 1 ELSE n + 1 END WHERE id = NEW.id;
+                 UPDATE users SET touched = 1 WHERE id = NEW.id;
+               END;
+               SELECT 1;";
+    assert!(check_script(sql).is_empty());
+}
+
+// --- Rule: at most 100 bound parameters ---
+
+#[test]
+fn parameter_limit() {
+    let placeholders = vec!["?"; MAX_BOUND_PARAMETERS].join(", ");
+    let sql = format!("INSERT INTO t VALUES ({placeholders})");
+    assert!(check_statement(&sql, &[]).is_empty());
+
+    let placeholders = vec!["?"; MAX_BOUND_PARAMETERS + 1].join(", ");
+    let sql = format!("INSERT INTO t VALUES ({placeholders})");
+    assert_eq!(
+        check_statement(&sql, &[]),
+        vec![Violation::TooManyParameters(MAX_BOUND_PARAMETERS + 1)]
+    );
+}
+
+#[test]
+fn numbered_and_named_parameters() {
+    assert_eq!(
+        check_statement("SELECT ?150", &[]),
+        vec![Violation::TooManyParameters(150)]
+    );
+    // Repeated names bind once
+    let sql = "SELECT :a, :a, @b, $c, ?";
+    assert!(check_statement(sql, &[]).is_empty());
+}
+
+#[test]
+fn placeholders_in_strings_and_comments_are_ignored() {
+    let many = "?".repeat(MAX_BOUND_PARAMETERS + 1);
+    let sql = format!("SELECT '{many}' -- {many}\n /* {many} */");
+    assert!(check_statement(&sql, &[]).is_empty());
+}
+
+// --- Rule: statements up to 100 KB ---
+
+#[test]
+fn statement_length_limit() {
+    let padding = "x".repeat(MAX_STATEMENT_BYTES);
+    let sql = format!("SELECT '{padding}'");
+    assert_eq!(
+        check_statement(&sql, &[]),
+        vec![Violation::StatementTooLong(sql.len())]
+    );
+}
+
+#[test]
+fn length_limit_applies_per_statement() {
+    let half = "x".repeat(MAX_STATEMENT_BYTES / 2);
+    let sql = format!("SELECT '{half}'; SELECT '{half}';");
+    assert!(check_script(&sql).is_empty());
+}
+
+// --- Rule: bound values up to 2 MB ---
+
+#[test]
+fn value_size_limit() {
+    let big = serde_json::json!("x".repeat(MAX_VALUE_BYTES + 1));
+    let v = check_statement("INSERT INTO t VALUES (?, ?)", &[serde_json::json!(1), big]);
+    assert_eq!(
+        v,
+        vec![Violation::ValueTooLarge {
+            index: 2,
+            bytes: MAX_VALUE_BYTES + 1
+        }]
+    );
+}
+
+// --- Scripts (db.exec) ---
+
+#[test]
+fn script_checks_every_statement() {
+    let v = check_script("CREATE TABLE t (id INTEGER); PRAGMA cache_size = 10; BEGIN;");
+    assert_eq!(
+        v,
+        vec![
+            Violation::Pragma("cache_size".into()),
+            Violation::Transaction("BEGIN".into()),
+        ]
+    );
+}
+
+#[test]
+fn semicolons_in_strings_do_not_split() {
+    let v = check_script("INSERT INTO t VALUES ('a; PRAGMA x'); SELECT \"b;c\" FROM t");
+    assert!(v.is_empty());
+}
+
+#[test]
+fn utf8_identifiers_and_strings() {
+    assert!(check_script("INSERT INTO тест VALUES ('привет; мир');").is_empty());
+}
+
+// --- Modes ---
+
+#[test]
+fn mode_parsing() {
+    assert_eq!("strict".parse::<CompatMode>(), Ok(CompatMode::Strict));
+    assert_eq!("warn".parse::<CompatMode>(), Ok(CompatMode::Warn));
+    assert_eq!("off".parse::<CompatMode>(), Ok(CompatMode::Off));
+    assert!("loose".parse::<CompatMode>().is_err());
+    assert_eq!(CompatMode::default(), CompatMode::Warn);
+}
+
+#[test]
+fn strict_mode_rejects() {
+    let err = enforce(CompatMode::Strict, &[Violation::Attach]).unwrap_err();
+    assert!(err.contains("D1 compatibility error"));
+    assert!(err.contains("ATTACH"));
+}
+
+#[test]
+fn warn_and_off_modes_allow() {
+    assert!(enforce(CompatMode::Warn, &[Violation::Attach]).is_ok());
+    assert!(enforce(CompatMode::Off, &[Violation::Attach]).is_ok());
+    assert!(enforce(CompatMode::Strict, &[]).is_ok());
+}
@@ -1,4 +1,277 @@
 //! D1-compatible SQLite database operations (core functionality).
+//!
+//! CLI handlers live in `src/cli/db_handler.rs`; this module holds the
+//! database logic shared by the CLI, the emulator and tooling.
 
-// This module previously contained CLI handlers, which have been moved to
-// `src/cli/db_handler.rs`. This file is kept for any future core DB functionality.
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+/// Full schema of a database: user tables and views.
+///
+/// Internal `sqlite_*` objects are excluded.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct Schema {
+    pub tables: Vec<Table>,
+    pub views: Vec<View>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<Column>,
+    /// Primary key columns in key order (empty for rowid-only tables).
+    pub primary_key: Vec<String>,
+    pub foreign_keys: Vec<ForeignKey>,
+    pub indexes: Vec<Index>,
+    pub triggers: Vec<Trigger>,
+    /// Original `CREATE TABLE` statement.
+    pub sql: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Column {
+    pub name: String,
+    /// Declared type as written in the schema (may be empty).
+    #[serde(rename = "type")]
+    pub decl_type: String,
+    pub not_null: bool,
+    /// Default value expression as written in the schema.
+    pub default: Option<String>,
+    pub primary_key: bool,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ForeignKey {
+    pub columns: Vec<String>,
+    pub references_table: String,
+    /// Referenced columns; `None` means the parent's primary key.
+    pub references_columns: Vec<Option<String>>,
+    pub on_update: String,
+    pub on_delete: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Index {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+    /// How the index was created: `c` (CREATE INDEX), `u` (UNIQUE constraint)
+    /// or `pk` (PRIMARY KEY constraint).
+    pub origin: String,
+    pub partial: bool,
+    /// `CREATE INDEX` statement; `None` for automatic constraint indexes.
+    pub sql: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Trigger {
+    pub name: String,
+    pub sql: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct View {
+    pub name: String,
+    pub columns: Vec<Column>,
+    pub sql: Option<String>,
+}
+
+impl Schema {
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.iter().find(|t| t.name == name)
+    }
+
+    pub fn view(&self, name: &str) -> Option<&View> {
+        self.views.iter().find(|v| v.name == name)
+    }
+}
+
+/// Quote an identifier for safe interpolation into SQL (`"name"`).
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Read the schema of all user tables and views.
+pub fn introspect(conn: &Connection) -> rusqlite::Result<Schema> {
+    let mut objects = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT type, name, sql FROM sqlite_master \
+             WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' \
+             ORDER BY name",
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let kind: String = row.get(0)?;
+            let name: String = row.get(1)?;
+            let sql: Option<String> = row.get(2)?;
+            objects.push((kind, name, sql));
+        }
+    }
+
+    let mut schema = Schema::default();
+    for (kind, name, sql) in objects {
+        let columns = columns(conn, &name)?;
+        if kind == "view" {
+            schema.views.push(View { name, columns, sql });
+            continue;
+        }
+
+        let mut pk: Vec<(i64, String)> = Vec::new();
+        {
+            let mut stmt =
+                conn.prepare("SELECT name, pk FROM pragma_table_info(?1) WHERE pk > 0")?;
+            let mut rows = stmt.query([&name])?;
+            while let Some(row) = rows.next()? {
+                pk.push((row.get(1)?, row.get(0)?));
+            }
+        }
+        pk.sort();
+
+        schema.tables.push(Table {
+            foreign_keys: foreign_keys(conn, &name)?,
+            indexes: indexes(conn, &name)?,
+            triggers: triggers(conn, &name)?,
+            primary_key: pk.into_iter().map(|(_, n)| n).collect(),
+            columns,
+            name,
+            sql,
+        });
+    }
+    Ok(schema)
+}
+
+fn columns(conn: &Connection, table: &str) -> rusqlite::Result<Vec<Column>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?1) ORDER BY cid",
+    )?;
+    let mut rows = stmt.query([table])?;
+    let mut columns = Vec::new();
+    while let Some(row) = rows.next()? {
+        columns.push(Column {
+            name: row.get(0)?,
+            decl_type: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            not_null: row.get::<_, i64>(2)? != 0,
+            default: row.get(3)?,
+            primary_key: row.get::<_, i64>(4)? > 0,
+        });
+    }
+    Ok(columns)
+}
+
+fn foreign_keys(conn: &Connection, table: &str) -> rusqlite::Result<Vec<ForeignKey>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, \"table\", \"from\", \"to\", on_update, on_delete \
+         FROM pragma_foreign_key_list(?1) ORDER BY id, seq",
+    )?;
+    let mut rows = stmt.query([table])?;
+    let mut keys: Vec<(i64, ForeignKey)> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get(0)?;
+        let from: String = row.get(2)?;
+        let to: Option<String> = row.get(3)?;
+        match keys.last_mut() {
+            Some((last_id, fk)) if *last_id == id => {
+                fk.columns.push(from);
+                fk.references_columns.push(to);
+            }
+            _ => keys.push((
+                id,
+                ForeignKey {
+                    columns: vec![from],
+                    references_table: row.get(1)?,
+                    references_columns: vec![to],
+                    on_update: row.get(4)?,
+                    on_delete: row.get(5)?,
+                },
+            )),
+        }
+    }
+    Ok(keys.into_iter().map(|(_, fk)| fk).collect())
+}
+
+fn indexes(conn: &Connection, table: &str) -> rusqlite::Result<Vec<Index>> {
+    let mut list = Vec::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT name, \"unique\", origin, partial FROM pragma_index_list(?1) ORDER BY name",
+        )?;
+        let mut rows = stmt.query([table])?;
+        while let Some(row) = rows.next()? {
+            let name: String = row.get(0)?;
+            let unique: i64 = row.get(1)?;
+            let origin: String = row.get(2)?;
+            let partial: i64 = row.get(3)?;
+            list.push((name, unique != 0, origin, partial != 0));
+        }
+    }
+
+    let mut indexes = Vec::new();
+    for (name, unique, origin, partial) in list {
+        let mut stmt = conn.prepare("SELECT name FROM pragma_index_info(?1) ORDER BY seqno")?;
+        let columns = stmt
+            .query_map([&name], |row| {
+                // Expression columns have no name
+                Ok(row
+                    .get::<_, Option<String>>(0)?
+                    .unwrap_or_else(|| "<expr>".into()))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let sql = conn
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'index' AND name = ?1",
+                [&name],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?
+            .flatten();
+        indexes.push(Index {
+            name,
+            columns,
+            unique,
+            origin,
+            partial,
+            sql,
+        });
+    }
+    Ok(indexes)
+}
+
+fn triggers(conn: &Connection, table: &str) -> rusqlite::Result<Vec<Trigger>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, sql FROM sqlite_master WHERE type = 'trigger' AND tbl_name = ?1 ORDER BY name",
+    )?;
+    stmt.query_map([table], |row| {
+        Ok(Trigger {
+            name: row.get(0)?,
+            sql: row.get(1)?,
+        })
+    })?
+    .collect()
+}
+
+/// Apply every `*.sql` file in `dir` to `conn`, in file name order.
+///
+/// Returns the applied files. Used to build a scratch database from
+/// migrations for type generation and schema diffs.
+pub fn apply_migrations(conn: &Connection, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let entries = std::fs::read_dir(dir)
+        .with_context(|| format!("migrations directory not found: {}", dir.display()))?;
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "sql"))
+        .collect();
+    files.sort();
+
+    for file in &files {
+        let sql = std::fs::read_to_string(file)
+            .with_context(|| format!("failed to read {}", file.display()))?;
+        conn.execute_batch(&sql)
+            .with_context(|| format!("migration failed: {}", file.display()))?;
+    }
+    Ok(files)
+}
@@ -0,0 +1,140 @@
+//! Unit tests for schema introspection
+
+use rusqlite::Connection;
+
+use super::db::{introspect, quote_ident};
+
+fn conn_with(sql: &str) -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql).unwrap();
+    conn
+}
+
+#[test]
+fn quote_ident_escapes_quotes() {
+    assert_eq!(quote_ident("users"), "\"users\"");
+    assert_eq!(quote_ident("we\"ird"), "\"we\"\"ird\"");
+}
+
+#[test]
+fn empty_database() {
+    let conn = Connection::open_in_memory().unwrap();
+    let schema = introspect(&conn).unwrap();
+    assert!(schema.tables.is_empty());
+    assert!(schema.views.is_empty());
+}
+
+#[test]
+fn columns_and_primary_key() {
+    let conn = conn_with(
+        "CREATE TABLE users (
+            id INTEGER PRIMARY KEY,
+            email TEXT NOT NULL,
+            role TEXT DEFAULT 'member',
+            bio
+        )",
+    );
+    let schema = introspect(&conn).unwrap();
+    let users = schema.table("users").unwrap();
+
+    assert_eq!(users.primary_key, vec!["id"]);
+    assert_eq!(users.columns.len(), 4);
+
+    let id = &users.columns[0];
+    assert_eq!(id.decl_type, "INTEGER");
+    assert!(id.primary_key);
+
+    let email = &users.columns[1];
+    assert!(email.not_null);
+    assert!(email.default.is_none());
+
+    let role = &users.columns[2];
+    assert!(!role.not_null);
+    assert_eq!(role.default.as_deref(), Some("'member'"));
+
+    assert_eq!(users.columns[3].decl_type, "");
+}
+
+#[test]
+fn composite_primary_key_order() {
+    let conn = conn_with("CREATE TABLE t (a TEXT, b TEXT, PRIMARY KEY (b, a))");
+    let schema = introspect(&conn).unwrap();
+    assert_eq!(schema.table("t").unwrap().primary_key, vec!["b", "a"]);
+}
+
+#[test]
+fn foreign_keys() {
+    let conn = conn_with(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY);
+         CREATE TABLE posts (
+            id INTEGER PRIMARY KEY,
+            author_id INTEGER REFERENCES users(id) ON DELETE CASCADE
+         );",
+    );
+    let schema = introspect(&conn).unwrap();
+    let posts = schema.table("posts").unwrap();
+    assert_eq!(posts.foreign_keys.len(), 1);
+
+    let fk = &posts.foreign_keys[0];
+    assert_eq!(fk.columns, vec!["author_id"]);
+    assert_eq!(fk.references_table, "users");
+    assert_eq!(fk.references_columns, vec![Some("id".to_string())]);
+    assert_eq!(fk.on_delete, "CASCADE");
+    assert_eq!(fk.on_update, "NO ACTION");
+}
+
+#[test]
+fn indexes() {
+    let conn = conn_with(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE, name TEXT);
+         CREATE INDEX idx_users_name ON users (name) WHERE name IS NOT NULL;",
+    );
+    let schema = introspect(&conn).unwrap();
+    let users = schema.table("users").unwrap();
+    assert_eq!(users.indexes.len(), 2);
+
+    let auto = users.indexes.iter().find(|i| i.origin == "u").unwrap();
+    assert!(auto.unique);
+    assert_eq!(auto.columns, vec!["email"]);
+    assert!(auto.sql.is_none());
+
+    let named = users
+        .indexes
+        .iter()
+        .find(|i| i.name == "idx_users_name")
+        .unwrap();
+    assert!(!named.unique);
+    assert!(named.partial);
+    assert_eq!(named.origin, "c");
+    assert!(named.sql.as_deref().unwrap().contains("CREATE INDEX"));
+}
+
+#[test]
+fn triggers_and_views() {
+    let conn = conn_with(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, updated_at TEXT);
+         CREATE TRIGGER touch AFTER UPDATE ON users BEGIN
+            UPDATE users SET updated_at = 'now' WHERE id = NEW.id;
+         END;
+         CREATE VIEW user_names AS SELECT id, name FROM users;",
+    );
+    let schema = introspect(&conn).unwrap();
+
+    let users = schema.table("users").unwrap();
+    assert_eq!(users.triggers.len(), 1);
+    assert_eq!(users.triggers[0].name, "touch");
+
+    assert_eq!(schema.views.len(), 1);
+    let view = schema.view("user_names").unwrap();
+    let cols: Vec<&str> = view.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(cols, vec!["id", "name"]);
+    assert!(schema.table("user_names").is_none());
+}
+
+#[test]
+fn excludes_internal_tables() {
+    let conn = conn_with("CREATE TABLE t (id INTEGER PRIMARY KEY AUTOINCREMENT)");
+    let schema = introspect(&conn).unwrap();
+    let names: Vec<&str> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["t"]);
+}
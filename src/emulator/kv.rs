@@ -10,6 +10,18 @@ use super::data_dir;
 struct Entry {
     value: String,
     expires_at: Option<Instant>,
+    metadata: Option<serde_json::Value>,
+}
+
+/// A live entry with its remaining TTL and metadata, for inspection.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KvEntry {
+    pub key: String,
+    pub value: String,
+    /// Seconds until expiry, `None` for keys without TTL
+    pub expires_in: Option<u64>,
+    pub metadata: Option<serde_json::Value>,
 }
 
 /// In-memory KV store with TTL support.
@@ -50,6 +62,17 @@ impl KvStore {
     }
 
     pub fn set(&self, key: String, value: String, ttl_secs: u64) {
+        self.set_with_metadata(key, value, ttl_secs, None);
+    }
+
+    /// Like [`KvStore::set`], attaching JSON metadata to the key.
+    pub fn set_with_metadata(
+        &self,
+        key: String,
+        value: String,
+        ttl_secs: u64,
+        metadata: Option<serde_json::Value>,
+    ) {
         let expires_at = if ttl_secs > 0 {
             Some(Instant::now() + Duration::from_secs(ttl_secs))
         } else {
@@ -62,7 +85,49 @@ impl KvStore {
                 tracing::warn!("KV store mutex was poisoned, recovering");
                 poisoned.into_inner()
             })
-            .insert(key, Entry { value, expires_at });
+            .insert(
+                key,
+                Entry {
+                    value,
+                    expires_at,
+                    metadata,
+                },
+            );
+    }
+
+    /// Value and metadata of a key.
+    pub fn get_with_metadata(&self, key: &str) -> Option<(String, Option<serde_json::Value>)> {
+        let store = self.inner.lock().unwrap_or_else(|poisoned| {
+            tracing::warn!("KV store mutex was poisoned, recovering");
+            poisoned.into_inner()
+        });
+        store
+            .get(key)
+            .filter(|entry| entry.expires_at.is_none_or(|e| Instant::now() <= e))
+            .map(|entry| (entry.value.clone(), entry.metadata.clone()))
+    }
+
+    /// Live entries in key order, optionally filtered by prefix.
+    pub fn entries(&self, prefix: Option<&str>, limit: usize) -> Vec<KvEntry> {
+        let store = self.inner.lock().unwrap_or_else(|poisoned| {
+            tracing::warn!("KV store mutex was poisoned, recovering");
+            poisoned.into_inner()
+        });
+        let now = Instant::now();
+        store
+            .iter()
+            .filter(|(k, _)| prefix.is_none_or(|p| k.starts_with(p)))
+            .filter(|(_, entry)| entry.expires_at.is_none_or(|e| now <= e))
+            .take(limit)
+            .map(|(key, entry)| KvEntry {
+                key: key.clone(),
+                value: entry.value.clone(),
+                expires_in: entry
+                    .expires_at
+                    .map(|e| e.saturating_duration_since(now).as_secs()),
+                metadata: entry.metadata.clone(),
+            })
+            .collect()
     }
 
     pub fn delete(&self, key: &str) -> bool {
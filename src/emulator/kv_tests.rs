@@ -202,3 +202,40 @@ fn kvfile_load_corrupt_returns_default() {
     let kv = load_kv_file(&path);
     assert!(kv.entries.is_empty());
 }
+
+#[test]
+fn store_metadata_and_entries() {
+    let kv = KvStore::new();
+    kv.set_with_metadata(
+        "user:1".into(),
+        "alice".into(),
+        60,
+        Some(serde_json::json!({ "role": "admin" })),
+    );
+    kv.set("user:2".into(), "bob".into(), 0);
+    kv.set("other".into(), "x".into(), 0);
+
+    let (value, metadata) = kv.get_with_metadata("user:1").unwrap();
+    assert_eq!(value, "alice");
+    assert_eq!(metadata.unwrap()["role"], "admin");
+    assert_eq!(kv.get_with_metadata("user:2").unwrap().1, None);
+
+    let entries = kv.entries(Some("user:"), 100);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].key, "user:1");
+    assert!(
+        entries[0]
+            .expires_in
+            .is_some_and(|s| (59..=60).contains(&s))
+    );
+    assert_eq!(entries[1].expires_in, None);
+}
+
+#[test]
+fn store_entries_skip_expired() {
+    let kv = KvStore::new();
+    kv.set("temp".into(), "v".into(), 1);
+    std::thread::sleep(Duration::from_millis(1100));
+    assert!(kv.entries(None, 100).is_empty());
+    assert!(kv.get_with_metadata("temp").is_none());
+}
@@ -1,12 +1,52 @@
+pub mod activity;
+pub mod auth;
+pub mod client;
+pub mod d1_compat;
 pub mod db;
 pub mod kv;
+pub mod protocol;
+pub mod runtime;
+pub mod schema_diff;
 pub mod server;
+pub mod typegen;
+
+#[cfg(test)]
+mod activity_tests;
+
+#[cfg(test)]
+mod auth_tests;
+
+#[cfg(test)]
+mod d1_compat_tests;
+
+#[cfg(test)]
+mod db_tests;
 
 #[cfg(test)]
 mod kv_tests;
 
+#[cfg(test)]
+mod protocol_tests;
+
+#[cfg(test)]
+mod runtime_tests;
+
+#[cfg(test)]
+mod schema_diff_tests;
+
+#[cfg(test)]
+mod typegen_tests;
+
 use std::path::{Path, PathBuf};
 
+/// Bootstrap script of `nrz dev` in the data directory.
+pub const BOOTSTRAP_FILE: &str = "bootstrap.mjs";
+
+/// Bootstrap script of `nrz emulator start` in the data directory. Apart
+/// from [`BOOTSTRAP_FILE`], so the two can run in one project without
+/// replacing each other's session token.
+pub const EMULATOR_BOOTSTRAP_FILE: &str = "emulator-bootstrap.mjs";
+
 /// Data directory for local emulator state.
 ///
 /// Located at `<project>/.onreza/data/` — should be gitignored.
@@ -20,3 +60,20 @@ pub fn ensure_data_dir(project_dir: &Path) -> anyhow::Result<PathBuf> {
     std::fs::create_dir_all(&dir)?;
     Ok(dir)
 }
+
+/// Write a file only the current user can read, for files holding the
+/// session token.
+pub fn write_private(path: &Path, contents: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))?;
+    // `mode` only applies to new files
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    std::io::Write::write_all(&mut file, contents.as_ref())?;
+    Ok(())
+}
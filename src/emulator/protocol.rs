@@ -0,0 +1,272 @@
+//! Wire types of the emulator HTTP API.
+//!
+//! Shared by the server and [`super::client`], and mirrored by hand in
+//! the JS bootstrap (`dev::inject`).
+//!
+//! KV and DB requests carry [`PROTOCOL_HEADER`]; the server rejects
+//! clients speaking another [`PROTOCOL_VERSION`]. Clients learn the
+//! server's version and [`CAPABILITIES`] from [`HEALTH`], which is not
+//! versioned.
+
+use serde::{Deserialize, Serialize};
+
+/// Version of the request and response shapes below. Bump it on any
+/// change an older client or server would misread.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Request header with the client's [`PROTOCOL_VERSION`].
+pub const PROTOCOL_HEADER: &str = "x-nrz-protocol";
+
+/// Operations this server supports, named `<binding>` or
+/// `<binding>.<feature>`. Additions within a protocol version only add
+/// capabilities.
+pub const CAPABILITIES: &[&str] = &["kv", "kv.metadata", "db", "db.batch", "db.exec", "rpc"];
+
+pub const HEALTH: &str = "/__nrz/health";
+pub const KV_GET: &str = "/__nrz/kv/get";
+pub const KV_GET_WITH_METADATA: &str = "/__nrz/kv/getWithMetadata";
+pub const KV_SET: &str = "/__nrz/kv/set";
+pub const KV_DELETE: &str = "/__nrz/kv/delete";
+pub const KV_HAS: &str = "/__nrz/kv/has";
+pub const KV_LIST: &str = "/__nrz/kv/list";
+pub const DB_QUERY: &str = "/__nrz/db/query";
+pub const DB_BATCH: &str = "/__nrz/db/batch";
+pub const DB_EXEC: &str = "/__nrz/db/exec";
+/// Batched calls; the body is an array of [`RpcCall`]
+pub const RPC: &str = "/__nrz/rpc";
+
+/// KV operation, with the positional arguments of the `ONREZA.kv` method
+/// of the same name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KvRequest {
+    pub args: Vec<serde_json::Value>,
+}
+
+/// Options object accepted as the third argument of `kv.set`. A plain
+/// number is accepted too, as the TTL.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KvSetOptions {
+    /// Seconds until the key expires
+    #[serde(
+        default,
+        rename = "expirationTtl",
+        alias = "ttl",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expiration_ttl: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Response of `kv.getWithMetadata`; both fields are null for a missing
+/// key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KvValueWithMetadata {
+    pub value: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// What a D1 prepared statement returns, after the method called on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryMode {
+    /// Rows as objects
+    All,
+    /// First row, or one column of it
+    First,
+    /// No rows, only `meta`
+    Run,
+    /// Rows as arrays
+    Raw,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbQueryRequest {
+    pub sql: String,
+    #[serde(default)]
+    pub bindings: Vec<serde_json::Value>,
+    pub mode: QueryMode,
+    /// Column to return in [`QueryMode::First`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    /// Prepend a row of column names in [`QueryMode::Raw`]
+    #[serde(
+        default,
+        rename = "columnNames",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub column_names: Option<bool>,
+}
+
+impl DbQueryRequest {
+    pub fn new(sql: impl Into<String>, bindings: Vec<serde_json::Value>, mode: QueryMode) -> Self {
+        Self {
+            sql: sql.into(),
+            bindings,
+            mode,
+            column: None,
+            column_names: None,
+        }
+    }
+}
+
+/// Statements run in order; the batch stops at the first error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbBatchRequest {
+    pub statements: Vec<DbBatchStatement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbBatchStatement {
+    pub sql: String,
+    #[serde(default)]
+    pub bindings: Vec<serde_json::Value>,
+}
+
+/// Script of one or more statements without bindings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbExecRequest {
+    pub sql: String,
+}
+
+/// Result of a query, shaped like D1's.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct D1Response {
+    /// Depends on the [`QueryMode`]: an array of rows, one row or value
+    /// (or null), or an empty array
+    pub results: serde_json::Value,
+    pub success: bool,
+    pub meta: D1Meta,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct D1Meta {
+    pub changes: i64,
+    pub last_row_id: i64,
+    /// Seconds
+    pub duration: f64,
+}
+
+/// One operation in an [`RPC`] batch.
+///
+/// `method` names a route above (`kv.get` for [`KV_GET`], `db.query` for
+/// [`DB_QUERY`]), and `params` is the body that route takes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcCall {
+    /// Chosen by the client to match the response to the call
+    pub id: u64,
+    pub method: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+impl RpcCall {
+    pub fn new(id: u64, method: &str, params: impl Serialize) -> serde_json::Result<Self> {
+        Ok(Self {
+            id,
+            method: method.to_string(),
+            params: serde_json::to_value(params)?,
+        })
+    }
+}
+
+/// Outcome of one [`RpcCall`], failed if `error` is set. Responses come
+/// back in the order of the calls.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub id: u64,
+    /// What the route would have returned; left out when null
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub result: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+/// Route of an RPC method, e.g. `/__nrz/kv/get` for `kv.get`.
+pub fn method_route(method: &str) -> String {
+    format!("/__nrz/{}", method.replace('.', "/"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    /// HTTP status the route would have answered with
+    pub code: u16,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub status: String,
+    /// Version of nrz serving the API; empty before protocol versioning
+    #[serde(default)]
+    pub version: String,
+    /// [`PROTOCOL_VERSION`] of the server; 0 before protocol versioning
+    #[serde(default)]
+    pub protocol: u32,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+impl HealthResponse {
+    /// Health of this server.
+    pub fn current() -> Self {
+        Self {
+            status: "ok".into(),
+            version: env!("CARGO_PKG_VERSION").into(),
+            protocol: PROTOCOL_VERSION,
+            capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    /// Check that a client speaking [`PROTOCOL_VERSION`] and needing
+    /// `required` capabilities can use this server.
+    pub fn check_compatible(&self, required: &[&str]) -> Result<(), String> {
+        if self.protocol != PROTOCOL_VERSION {
+            let server = if self.protocol == 0 {
+                "an nrz release without protocol versioning".to_string()
+            } else {
+                format!("nrz {} (protocol v{})", self.version, self.protocol)
+            };
+            return Err(format!(
+                "emulator runs {server}, but this client speaks protocol v{PROTOCOL_VERSION} \
+                 (nrz {}); run the emulator and client from the same nrz version",
+                env!("CARGO_PKG_VERSION")
+            ));
+        }
+        let missing: Vec<&str> = required
+            .iter()
+            .copied()
+            .filter(|c| !self.capabilities.iter().any(|have| have == c))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "emulator (nrz {}) does not support {}; upgrade nrz",
+                self.version,
+                missing.join(", ")
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Check the [`PROTOCOL_HEADER`] of a KV or DB request.
+pub fn check_request_version(header: Option<&str>) -> Result<(), String> {
+    let server = format!(
+        "this emulator (nrz {}) speaks protocol v{PROTOCOL_VERSION}",
+        env!("CARGO_PKG_VERSION")
+    );
+    match header.map(|h| h.trim().parse::<u32>()) {
+        Some(Ok(PROTOCOL_VERSION)) => Ok(()),
+        Some(Ok(version)) => Err(format!(
+            "nrz: client speaks emulator protocol v{version}, but {server}. \
+             Restart the dev command so it loads the bootstrap written by this emulator, \
+             or use the same nrz version for client and emulator"
+        )),
+        Some(Err(_)) => Err(format!("nrz: invalid {PROTOCOL_HEADER} header; {server}")),
+        None => Err(format!(
+            "nrz: request without {PROTOCOL_HEADER} header, likely from a bootstrap \
+             generated by an older nrz; {server}. Restart the dev command so it loads \
+             the bootstrap written by this emulator"
+        )),
+    }
+}
@@ -0,0 +1,75 @@
+//! Unit tests for emulator protocol versioning
+
+use super::protocol::{
+    CAPABILITIES, HealthResponse, PROTOCOL_VERSION, QueryMode, check_request_version,
+};
+
+#[test]
+fn current_health_has_every_capability() {
+    let health = HealthResponse::current();
+    assert_eq!(health.protocol, PROTOCOL_VERSION);
+    assert_eq!(health.check_compatible(CAPABILITIES), Ok(()));
+}
+
+#[test]
+fn health_from_before_versioning_parses_as_protocol_zero() {
+    let health: HealthResponse = serde_json::from_str(r#"{"status":"ok"}"#).unwrap();
+    assert_eq!(health.protocol, 0);
+    let err = health.check_compatible(&[]).unwrap_err();
+    assert!(err.contains("without protocol versioning"), "{err}");
+}
+
+#[test]
+fn other_protocol_versions_are_incompatible() {
+    let mut health = HealthResponse::current();
+    health.protocol = PROTOCOL_VERSION + 1;
+    health.version = "9.9.9".into();
+    let err = health.check_compatible(&[]).unwrap_err();
+    assert!(
+        err.contains(&format!("nrz 9.9.9 (protocol v{})", PROTOCOL_VERSION + 1)),
+        "{err}"
+    );
+}
+
+#[test]
+fn missing_capabilities_are_named() {
+    let mut health = HealthResponse::current();
+    health.capabilities.retain(|c| !c.starts_with("db"));
+    let err = health
+        .check_compatible(&["kv", "db", "db.batch"])
+        .unwrap_err();
+    assert!(err.contains("does not support db, db.batch"), "{err}");
+    assert_eq!(health.check_compatible(&["kv"]), Ok(()));
+}
+
+#[test]
+fn request_version_header() {
+    assert_eq!(
+        check_request_version(Some(&PROTOCOL_VERSION.to_string())),
+        Ok(())
+    );
+    assert!(
+        check_request_version(None)
+            .unwrap_err()
+            .contains("older nrz")
+    );
+    assert!(
+        check_request_version(Some("0"))
+            .unwrap_err()
+            .contains("client speaks emulator protocol v0")
+    );
+    assert!(
+        check_request_version(Some("one"))
+            .unwrap_err()
+            .contains("invalid x-nrz-protocol header")
+    );
+}
+
+#[test]
+fn query_modes_are_lowercase_on_the_wire() {
+    assert_eq!(
+        serde_json::to_string(&QueryMode::First).unwrap(),
+        r#""first""#
+    );
+    assert!(serde_json::from_str::<QueryMode>(r#""All""#).is_err());
+}
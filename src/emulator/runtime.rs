@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// File in the data directory describing a running `nrz dev`.
+pub const RUNTIME_FILE: &str = "runtime.json";
+
+/// Ports of a running dev session, written to
+/// `.onreza/data/runtime.json` so other tools can find the emulator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeInfo {
+    /// Process id of `nrz dev`
+    pub pid: u32,
+    /// Port the app is served on
+    pub port: u16,
+    /// Port the framework dev server listens on (same as `port` without
+    /// the context proxy)
+    pub upstream_port: u16,
+    pub emulator_port: u16,
+    /// Base URL of the emulator HTTP API
+    pub emulator_url: String,
+    /// Unix socket serving the same API, when the emulator bound one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emulator_socket: Option<PathBuf>,
+    /// Session token for the emulator API (`Authorization: Bearer`)
+    pub token: String,
+    /// Unix timestamp (seconds)
+    pub started_at: u64,
+}
+
+impl RuntimeInfo {
+    pub fn new(port: u16, upstream_port: u16, emulator_port: u16, token: &str) -> Self {
+        Self {
+            pid: std::process::id(),
+            port,
+            upstream_port,
+            emulator_port,
+            emulator_url: format!("http://127.0.0.1:{emulator_port}"),
+            emulator_socket: None,
+            token: token.to_string(),
+            started_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(RUNTIME_FILE)
+    }
+
+    /// Write the runtime file, readable only by the current user since it
+    /// holds the token.
+    pub fn write(&self, data_dir: &Path) -> anyhow::Result<()> {
+        super::write_private(&Self::path(data_dir), serde_json::to_string_pretty(self)?)
+    }
+
+    /// Read the runtime file, ignoring it when missing, unreadable or left
+    /// behind by a process that no longer runs.
+    pub fn read(data_dir: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(Self::path(data_dir)).ok()?;
+        let info: Self = serde_json::from_str(&content).ok()?;
+        info.is_alive().then_some(info)
+    }
+
+    /// Remove the runtime file if it belongs to this process.
+    pub fn remove(data_dir: &Path) {
+        let path = Self::path(data_dir);
+        let ours = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<Self>(&c).ok())
+            .is_some_and(|info| info.pid == std::process::id());
+        if ours {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[cfg(unix)]
+    fn is_alive(&self) -> bool {
+        // Signal 0 checks for existence; EPERM still means it exists
+        let result = unsafe { libc::kill(self.pid as libc::pid_t, 0) };
+        result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    #[cfg(not(unix))]
+    fn is_alive(&self) -> bool {
+        true
+    }
+}
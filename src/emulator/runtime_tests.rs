@@ -0,0 +1,70 @@
+//! Unit tests for the dev runtime file
+
+use super::runtime::{RUNTIME_FILE, RuntimeInfo};
+
+#[test]
+fn write_and_read_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let info = RuntimeInfo::new(4321, 40123, 4322, "secret");
+    info.write(dir.path()).unwrap();
+
+    let read = RuntimeInfo::read(dir.path()).unwrap();
+    assert_eq!(read, info);
+    assert_eq!(read.emulator_url, "http://127.0.0.1:4322");
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join(RUNTIME_FILE)).unwrap())
+            .unwrap();
+    assert_eq!(json["emulatorPort"], 4322);
+    assert_eq!(json["upstreamPort"], 40123);
+    assert_eq!(json["token"], "secret");
+}
+
+#[cfg(unix)]
+#[test]
+fn runtime_file_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    RuntimeInfo::new(4321, 4321, 4322, "secret")
+        .write(dir.path())
+        .unwrap();
+    let mode = std::fs::metadata(dir.path().join(RUNTIME_FILE))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[test]
+fn read_missing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(RuntimeInfo::read(dir.path()).is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn read_ignores_dead_process() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut info = RuntimeInfo::new(4321, 4321, 4322, "secret");
+    // Above the default pid_max on Linux and macOS
+    info.pid = 99_999_999;
+    info.write(dir.path()).unwrap();
+    assert!(RuntimeInfo::read(dir.path()).is_none());
+}
+
+#[test]
+fn remove_only_own_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut other = RuntimeInfo::new(4321, 4321, 4322, "secret");
+    other.pid = std::process::id() + 1;
+    other.write(dir.path()).unwrap();
+    RuntimeInfo::remove(dir.path());
+    assert!(dir.path().join(RUNTIME_FILE).exists());
+
+    RuntimeInfo::new(4321, 4321, 4322, "secret")
+        .write(dir.path())
+        .unwrap();
+    RuntimeInfo::remove(dir.path());
+    assert!(!dir.path().join(RUNTIME_FILE).exists());
+}
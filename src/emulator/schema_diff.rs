@@ -0,0 +1,405 @@
+//! Schema comparison and migration generation.
+
+use std::fmt;
+
+use super::db::{Column, Index, Schema, Table, quote_ident};
+
+/// One difference between two schemas, describing how to get from the
+/// `from` schema to the `to` schema.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    CreateTable(Table),
+    DropTable(String),
+    AddColumn {
+        table: String,
+        column: Column,
+    },
+    DropColumn {
+        table: String,
+        column: String,
+    },
+    AlterColumn {
+        table: String,
+        from: Column,
+        to: Column,
+    },
+    /// Primary key, UNIQUE constraints or foreign keys differ
+    AlterConstraints {
+        table: String,
+    },
+    CreateIndex {
+        table: String,
+        index: Index,
+    },
+    DropIndex {
+        table: String,
+        name: String,
+    },
+    CreateView {
+        name: String,
+        sql: Option<String>,
+    },
+    DropView(String),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CreateTable(t) => write!(f, "+ table {}", t.name),
+            Self::DropTable(name) => write!(f, "- table {name}"),
+            Self::AddColumn { table, column } => {
+                write!(f, "+ column {table}.{} {}", column.name, describe(column))
+            }
+            Self::DropColumn { table, column } => write!(f, "- column {table}.{column}"),
+            Self::AlterColumn { table, from, to } => write!(
+                f,
+                "~ column {table}.{}: {} -> {}",
+                to.name,
+                describe(from),
+                describe(to)
+            ),
+            Self::AlterConstraints { table } => write!(
+                f,
+                "~ table {table}: primary key, unique or foreign key constraints differ"
+            ),
+            Self::CreateIndex { table, index } => write!(
+                f,
+                "+ index {} on {table} ({})",
+                index.name,
+                index.columns.join(", ")
+            ),
+            Self::DropIndex { table, name } => write!(f, "- index {name} on {table}"),
+            Self::CreateView { name, .. } => write!(f, "+ view {name}"),
+            Self::DropView(name) => write!(f, "- view {name}"),
+        }
+    }
+}
+
+fn describe(col: &Column) -> String {
+    let mut parts = vec![if col.decl_type.is_empty() {
+        "(no type)".to_string()
+    } else {
+        col.decl_type.clone()
+    }];
+    if col.primary_key {
+        parts.push("PRIMARY KEY".into());
+    }
+    if col.not_null {
+        parts.push("NOT NULL".into());
+    }
+    if let Some(ref d) = col.default {
+        parts.push(format!("DEFAULT {d}"));
+    }
+    parts.join(" ")
+}
+
+/// Compare two schemas. Tables, columns, indexes and views are compared;
+/// triggers are not.
+pub fn diff(from: &Schema, to: &Schema) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for table in &from.tables {
+        if to.table(&table.name).is_none() {
+            changes.push(Change::DropTable(table.name.clone()));
+        }
+    }
+
+    for table in &to.tables {
+        let Some(old) = from.table(&table.name) else {
+            changes.push(Change::CreateTable(table.clone()));
+            for index in table.indexes.iter().filter(|i| i.sql.is_some()) {
+                changes.push(Change::CreateIndex {
+                    table: table.name.clone(),
+                    index: index.clone(),
+                });
+            }
+            continue;
+        };
+
+        for col in &old.columns {
+            if !table.columns.iter().any(|c| c.name == col.name) {
+                changes.push(Change::DropColumn {
+                    table: table.name.clone(),
+                    column: col.name.clone(),
+                });
+            }
+        }
+        for col in &table.columns {
+            match old.columns.iter().find(|c| c.name == col.name) {
+                None => changes.push(Change::AddColumn {
+                    table: table.name.clone(),
+                    column: col.clone(),
+                }),
+                Some(prev) if prev != col => changes.push(Change::AlterColumn {
+                    table: table.name.clone(),
+                    from: prev.clone(),
+                    to: col.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+
+        if constraints(old) != constraints(table) {
+            changes.push(Change::AlterConstraints {
+                table: table.name.clone(),
+            });
+        }
+
+        let named = |t: &Table| -> Vec<Index> {
+            t.indexes
+                .iter()
+                .filter(|i| i.sql.is_some())
+                .cloned()
+                .collect()
+        };
+        let (old_indexes, new_indexes) = (named(old), named(table));
+        for idx in &old_indexes {
+            if !new_indexes.iter().any(|i| same_index(i, idx)) {
+                changes.push(Change::DropIndex {
+                    table: table.name.clone(),
+                    name: idx.name.clone(),
+                });
+            }
+        }
+        for idx in &new_indexes {
+            if !old_indexes.iter().any(|i| same_index(i, idx)) {
+                changes.push(Change::CreateIndex {
+                    table: table.name.clone(),
+                    index: idx.clone(),
+                });
+            }
+        }
+    }
+
+    for view in &from.views {
+        match to.view(&view.name) {
+            None => changes.push(Change::DropView(view.name.clone())),
+            Some(new) if normalize(&new.sql) != normalize(&view.sql) => {
+                changes.push(Change::DropView(view.name.clone()));
+                changes.push(Change::CreateView {
+                    name: new.name.clone(),
+                    sql: new.sql.clone(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for view in &to.views {
+        if from.view(&view.name).is_none() {
+            changes.push(Change::CreateView {
+                name: view.name.clone(),
+                sql: view.sql.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Constraint fingerprint: primary key, automatic UNIQUE indexes and
+/// foreign keys. Changing any of these requires rebuilding the table.
+#[derive(PartialEq)]
+struct Constraints {
+    primary_key: Vec<String>,
+    unique: Vec<(Vec<String>, bool)>,
+    foreign_keys: String,
+}
+
+fn constraints(t: &Table) -> Constraints {
+    let mut unique: Vec<(Vec<String>, bool)> = t
+        .indexes
+        .iter()
+        .filter(|i| i.sql.is_none())
+        .map(|i| (i.columns.clone(), i.unique))
+        .collect();
+    unique.sort();
+    Constraints {
+        primary_key: t.primary_key.clone(),
+        unique,
+        foreign_keys: serde_json::to_string(&t.foreign_keys).unwrap_or_default(),
+    }
+}
+
+fn same_index(a: &Index, b: &Index) -> bool {
+    a.name == b.name && normalize(&a.sql) == normalize(&b.sql)
+}
+
+fn normalize(sql: &Option<String>) -> String {
+    sql.as_deref()
+        .unwrap_or("")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Rewrite a `CREATE TABLE name ...` statement to create `__nrz_new_<name>`.
+fn rename_create(create: &str, name: &str) -> Option<String> {
+    let upper = create.to_ascii_uppercase();
+    let mut pos = upper.find("TABLE")? + "TABLE".len();
+    let skip_ws = |p: usize| p + create[p..].len() - create[p..].trim_start().len();
+    pos = skip_ws(pos);
+    if upper[pos..].starts_with("IF NOT EXISTS") {
+        pos = skip_ws(pos + "IF NOT EXISTS".len());
+    }
+
+    // The name is a bare word or quoted with "", ``, [] or ''
+    let rest = &create[pos..];
+    let end = match rest.chars().next()? {
+        q @ ('"' | '`' | '\'' | '[') => {
+            let close = if q == '[' { ']' } else { q };
+            rest[1..].find(close)? + 2
+        }
+        _ => rest
+            .find(|c: char| c.is_whitespace() || c == '(')
+            .unwrap_or(rest.len()),
+    };
+    Some(format!(
+        "{}{}{}",
+        &create[..pos],
+        quote_ident(&format!("__nrz_new_{name}")),
+        &rest[end..]
+    ))
+}
+
+/// Whether `ALTER TABLE ADD COLUMN` can add this column.
+fn can_add_column(col: &Column) -> bool {
+    !col.primary_key && (!col.not_null || col.default.is_some())
+}
+
+/// Generate SQL that migrates the `from` schema of [`diff`] to `to`.
+///
+/// Changes SQLite cannot apply in place (altered columns, constraints,
+/// columns that `ADD COLUMN` rejects) rebuild the table: the new definition
+/// is created under a scratch name, the shared columns are copied over, the
+/// old table is replaced and its indexes and triggers are recreated.
+pub fn migration_sql(to: &Schema, changes: &[Change]) -> String {
+    let mut rebuild: Vec<&str> = Vec::new();
+    for change in changes {
+        let table = match change {
+            Change::AlterColumn { table, .. } | Change::AlterConstraints { table } => table,
+            Change::AddColumn { table, column } if !can_add_column(column) => table,
+            _ => continue,
+        };
+        if !rebuild.contains(&table.as_str()) {
+            rebuild.push(table);
+        }
+    }
+
+    let mut sql = String::from("-- Generated by `nrz db diff`. Review before applying.\n");
+
+    for change in changes {
+        match change {
+            Change::DropView(name) => {
+                sql.push_str(&format!("DROP VIEW IF EXISTS {};\n", quote_ident(name)));
+            }
+            Change::DropIndex { table, name } if !rebuild.contains(&table.as_str()) => {
+                sql.push_str(&format!("DROP INDEX IF EXISTS {};\n", quote_ident(name)));
+            }
+            Change::DropTable(name) => {
+                sql.push_str(&format!("DROP TABLE IF EXISTS {};\n", quote_ident(name)));
+            }
+            _ => {}
+        }
+    }
+
+    for change in changes {
+        match change {
+            Change::CreateTable(table) => {
+                if let Some(ref create) = table.sql {
+                    sql.push_str(&format!("{create};\n"));
+                }
+            }
+            Change::AddColumn { table, column } if !rebuild.contains(&table.as_str()) => {
+                let mut def = format!("{} {}", quote_ident(&column.name), column.decl_type);
+                if column.not_null {
+                    def.push_str(" NOT NULL");
+                }
+                if let Some(ref d) = column.default {
+                    def.push_str(&format!(" DEFAULT {d}"));
+                }
+                sql.push_str(&format!(
+                    "ALTER TABLE {} ADD COLUMN {};\n",
+                    quote_ident(table),
+                    def.trim_end()
+                ));
+            }
+            Change::DropColumn { table, column } if !rebuild.contains(&table.as_str()) => {
+                sql.push_str(&format!(
+                    "ALTER TABLE {} DROP COLUMN {};\n",
+                    quote_ident(table),
+                    quote_ident(column)
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    if !rebuild.is_empty() {
+        // Dropping the old table would otherwise cascade or fail on rows
+        // that reference it. `foreign_keys` is a no-op inside a transaction,
+        // where deferring the checks to commit is the best available option.
+        sql.push_str("PRAGMA foreign_keys = OFF;\n");
+        sql.push_str("PRAGMA defer_foreign_keys = ON;\n");
+        for name in &rebuild {
+            let Some(table) = to.table(name) else {
+                continue;
+            };
+            let Some(create) = table.sql.as_deref().and_then(|s| rename_create(s, name)) else {
+                continue;
+            };
+            // Build the new table under a scratch name and swap it in, so
+            // references from other tables keep pointing at `name`
+            let new = format!("__nrz_new_{name}");
+            sql.push_str(&format!("{create};\n"));
+
+            let shared: Vec<String> = table
+                .columns
+                .iter()
+                .filter(|c| {
+                    !changes.iter().any(|ch| {
+                        matches!(ch, Change::AddColumn { table: t, column } if t == name && column.name == c.name)
+                    })
+                })
+                .map(|c| quote_ident(&c.name))
+                .collect();
+            if !shared.is_empty() {
+                let cols = shared.join(", ");
+                sql.push_str(&format!(
+                    "INSERT INTO {} ({cols}) SELECT {cols} FROM {};\n",
+                    quote_ident(&new),
+                    quote_ident(name)
+                ));
+            }
+            sql.push_str(&format!("DROP TABLE {};\n", quote_ident(name)));
+            sql.push_str(&format!(
+                "ALTER TABLE {} RENAME TO {};\n",
+                quote_ident(&new),
+                quote_ident(name)
+            ));
+            let recreate = table.indexes.iter().filter_map(|i| i.sql.as_ref());
+            let triggers = table.triggers.iter().filter_map(|t| t.sql.as_ref());
+            for stmt in recreate.chain(triggers) {
+                sql.push_str(&format!("{stmt};\n"));
+            }
+        }
+        sql.push_str("PRAGMA foreign_keys = ON;\n");
+    }
+
+    for change in changes {
+        match change {
+            Change::CreateIndex { table, index } if !rebuild.contains(&table.as_str()) => {
+                if let Some(ref create) = index.sql {
+                    sql.push_str(&format!("{create};\n"));
+                }
+            }
+            Change::CreateView {
+                sql: Some(create), ..
+            } => {
+                sql.push_str(&format!("{create};\n"));
+            }
+            _ => {}
+        }
+    }
+
+    sql
+}
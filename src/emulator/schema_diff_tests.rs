@@ -0,0 +1,195 @@
+//! Unit tests for schema diffing and migration generation
+
+use rusqlite::Connection;
+
+use super::db::{Schema, introspect};
+use super::schema_diff::{Change, diff, migration_sql};
+
+fn schema(sql: &str) -> (Connection, Schema) {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql).unwrap();
+    let schema = introspect(&conn).unwrap();
+    (conn, schema)
+}
+
+/// Apply the generated migration to `from` and check it now matches `to`.
+fn assert_roundtrip(from_sql: &str, to_sql: &str) -> Connection {
+    let (conn, from) = schema(from_sql);
+    let (_, to) = schema(to_sql);
+    let changes = diff(&from, &to);
+    let migration = migration_sql(&to, &changes);
+    conn.execute_batch(&migration)
+        .unwrap_or_else(|e| panic!("migration failed: {e}\n{migration}"));
+
+    let after = introspect(&conn).unwrap();
+    let remaining = diff(&after, &to);
+    assert!(
+        remaining.is_empty(),
+        "schema still differs: {remaining:?}\n{migration}"
+    );
+    conn
+}
+
+#[test]
+fn identical_schemas() {
+    let sql = "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);
+               CREATE INDEX idx_t_name ON t (name);";
+    let (_, a) = schema(sql);
+    let (_, b) = schema(sql);
+    assert!(diff(&a, &b).is_empty());
+}
+
+#[test]
+fn whitespace_in_index_sql_is_ignored() {
+    let (_, a) = schema("CREATE TABLE t (n TEXT); CREATE INDEX i ON t (n);");
+    let (_, b) = schema("CREATE TABLE t (n TEXT); CREATE INDEX i ON t   (n);");
+    assert!(diff(&a, &b).is_empty());
+}
+
+#[test]
+fn create_and_drop_tables() {
+    let (_, from) = schema("CREATE TABLE old (id INTEGER)");
+    let (_, to) = schema("CREATE TABLE new (id INTEGER)");
+    let changes = diff(&from, &to);
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0], Change::DropTable("old".into()));
+    assert!(matches!(&changes[1], Change::CreateTable(t) if t.name == "new"));
+    assert_eq!(changes[0].to_string(), "- table old");
+    assert_eq!(changes[1].to_string(), "+ table new");
+
+    assert_roundtrip(
+        "CREATE TABLE old (id INTEGER)",
+        "CREATE TABLE new (id INTEGER)",
+    );
+}
+
+#[test]
+fn add_nullable_column_uses_alter_table() {
+    let (_, from) = schema("CREATE TABLE users (id INTEGER PRIMARY KEY)");
+    let (_, to) = schema("CREATE TABLE users (id INTEGER PRIMARY KEY, bio TEXT DEFAULT '')");
+    let changes = diff(&from, &to);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].to_string(), "+ column users.bio TEXT DEFAULT ''");
+
+    let sql = migration_sql(&to, &changes);
+    assert!(sql.contains("ALTER TABLE \"users\" ADD COLUMN \"bio\" TEXT DEFAULT '';"));
+    assert!(!sql.contains("RENAME"));
+}
+
+#[test]
+fn drop_column() {
+    let conn = assert_roundtrip(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, nickname TEXT);
+         INSERT INTO users VALUES (1, 'a');",
+        "CREATE TABLE users (id INTEGER PRIMARY KEY)",
+    );
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM users", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn altered_column_rebuilds_table_and_keeps_data() {
+    let from = "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT);
+                CREATE INDEX idx_email ON users (email);
+                INSERT INTO users VALUES (1, 'a@example.com');";
+    let to = "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL, age INTEGER NOT NULL DEFAULT 0);
+              CREATE INDEX idx_email ON users (email);";
+
+    let (_, a) = schema(from);
+    let (_, b) = schema(to);
+    let changes = diff(&a, &b);
+    assert!(
+        changes
+            .iter()
+            .any(|c| c.to_string() == "~ column users.email: TEXT -> TEXT NOT NULL")
+    );
+    let sql = migration_sql(&b, &changes);
+    assert!(sql.contains("CREATE TABLE \"__nrz_new_users\" (id INTEGER PRIMARY KEY"));
+    assert!(sql.contains("ALTER TABLE \"__nrz_new_users\" RENAME TO \"users\";"));
+
+    let conn = assert_roundtrip(from, to);
+    let email: String = conn
+        .query_row("SELECT email FROM users WHERE id = 1", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(email, "a@example.com");
+}
+
+#[test]
+fn unique_constraint_change_is_detected() {
+    let from = "CREATE TABLE t (id INTEGER PRIMARY KEY, code TEXT)";
+    let to = "CREATE TABLE t (id INTEGER PRIMARY KEY, code TEXT UNIQUE)";
+    let (_, a) = schema(from);
+    let (_, b) = schema(to);
+    assert!(
+        diff(&a, &b)
+            .iter()
+            .any(|c| matches!(c, Change::AlterConstraints { table } if table == "t"))
+    );
+    assert_roundtrip(from, to);
+}
+
+#[test]
+fn foreign_keys_survive_rebuild() {
+    let conn = assert_roundtrip(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+         CREATE TABLE posts (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users(id));
+         INSERT INTO users VALUES (1, 'a');
+         INSERT INTO posts VALUES (1, 1);",
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL DEFAULT '');
+         CREATE TABLE posts (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users(id));",
+    );
+    let violations: i64 = conn
+        .query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |r| {
+            r.get(0)
+        })
+        .unwrap();
+    assert_eq!(violations, 0);
+}
+
+#[test]
+fn quoted_table_names_are_rebuilt() {
+    assert_roundtrip(
+        "CREATE TABLE \"order items\" (id INTEGER PRIMARY KEY, qty INTEGER)",
+        "CREATE TABLE \"order items\" (id INTEGER PRIMARY KEY, qty INTEGER NOT NULL)",
+    );
+}
+
+#[test]
+fn index_changes() {
+    let from = "CREATE TABLE t (a TEXT, b TEXT);
+                CREATE INDEX idx_a ON t (a);
+                CREATE INDEX idx_old ON t (b);";
+    let to = "CREATE TABLE t (a TEXT, b TEXT);
+              CREATE INDEX idx_a ON t (a, b);
+              CREATE UNIQUE INDEX idx_new ON t (b);";
+    let (_, x) = schema(from);
+    let (_, y) = schema(to);
+    let lines: Vec<String> = diff(&x, &y).iter().map(|c| c.to_string()).collect();
+    assert!(lines.contains(&"- index idx_a on t".to_string()));
+    assert!(lines.contains(&"+ index idx_a on t (a, b)".to_string()));
+    assert!(lines.contains(&"- index idx_old on t".to_string()));
+    assert!(lines.contains(&"+ index idx_new on t (b)".to_string()));
+    assert_roundtrip(from, to);
+}
+
+#[test]
+fn view_changes() {
+    assert_roundtrip(
+        "CREATE TABLE t (a TEXT, b TEXT);
+         CREATE VIEW v AS SELECT a FROM t;
+         CREATE VIEW gone AS SELECT b FROM t;",
+        "CREATE TABLE t (a TEXT, b TEXT);
+         CREATE VIEW v AS SELECT a, b FROM t;",
+    );
+}
+
+#[test]
+fn new_table_brings_its_indexes() {
+    let (_, from) = schema("");
+    let (_, to) = schema("CREATE TABLE t (a TEXT); CREATE INDEX idx ON t (a);");
+    let changes = diff(&from, &to);
+    assert_eq!(changes.len(), 2);
+    assert!(matches!(&changes[1], Change::CreateIndex { index, .. } if index.name == "idx"));
+}
@@ -1,87 +1,117 @@
+use std::future::IntoFuture;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
-use axum::extract::State;
+use anyhow::Context;
+use axum::extract::{Query, Request, State};
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use axum::routing::{get, post};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{MethodRouter, get, post};
 use axum::{Json, Router};
 use rusqlite::Connection;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use super::activity::{ActivityKind, ActivityLog};
+use super::auth::{self, Guard, require_session};
+use super::d1_compat::{self, CompatMode};
 use super::kv::KvStore;
+use super::protocol::{
+    self, D1Meta, D1Response, DbBatchRequest, DbExecRequest, DbQueryRequest, HealthResponse,
+    KvRequest, KvSetOptions, KvValueWithMetadata, QueryMode, RpcCall, RpcError, RpcResponse,
+};
+
+/// `db_path` for an in-memory database.
+pub const IN_MEMORY: &str = ":memory:";
+
+/// Name of the emulator's Unix socket in the data directory.
+pub const SOCKET_FILE: &str = "emulator.sock";
+
+/// Dashboard page, self-contained so it works offline.
+const UI_HTML: &str = include_str!("ui.html");
 
 /// Local HTTP server for the emulator.
 ///
 /// Runs alongside the framework dev server. The JS bootstrap
-/// (injected into Node.js) proxies ONREZA.kv/db calls to this server.
+/// (injected into Node.js) proxies ONREZA.kv/db calls to this server,
+/// authenticated with the session token.
+#[derive(Clone)]
 pub struct EmulatorServer {
     pub kv: KvStore,
+    /// SQLite database file, or [`IN_MEMORY`]
     pub db_path: PathBuf,
     pub addr: SocketAddr,
+    /// How D1 incompatibilities in SQL are reported.
+    pub d1_compat: CompatMode,
+    /// Recent KV operations and queries (and app requests, when shared
+    /// with the dev proxy), shown on the dashboard.
+    pub activity: ActivityLog,
+    /// Runtime details for the dashboard, such as `ONREZA.env` and the
+    /// default context.
+    pub dev_info: DevInfo,
+    /// Secret required on every request (see [`Guard`]). When empty, as
+    /// by default, [`EmulatorServer::bind`] generates a random one.
+    pub token: String,
+    /// Unix socket to serve the API on as well as `addr`, so local clients
+    /// need no TCP port. Ignored on Windows; when the socket cannot be
+    /// bound the emulator serves TCP only.
+    pub socket: Option<PathBuf>,
 }
 
 #[derive(Clone)]
 struct AppState {
     kv: KvStore,
     db: Arc<Mutex<Connection>>,
+    d1_compat: CompatMode,
+    activity: ActivityLog,
+    dev_info: DevInfo,
 }
 
-// --- Request/Response types ---
-
-#[derive(Deserialize)]
-struct KvRequest {
-    args: Vec<serde_json::Value>,
+/// Runtime details shown on the dashboard, shared with the dev session,
+/// which replaces them when the config or env files change.
+#[derive(Clone, Default)]
+pub struct DevInfo {
+    inner: Arc<Mutex<serde_json::Value>>,
 }
 
-#[derive(Deserialize)]
-struct DbQueryRequest {
-    sql: String,
-    #[serde(default)]
-    bindings: Vec<serde_json::Value>,
-    mode: String,
-    #[serde(default)]
-    column: Option<String>,
-    #[serde(default, rename = "columnNames")]
-    column_names: Option<bool>,
-}
+impl DevInfo {
+    pub fn new(info: serde_json::Value) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(info)),
+        }
+    }
 
-#[derive(Deserialize)]
-struct DbBatchRequest {
-    statements: Vec<DbBatchStatement>,
-}
+    pub fn get(&self) -> serde_json::Value {
+        self.lock().clone()
+    }
 
-#[derive(Deserialize)]
-struct DbBatchStatement {
-    sql: String,
-    #[serde(default)]
-    bindings: Vec<serde_json::Value>,
-}
+    pub fn set(&self, info: serde_json::Value) {
+        *self.lock() = info;
+    }
 
-#[derive(Deserialize)]
-struct DbExecRequest {
-    sql: String,
+    fn lock(&self) -> std::sync::MutexGuard<'_, serde_json::Value> {
+        self.inner.lock().unwrap_or_else(|poisoned| {
+            tracing::warn!("dev info mutex was poisoned, recovering");
+            poisoned.into_inner()
+        })
+    }
 }
 
-#[derive(Serialize)]
-struct D1Response {
-    results: serde_json::Value,
-    success: bool,
-    meta: D1Meta,
-}
+// --- Dashboard query types ---
 
-#[derive(Serialize)]
-struct D1Meta {
-    changes: i64,
-    last_row_id: i64,
-    duration: f64,
+#[derive(Deserialize)]
+struct KvEntriesQuery {
+    prefix: Option<String>,
+    limit: Option<usize>,
 }
 
-#[derive(Serialize)]
-struct HealthResponse {
-    status: &'static str,
+#[derive(Deserialize)]
+struct ActivityQuery {
+    #[serde(default)]
+    since: u64,
 }
 
 type AppError = (StatusCode, String);
@@ -92,62 +122,335 @@ impl EmulatorServer {
             kv,
             db_path,
             addr: SocketAddr::from(([127, 0, 0, 1], port)),
+            d1_compat: CompatMode::default(),
+            activity: ActivityLog::new(),
+            dev_info: DevInfo::default(),
+            token: String::new(),
+            socket: None,
         }
     }
 
-    /// Start the emulator HTTP server.
-    pub async fn start(&self) -> anyhow::Result<()> {
+    /// Emulator for tests: empty in-memory KV and database, on a free port.
+    pub fn ephemeral() -> Self {
+        Self::new(KvStore::new(), PathBuf::from(IN_MEMORY), 0)
+    }
+
+    /// Build the API router for a server listening on `port`, which the
+    /// `Host` check needs. Opens the database. Fails without a token.
+    pub fn router(&self, port: u16) -> anyhow::Result<Router> {
+        anyhow::ensure!(!self.token.is_empty(), "the emulator needs a session token");
         let conn = Connection::open(&self.db_path)?;
         conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")?;
 
         let state = AppState {
             kv: self.kv.clone(),
             db: Arc::new(Mutex::new(conn)),
+            d1_compat: self.d1_compat,
+            activity: self.activity.clone(),
+            dev_info: self.dev_info.clone(),
+        };
+        let guard = Guard {
+            token: self.token.clone(),
+            port,
+        };
+
+        // Versioned API; health and the dashboard are served regardless
+        let api = Router::new()
+            .route(protocol::KV_GET, op(kv_get))
+            .route(protocol::KV_GET_WITH_METADATA, op(kv_get_with_metadata))
+            .route(protocol::KV_SET, op(kv_set))
+            .route(protocol::KV_DELETE, op(kv_delete))
+            .route(protocol::KV_HAS, op(kv_has))
+            .route(protocol::KV_LIST, op(kv_list))
+            .route(protocol::DB_QUERY, op(db_query))
+            .route(protocol::DB_BATCH, op(db_batch))
+            .route(protocol::DB_EXEC, op(db_exec))
+            .route(protocol::RPC, post(rpc))
+            .route_layer(middleware::from_fn(require_protocol));
+
+        Ok(Router::new()
+            .route(protocol::HEALTH, get(health))
+            .merge(api)
+            .route("/__nrz/ui", get(ui))
+            .route("/__nrz/ui/api/kv", get(ui_kv_entries))
+            .route(
+                "/__nrz/ui/api/activity",
+                get(ui_activity).delete(ui_clear_activity),
+            )
+            .route("/__nrz/ui/api/info", get(ui_info))
+            .with_state(state)
+            .layer(middleware::from_fn_with_state(guard, require_session)))
+    }
+
+    /// Start the emulator HTTP server and serve until it fails. Use
+    /// [`EmulatorServer::bind`] to get a handle that can stop it.
+    pub async fn start(&self) -> anyhow::Result<()> {
+        self.clone().bind().await?.wait().await
+    }
+
+    /// Bind `addr` (port 0 picks a free one) and `socket`, and serve in
+    /// the background.
+    ///
+    /// Returns once the server accepts connections. Stop it with
+    /// [`RunningEmulator::shutdown`]; dropping the handle stops it too.
+    pub async fn bind(mut self) -> anyhow::Result<RunningEmulator> {
+        if self.token.is_empty() {
+            self.token = auth::session_token()?;
+        }
+        let listener = tokio::net::TcpListener::bind(self.addr)
+            .await
+            .with_context(|| format!("failed to listen on {}", self.addr))?;
+        let addr = listener.local_addr()?;
+        let app = self.router(addr.port())?;
+        tracing::info!(%addr, "emulator server listening");
+
+        #[cfg(unix)]
+        let unix = self
+            .socket
+            .as_ref()
+            .and_then(|path| match bind_socket(path) {
+                Ok(listener) => {
+                    tracing::info!(socket = %path.display(), "emulator server listening");
+                    Some((listener, path.clone()))
+                }
+                Err(e) => {
+                    tracing::warn!("emulator serves TCP only: {e:#}");
+                    None
+                }
+            });
+
+        // Sending stops the server; so does dropping the handle
+        let (shutdown, signal) = tokio::sync::watch::channel(());
+        let stopped = |mut signal: tokio::sync::watch::Receiver<()>| async move {
+            let _ = signal.changed().await;
         };
+        let socket = {
+            #[cfg(unix)]
+            {
+                unix.as_ref().map(|(_, path)| path.clone())
+            }
+            #[cfg(not(unix))]
+            {
+                None
+            }
+        };
+        let tcp = axum::serve(listener, app.clone())
+            .with_graceful_shutdown(stopped(signal.clone()))
+            .into_future();
+        let task = tokio::spawn(async move {
+            #[cfg(unix)]
+            if let Some((listener, path)) = unix {
+                let uds = axum::serve(listener, app)
+                    .with_graceful_shutdown(stopped(signal))
+                    .into_future();
+                let result = tokio::try_join!(tcp, uds);
+                let _ = std::fs::remove_file(&path);
+                result?;
+                return Ok(());
+            }
+            tcp.await?;
+            Ok(())
+        });
+        Ok(RunningEmulator {
+            addr,
+            socket,
+            token: self.token,
+            shutdown,
+            task: Some(task),
+        })
+    }
+}
 
-        let app = Router::new()
-            .route("/__nrz/health", get(health))
-            .route("/__nrz/kv/get", post(kv_get))
-            .route("/__nrz/kv/set", post(kv_set))
-            .route("/__nrz/kv/delete", post(kv_delete))
-            .route("/__nrz/kv/has", post(kv_has))
-            .route("/__nrz/kv/list", post(kv_list))
-            .route("/__nrz/db/query", post(db_query))
-            .route("/__nrz/db/batch", post(db_batch))
-            .route("/__nrz/db/exec", post(db_exec))
-            .with_state(state);
-
-        let listener = tokio::net::TcpListener::bind(self.addr).await?;
-        tracing::info!(addr = %self.addr, "emulator server listening");
-
-        axum::serve(listener, app).await?;
-        Ok(())
+/// Listen on a Unix socket only the current user can connect to,
+/// replacing one left behind by an emulator that did not shut down.
+#[cfg(unix)]
+fn bind_socket(path: &Path) -> anyhow::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if std::os::unix::net::UnixStream::connect(path).is_ok() {
+        anyhow::bail!("another emulator is listening on {}", path.display());
+    }
+    let _ = std::fs::remove_file(path);
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("failed to listen on {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// A bound emulator server (see [`EmulatorServer::bind`]).
+pub struct RunningEmulator {
+    addr: SocketAddr,
+    socket: Option<PathBuf>,
+    token: String,
+    shutdown: tokio::sync::watch::Sender<()>,
+    /// Server task; `None` once [`RunningEmulator::wait`] has returned its
+    /// result
+    task: Option<tokio::task::JoinHandle<anyhow::Result<()>>>,
+}
+
+impl RunningEmulator {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL of the API, e.g. `http://127.0.0.1:4322`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Unix socket serving the same API, if one was bound.
+    pub fn socket(&self) -> Option<&Path> {
+        self.socket.as_deref()
+    }
+
+    /// Session token for `Authorization: Bearer`.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Stop accepting connections and wait for requests in flight. Does
+    /// nothing once [`RunningEmulator::wait`] has returned.
+    pub async fn shutdown(self) -> anyhow::Result<()> {
+        let _ = self.shutdown.send(());
+        match self.task {
+            Some(task) => task.await.context("emulator server task panicked")?,
+            None => Ok(()),
+        }
+    }
+
+    /// Wait until the server stops on its own, which only happens on
+    /// errors. Cancel-safe, so it can be raced against a shutdown signal.
+    pub async fn wait(&mut self) -> anyhow::Result<()> {
+        let Some(task) = &mut self.task else {
+            return Ok(());
+        };
+        let result = task.await;
+        self.task = None;
+        result.context("emulator server task panicked")?
     }
 }
 
 // --- Health ---
 
 async fn health() -> Json<HealthResponse> {
-    Json(HealthResponse { status: "ok" })
+    Json(HealthResponse::current())
 }
 
-// --- KV handlers ---
+/// Reject KV and DB requests from clients built for another protocol
+/// version, before their arguments are misread.
+async fn require_protocol(req: Request, next: Next) -> Response {
+    let header = req
+        .headers()
+        .get(protocol::PROTOCOL_HEADER)
+        .and_then(|h| h.to_str().ok());
+    match protocol::check_request_version(header) {
+        Ok(()) => next.run(req).await,
+        Err(message) => (StatusCode::UPGRADE_REQUIRED, message).into_response(),
+    }
+}
 
-async fn kv_get(
+// --- Operations ---
+
+/// Route for an operation, taking its request as JSON.
+fn op<T, R>(f: fn(&AppState, T) -> Result<R, AppError>) -> MethodRouter<AppState>
+where
+    T: DeserializeOwned + Send + 'static,
+    R: Serialize + 'static,
+{
+    post(
+        move |State(state): State<AppState>, Json(req): Json<T>| async move { f(&state, req).map(Json) },
+    )
+}
+
+/// Run batched calls in order, each as its route would.
+async fn rpc(
     State(state): State<AppState>,
-    Json(req): Json<KvRequest>,
-) -> Result<impl IntoResponse, AppError> {
+    Json(calls): Json<Vec<RpcCall>>,
+) -> Json<Vec<RpcResponse>> {
+    let responses = calls
+        .into_iter()
+        .map(|call| match dispatch(&state, &call.method, call.params) {
+            Ok(result) => RpcResponse {
+                id: call.id,
+                result,
+                error: None,
+            },
+            Err((status, message)) => RpcResponse {
+                id: call.id,
+                result: serde_json::Value::Null,
+                error: Some(RpcError {
+                    code: status.as_u16(),
+                    message,
+                }),
+            },
+        })
+        .collect();
+    Json(responses)
+}
+
+fn dispatch(
+    state: &AppState,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, AppError> {
+    fn call<T: DeserializeOwned, R: Serialize>(
+        state: &AppState,
+        f: fn(&AppState, T) -> Result<R, AppError>,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, AppError> {
+        let req = serde_json::from_value(params).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("{method}: invalid params: {e}"),
+            )
+        })?;
+        serde_json::to_value(f(state, req)?)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+    }
+
+    match protocol::method_route(method).as_str() {
+        protocol::KV_GET => call(state, kv_get, method, params),
+        protocol::KV_GET_WITH_METADATA => call(state, kv_get_with_metadata, method, params),
+        protocol::KV_SET => call(state, kv_set, method, params),
+        protocol::KV_DELETE => call(state, kv_delete, method, params),
+        protocol::KV_HAS => call(state, kv_has, method, params),
+        protocol::KV_LIST => call(state, kv_list, method, params),
+        protocol::DB_QUERY => call(state, db_query, method, params),
+        protocol::DB_BATCH => call(state, db_batch, method, params),
+        protocol::DB_EXEC => call(state, db_exec, method, params),
+        _ => Err((StatusCode::NOT_FOUND, format!("unknown method: {method}"))),
+    }
+}
+
+// --- KV ---
+
+fn kv_get(state: &AppState, req: KvRequest) -> Result<Option<String>, AppError> {
     let key = req.args.first().and_then(|v| v.as_str()).ok_or((
         StatusCode::BAD_REQUEST,
         "kv.get requires args: [key]".into(),
     ))?;
-    Ok(Json(serde_json::to_value(state.kv.get(key)).unwrap()))
+    let start = Instant::now();
+    let value = state.kv.get(key);
+    state.log_kv(format!("kv.get {key}"), start);
+    Ok(value)
 }
 
-async fn kv_set(
-    State(state): State<AppState>,
-    Json(req): Json<KvRequest>,
-) -> Result<impl IntoResponse, AppError> {
+fn kv_get_with_metadata(state: &AppState, req: KvRequest) -> Result<KvValueWithMetadata, AppError> {
+    let key = req.args.first().and_then(|v| v.as_str()).ok_or((
+        StatusCode::BAD_REQUEST,
+        "kv.getWithMetadata requires args: [key]".into(),
+    ))?;
+    let start = Instant::now();
+    let (value, metadata) = match state.kv.get_with_metadata(key) {
+        Some((value, metadata)) => (Some(value), metadata),
+        None => (None, None),
+    };
+    state.log_kv(format!("kv.getWithMetadata {key}"), start);
+    Ok(KvValueWithMetadata { value, metadata })
+}
+
+fn kv_set(state: &AppState, req: KvRequest) -> Result<&'static str, AppError> {
     let key = req
         .args
         .first()
@@ -166,40 +469,54 @@ async fn kv_set(
             "kv.set requires args: [key, value]".into(),
         ))?
         .to_string();
-    let ttl = req.args.get(2).and_then(|v| v.as_u64()).unwrap_or(0);
-    state.kv.set(key, value, ttl);
-    Ok(Json(serde_json::json!("OK")))
+    // Third argument: a TTL in seconds, or { ttl | expirationTtl, metadata }
+    let options = match req.args.get(2) {
+        Some(serde_json::Value::Number(ttl)) => KvSetOptions {
+            expiration_ttl: ttl.as_u64(),
+            metadata: None,
+        },
+        Some(o @ serde_json::Value::Object(_)) => serde_json::from_value(o.clone())
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("kv.set options: {e}")))?,
+        _ => KvSetOptions::default(),
+    };
+    let ttl = options.expiration_ttl.unwrap_or(0);
+    let metadata = options.metadata.filter(|m| !m.is_null());
+    let summary = format!("kv.set {key}");
+    let start = Instant::now();
+    state.kv.set_with_metadata(key, value, ttl, metadata);
+    state.log_kv(summary, start);
+    Ok("OK")
 }
 
-async fn kv_delete(
-    State(state): State<AppState>,
-    Json(req): Json<KvRequest>,
-) -> Result<impl IntoResponse, AppError> {
+fn kv_delete(state: &AppState, req: KvRequest) -> Result<bool, AppError> {
     let key = req.args.first().and_then(|v| v.as_str()).ok_or((
         StatusCode::BAD_REQUEST,
         "kv.delete requires args: [key]".into(),
     ))?;
-    Ok(Json(serde_json::json!(state.kv.delete(key))))
+    let start = Instant::now();
+    let deleted = state.kv.delete(key);
+    state.log_kv(format!("kv.delete {key}"), start);
+    Ok(deleted)
 }
 
-async fn kv_has(
-    State(state): State<AppState>,
-    Json(req): Json<KvRequest>,
-) -> Result<impl IntoResponse, AppError> {
+fn kv_has(state: &AppState, req: KvRequest) -> Result<bool, AppError> {
     let key = req.args.first().and_then(|v| v.as_str()).ok_or((
         StatusCode::BAD_REQUEST,
         "kv.has requires args: [key]".into(),
     ))?;
-    Ok(Json(serde_json::json!(state.kv.has(key))))
+    let start = Instant::now();
+    let has = state.kv.has(key);
+    state.log_kv(format!("kv.has {key}"), start);
+    Ok(has)
 }
 
-async fn kv_list(
-    State(state): State<AppState>,
-    Json(req): Json<KvRequest>,
-) -> Result<impl IntoResponse, AppError> {
+fn kv_list(state: &AppState, req: KvRequest) -> Result<Vec<String>, AppError> {
     let prefix = req.args.first().and_then(|v| v.as_str());
     let limit = req.args.get(1).and_then(|v| v.as_u64()).unwrap_or(1000) as usize;
-    Ok(Json(serde_json::json!(state.kv.list(prefix, limit))))
+    let start = Instant::now();
+    let keys = state.kv.list(prefix, limit);
+    state.log_kv(format!("kv.list {}", prefix.unwrap_or("")), start);
+    Ok(keys)
 }
 
 // --- DB helpers ---
@@ -318,14 +635,26 @@ fn base64_encode(data: &[u8]) -> String {
     result
 }
 
+fn check_d1_compat(
+    mode: CompatMode,
+    violations: Vec<d1_compat::Violation>,
+) -> Result<(), AppError> {
+    d1_compat::enforce(mode, &violations).map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
 fn execute_query(
     conn: &Connection,
+    compat: CompatMode,
     sql: &str,
     bindings: &[serde_json::Value],
-    mode: &str,
+    mode: QueryMode,
     column: Option<&str>,
     column_names: Option<bool>,
 ) -> Result<D1Response, AppError> {
+    if compat != CompatMode::Off {
+        check_d1_compat(compat, d1_compat::check_statement(sql, bindings))?;
+    }
+
     let start = Instant::now();
     let mut stmt = conn
         .prepare(sql)
@@ -334,12 +663,12 @@ fn execute_query(
     bind_params(&mut stmt, bindings)?;
 
     let results = match mode {
-        "all" => {
+        QueryMode::All => {
             let rows =
                 rows_to_json(&mut stmt).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
             serde_json::json!(rows)
         }
-        "first" => {
+        QueryMode::First => {
             let rows =
                 rows_to_json(&mut stmt).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
             match rows.into_iter().next() {
@@ -353,22 +682,19 @@ fn execute_query(
                 None => serde_json::Value::Null,
             }
         }
-        "run" => {
+        QueryMode::Run => {
             // For run mode, execute and return meta only
             let _count = stmt
                 .raw_execute()
                 .map_err(|e| (StatusCode::BAD_REQUEST, format!("SQL error: {e}")))?;
             serde_json::json!([])
         }
-        "raw" => {
+        QueryMode::Raw => {
             let include_cols = column_names.unwrap_or(false);
             let (_cols, rows) = rows_to_arrays(&mut stmt, include_cols)
                 .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
             serde_json::json!(rows)
         }
-        _ => {
-            return Err((StatusCode::BAD_REQUEST, format!("unknown mode: {mode}")));
-        }
     };
 
     let duration = start.elapsed().as_secs_f64();
@@ -386,33 +712,30 @@ fn execute_query(
     })
 }
 
-// --- DB handlers ---
+// --- DB ---
 
-async fn db_query(
-    State(state): State<AppState>,
-    Json(req): Json<DbQueryRequest>,
-) -> Result<impl IntoResponse, AppError> {
+fn db_query(state: &AppState, req: DbQueryRequest) -> Result<D1Response, AppError> {
     let conn = state.db.lock().map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             format!("db lock error: {e}"),
         )
     })?;
+    let start = Instant::now();
     let resp = execute_query(
         &conn,
+        state.d1_compat,
         &req.sql,
         &req.bindings,
-        &req.mode,
+        req.mode,
         req.column.as_deref(),
         req.column_names,
-    )?;
-    Ok(Json(resp))
+    );
+    state.log_db(&req.sql, start, &resp);
+    resp
 }
 
-async fn db_batch(
-    State(state): State<AppState>,
-    Json(req): Json<DbBatchRequest>,
-) -> Result<impl IntoResponse, AppError> {
+fn db_batch(state: &AppState, req: DbBatchRequest) -> Result<Vec<D1Response>, AppError> {
     let conn = state.db.lock().map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -421,16 +744,27 @@ async fn db_batch(
     })?;
     let mut results = Vec::new();
     for stmt in &req.statements {
-        let resp = execute_query(&conn, &stmt.sql, &stmt.bindings, "all", None, None)?;
-        results.push(resp);
+        let start = Instant::now();
+        let resp = execute_query(
+            &conn,
+            state.d1_compat,
+            &stmt.sql,
+            &stmt.bindings,
+            QueryMode::All,
+            None,
+            None,
+        );
+        state.log_db(&stmt.sql, start, &resp);
+        results.push(resp?);
     }
-    Ok(Json(results))
+    Ok(results)
 }
 
-async fn db_exec(
-    State(state): State<AppState>,
-    Json(req): Json<DbExecRequest>,
-) -> Result<impl IntoResponse, AppError> {
+fn db_exec(state: &AppState, req: DbExecRequest) -> Result<D1Response, AppError> {
+    if state.d1_compat != CompatMode::Off {
+        check_d1_compat(state.d1_compat, d1_compat::check_script(&req.sql))?;
+    }
+
     let start = Instant::now();
     let conn = state.db.lock().map_err(|e| {
         (
@@ -438,17 +772,72 @@ async fn db_exec(
             format!("db lock error: {e}"),
         )
     })?;
-    conn.execute_batch(&req.sql)
-        .map_err(|e| (StatusCode::BAD_REQUEST, format!("SQL error: {e}")))?;
-    let duration = start.elapsed().as_secs_f64();
+    let resp = conn
+        .execute_batch(&req.sql)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("SQL error: {e}")))
+        .map(|()| D1Response {
+            results: serde_json::json!([]),
+            success: true,
+            meta: D1Meta {
+                changes: conn.changes() as i64,
+                last_row_id: conn.last_insert_rowid(),
+                duration: start.elapsed().as_secs_f64(),
+            },
+        });
+    state.log_db(&req.sql, start, &resp);
+    resp
+}
 
-    Ok(Json(D1Response {
-        results: serde_json::json!([]),
-        success: true,
-        meta: D1Meta {
-            changes: conn.changes() as i64,
-            last_row_id: conn.last_insert_rowid(),
-            duration,
-        },
+impl AppState {
+    fn log_kv(&self, summary: String, start: Instant) {
+        self.activity
+            .record(ActivityKind::Kv, summary, start.elapsed(), None, None);
+    }
+
+    fn log_db(&self, sql: &str, start: Instant, resp: &Result<D1Response, AppError>) {
+        let (error, detail) = match resp {
+            Ok(resp) => (
+                None,
+                Some(serde_json::json!({ "changes": resp.meta.changes })),
+            ),
+            Err((_, e)) => (Some(e.clone()), None),
+        };
+        self.activity
+            .record(ActivityKind::Db, sql.trim(), start.elapsed(), error, detail);
+    }
+}
+
+// --- Dashboard ---
+
+async fn ui() -> Html<String> {
+    Html(UI_HTML.replace("__NRZ_PROTOCOL__", &protocol::PROTOCOL_VERSION.to_string()))
+}
+
+async fn ui_kv_entries(
+    State(state): State<AppState>,
+    Query(query): Query<KvEntriesQuery>,
+) -> impl IntoResponse {
+    let entries = state
+        .kv
+        .entries(query.prefix.as_deref(), query.limit.unwrap_or(1000));
+    Json(entries)
+}
+
+async fn ui_activity(
+    State(state): State<AppState>,
+    Query(query): Query<ActivityQuery>,
+) -> impl IntoResponse {
+    Json(state.activity.since(query.since))
+}
+
+async fn ui_clear_activity(State(state): State<AppState>) -> impl IntoResponse {
+    state.activity.clear();
+    StatusCode::NO_CONTENT
+}
+
+async fn ui_info(State(state): State<AppState>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "d1Compat": format!("{:?}", state.d1_compat).to_lowercase(),
+        "dev": state.dev_info.get(),
     }))
 }
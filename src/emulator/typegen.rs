@@ -0,0 +1,131 @@
+//! TypeScript type generation from a database schema.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use super::db::{Column, Schema};
+
+/// Generate a `.d.ts` file with one interface per table and view.
+pub fn generate_typescript(schema: &Schema) -> String {
+    let mut out = String::from("// Auto-generated by `nrz db types` — do not edit\n");
+    let mut used = HashSet::new();
+
+    for table in &schema.tables {
+        // `INTEGER PRIMARY KEY` is an alias for rowid and can never be NULL
+        let rowid_alias = match table.primary_key.as_slice() {
+            [pk] => table
+                .columns
+                .iter()
+                .find(|c| &c.name == pk)
+                .filter(|c| c.decl_type.eq_ignore_ascii_case("INTEGER"))
+                .map(|c| c.name.as_str()),
+            _ => None,
+        };
+
+        let _ = writeln!(out, "\n/** Row of table `{}` */", table.name);
+        let _ = writeln!(
+            out,
+            "export interface {} {{",
+            unique_name(&table.name, &mut used)
+        );
+        for col in &table.columns {
+            let nullable = !col.not_null && rowid_alias != Some(col.name.as_str());
+            write_property(&mut out, col, nullable);
+        }
+        out.push_str("}\n");
+    }
+
+    for view in &schema.views {
+        // SQLite does not track nullability of view columns
+        let _ = writeln!(out, "\n/** Row of view `{}` */", view.name);
+        let _ = writeln!(
+            out,
+            "export interface {} {{",
+            unique_name(&view.name, &mut used)
+        );
+        for col in &view.columns {
+            write_property(&mut out, col, true);
+        }
+        out.push_str("}\n");
+    }
+
+    out
+}
+
+/// Map a declared column type to a TypeScript type using SQLite's
+/// type affinity rules.
+///
+/// NUMERIC-affinity date/time columns map to `string` because SQLite stores
+/// `CURRENT_TIMESTAMP`-style values as text. BLOBs come back from D1 as
+/// byte arrays.
+pub fn ts_type(decl_type: &str) -> &'static str {
+    let t = decl_type.to_ascii_uppercase();
+    if t.contains("INT") {
+        "number"
+    } else if t.contains("CHAR") || t.contains("CLOB") || t.contains("TEXT") {
+        "string"
+    } else if t.contains("BLOB") {
+        "number[]"
+    } else if t.is_empty() {
+        // No declared type: the column can hold anything
+        "unknown"
+    } else if t.contains("REAL") || t.contains("FLOA") || t.contains("DOUB") {
+        "number"
+    } else if t.contains("DATE") || t.contains("TIME") {
+        "string"
+    } else {
+        "number"
+    }
+}
+
+fn write_property(out: &mut String, col: &Column, nullable: bool) {
+    let ty = ts_type(&col.decl_type);
+    let ty = if nullable && ty != "unknown" {
+        format!("{ty} | null")
+    } else {
+        ty.to_string()
+    };
+    let _ = writeln!(out, "  {}: {ty};", property_name(&col.name));
+}
+
+fn property_name(name: &str) -> String {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if valid {
+        name.to_string()
+    } else {
+        serde_json::to_string(name).unwrap_or_else(|_| format!("\"{name}\""))
+    }
+}
+
+/// Convert a table name to a PascalCase interface name.
+pub fn interface_name(name: &str) -> String {
+    let mut result = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            result.push(first.to_ascii_uppercase());
+            result.extend(chars);
+        }
+    }
+    if result.is_empty() || result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+    result
+}
+
+fn unique_name(name: &str, used: &mut HashSet<String>) -> String {
+    let base = interface_name(name);
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{base}{n}");
+        n += 1;
+    }
+    candidate
+}
@@ -0,0 +1,87 @@
+//! Unit tests for TypeScript type generation
+
+use rusqlite::Connection;
+
+use super::db::introspect;
+use super::typegen::{generate_typescript, interface_name, ts_type};
+
+fn generate(sql: &str) -> String {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(sql).unwrap();
+    generate_typescript(&introspect(&conn).unwrap())
+}
+
+#[test]
+fn affinity_mapping() {
+    assert_eq!(ts_type("INTEGER"), "number");
+    assert_eq!(ts_type("bigint"), "number");
+    assert_eq!(ts_type("VARCHAR(255)"), "string");
+    assert_eq!(ts_type("TEXT"), "string");
+    assert_eq!(ts_type("CLOB"), "string");
+    assert_eq!(ts_type("BLOB"), "number[]");
+    assert_eq!(ts_type("REAL"), "number");
+    assert_eq!(ts_type("DOUBLE PRECISION"), "number");
+    assert_eq!(ts_type("NUMERIC"), "number");
+    assert_eq!(ts_type("BOOLEAN"), "number");
+    assert_eq!(ts_type("DATETIME"), "string");
+    assert_eq!(ts_type(""), "unknown");
+}
+
+#[test]
+fn interface_names() {
+    assert_eq!(interface_name("users"), "Users");
+    assert_eq!(interface_name("blog_posts"), "BlogPosts");
+    assert_eq!(interface_name("order-items"), "OrderItems");
+    assert_eq!(interface_name("2fa_codes"), "_2faCodes");
+}
+
+#[test]
+fn nullable_columns() {
+    let ts = generate(
+        "CREATE TABLE users (
+            id INTEGER PRIMARY KEY,
+            email TEXT NOT NULL,
+            bio TEXT,
+            data
+        )",
+    );
+    assert!(ts.contains("export interface Users {"));
+    assert!(ts.contains("  id: number;\n"));
+    assert!(ts.contains("  email: string;\n"));
+    assert!(ts.contains("  bio: string | null;\n"));
+    assert!(ts.contains("  data: unknown;\n"));
+}
+
+#[test]
+fn non_rowid_primary_key_is_nullable() {
+    // SQLite allows NULL in non-INTEGER primary keys unless NOT NULL is declared
+    let ts = generate("CREATE TABLE t (code TEXT PRIMARY KEY)");
+    assert!(ts.contains("  code: string | null;\n"));
+}
+
+#[test]
+fn views_are_generated() {
+    let ts = generate(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+         CREATE VIEW user_names AS SELECT name FROM users;",
+    );
+    assert!(ts.contains("/** Row of view `user_names` */"));
+    assert!(ts.contains("export interface UserNames {"));
+    assert!(ts.contains("  name: string | null;\n"));
+}
+
+#[test]
+fn quotes_invalid_property_names() {
+    let ts = generate("CREATE TABLE t (\"first name\" TEXT NOT NULL)");
+    assert!(ts.contains("  \"first name\": string;\n"));
+}
+
+#[test]
+fn deduplicates_interface_names() {
+    let ts = generate(
+        "CREATE TABLE user_s (id INTEGER PRIMARY KEY);
+         CREATE TABLE \"user s\" (id INTEGER PRIMARY KEY);",
+    );
+    assert!(ts.contains("export interface UserS {"));
+    assert!(ts.contains("export interface UserS2 {"));
+}
@@ -3,5 +3,6 @@
 pub mod emulator;
 
 // Re-export commonly used types
+pub use emulator::client::EmulatorClient;
 pub use emulator::kv::KvStore;
-pub use emulator::server::EmulatorServer;
+pub use emulator::server::{EmulatorServer, RunningEmulator};
@@ -1,8 +1,11 @@
+mod api;
 mod auth;
 mod build;
 mod cli;
+mod config;
 mod deploy;
 mod dev;
+mod types;
 mod upgrade;
 
 use clap::Parser;
@@ -11,8 +14,12 @@ use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Logs go to stderr; stdout is for machine-readable output. Warnings,
+    // such as the emulator's D1 compatibility warnings, show by default
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
     tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
         .init();
 
     let cli = Cli::parse();
@@ -23,6 +30,9 @@ async fn main() -> anyhow::Result<()> {
         Command::Deploy(args) => deploy::run(args).await,
         Command::Db(args) => cli::db_handler::run(args).await,
         Command::Kv(args) => cli::kv_handler::run(args).await,
+        Command::Emulator(args) => cli::emulator_handler::run(args).await,
+        Command::Trigger(args) => cli::trigger_handler::run(args).await,
+        Command::Types(args) => types::run(args).await,
         Command::Login => auth::login().await,
         Command::Whoami => auth::whoami().await,
         Command::Upgrade(args) => upgrade::run(args).await,
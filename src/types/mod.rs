@@ -0,0 +1,221 @@
+#[cfg(test)]
+mod types_tests;
+
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::cli::TypesArgs;
+use crate::config::Config;
+use crate::dev::env::load_env;
+use crate::dev::workspace::resolve_project_dir;
+
+/// Default declaration file, relative to the project directory.
+pub const TYPES_FILE: &str = "onreza.d.ts";
+
+/// Write TypeScript declarations for `globalThis.ONREZA`.
+pub async fn run(args: TypesArgs) -> anyhow::Result<()> {
+    let dir = Path::new(&args.dir)
+        .canonicalize()
+        .with_context(|| format!("project directory not found: {}", args.dir))?;
+    let project_dir = resolve_project_dir(&dir, args.app.as_deref())?;
+    let config = Config::load(&project_dir)?;
+
+    let out = args.out.unwrap_or_else(|| TYPES_FILE.into());
+    let content = generate_for_project(&project_dir, &config, &args.mode)?;
+    let out_path = project_dir.join(&out);
+    if let Some(parent) = out_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&out_path, content)
+        .with_context(|| format!("failed to write {}", out_path.display()))?;
+    eprintln!(
+        "  {} wrote {}",
+        console::style("✓").green().bold(),
+        out.display()
+    );
+    Ok(())
+}
+
+/// Declarations for a project: its bindings and the variable names from
+/// the config and env files for `mode`. Values are never included.
+pub fn generate_for_project(
+    project_dir: &Path,
+    config: &Config,
+    mode: &str,
+) -> anyhow::Result<String> {
+    let vars = load_env(project_dir, mode, &config.env(mode))?;
+    let names: Vec<&str> = vars.keys().map(String::as_str).collect();
+    Ok(generate(config, &names))
+}
+
+/// Regenerate the declarations if they changed. Returns whether the file
+/// was written.
+pub fn update(project_dir: &Path, config: &Config, mode: &str) -> anyhow::Result<bool> {
+    let content = generate_for_project(project_dir, config, mode)?;
+    let path = project_dir.join(TYPES_FILE);
+    if std::fs::read_to_string(&path).is_ok_and(|existing| existing == content) {
+        return Ok(false);
+    }
+    std::fs::write(&path, content)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// Generate `onreza.d.ts`.
+///
+/// Disabled bindings are left out of the `ONREZA` type, so using them is a
+/// type error. Known variable names make `ONREZA.env.get()` return `string`
+/// instead of `string | undefined`.
+pub fn generate(config: &Config, env_names: &[&str]) -> String {
+    let mut out = String::from(
+        "// Auto-generated by `nrz types` — do not edit\n\
+         // Regenerated by `nrz dev` when the config or env files change.\n\
+         \n\
+         export {};\n\
+         \n\
+         declare global {\n",
+    );
+    out.push_str(CONTEXT_TYPES);
+    out.push_str(SCHEDULED_TYPES);
+
+    let env_key = if env_names.is_empty() {
+        "never".to_string()
+    } else {
+        env_names
+            .iter()
+            .map(|n| serde_json::to_string(n).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(" | ")
+    };
+    let _ = write!(
+        out,
+        r#"
+  /** Variables from onreza.jsonc, .env files and .dev.vars */
+  type OnrezaEnvKey = {env_key};
+
+  interface OnrezaEnv extends ReadonlyMap<string, string> {{
+    get(key: OnrezaEnvKey): string;
+    get(key: string): string | undefined;
+  }}
+"#
+    );
+
+    if config.bindings.kv.enabled {
+        out.push_str(KV_TYPES);
+    }
+    if config.bindings.db.enabled {
+        out.push_str(DB_TYPES);
+    }
+
+    out.push_str("\n  interface OnrezaRuntime {\n");
+    out.push_str("    readonly env: OnrezaEnv;\n");
+    out.push_str("    /** Context of the request being handled */\n");
+    out.push_str("    readonly context: OnrezaContext;\n");
+    out.push_str("    /** Cron handler for apps that export no `scheduled`; `nrz dev` only */\n");
+    out.push_str("    onScheduled?(handler: OnrezaScheduledHandler): void;\n");
+    if config.bindings.kv.enabled {
+        out.push_str("    readonly kv: OnrezaKv;\n");
+    }
+    if config.bindings.db.enabled {
+        out.push_str("    readonly db: OnrezaDatabase;\n");
+    }
+    out.push_str("  }\n\n  var ONREZA: OnrezaRuntime;\n}\n");
+    out
+}
+
+const CONTEXT_TYPES: &str = r#"
+  interface OnrezaGeo {
+    country: string;
+    city: string;
+    continent: string;
+    region: string;
+  }
+
+  interface OnrezaContext {
+    clientIp: string;
+    geo: OnrezaGeo;
+    deploymentId: string;
+    projectId: string;
+    commitSha: string;
+    /** `null` outside of a request */
+    requestId: string | null;
+  }
+"#;
+
+const SCHEDULED_TYPES: &str = r#"
+  interface OnrezaScheduledEvent {
+    readonly type: "scheduled";
+    /** Expression of the trigger that fired */
+    readonly cron: string;
+    /** Minute the trigger was due, in milliseconds since the epoch */
+    readonly scheduledTime: number;
+  }
+
+  interface OnrezaExecutionContext {
+    /** Keep the invocation open until `promise` settles */
+    waitUntil(promise: Promise<unknown>): void;
+  }
+
+  type OnrezaScheduledHandler = (
+    event: OnrezaScheduledEvent,
+    env: OnrezaEnv,
+    ctx: OnrezaExecutionContext,
+  ) => void | Promise<void>;
+"#;
+
+const KV_TYPES: &str = r#"
+  interface OnrezaKvSetOptions {
+    /** Seconds until the key expires */
+    ttl?: number;
+    expirationTtl?: number;
+    metadata?: unknown;
+  }
+
+  interface OnrezaKv {
+    get(key: string): Promise<string | null>;
+    getWithMetadata<M = unknown>(
+      key: string,
+    ): Promise<{ value: string | null; metadata: M | null }>;
+    /** `options` is a TTL in seconds or an options object */
+    set(key: string, value: string, options?: number | OnrezaKvSetOptions): Promise<"OK">;
+    delete(key: string): Promise<boolean>;
+    has(key: string): Promise<boolean>;
+    list(prefix?: string, limit?: number): Promise<string[]>;
+  }
+"#;
+
+const DB_TYPES: &str = r#"
+  interface OnrezaD1Meta {
+    changes: number;
+    last_row_id: number;
+    /** Seconds */
+    duration: number;
+  }
+
+  interface OnrezaD1Result<T = Record<string, unknown>> {
+    results: T[];
+    success: boolean;
+    meta: OnrezaD1Meta;
+  }
+
+  interface OnrezaPreparedStatement {
+    bind(...values: unknown[]): OnrezaPreparedStatement;
+    all<T = Record<string, unknown>>(): Promise<OnrezaD1Result<T>>;
+    first<T = Record<string, unknown>>(): Promise<T | null>;
+    first<T = unknown>(column: string): Promise<T | null>;
+    run(): Promise<OnrezaD1Result<never>>;
+    raw<T extends unknown[] = unknown[]>(options?: { columnNames?: false }): Promise<T[]>;
+    raw<T extends unknown[] = unknown[]>(options: { columnNames: true }): Promise<[string[], ...T[]]>;
+  }
+
+  /** D1-compatible database */
+  interface OnrezaDatabase {
+    prepare(sql: string): OnrezaPreparedStatement;
+    batch<T = Record<string, unknown>>(
+      statements: OnrezaPreparedStatement[],
+    ): Promise<OnrezaD1Result<T>[]>;
+    exec(sql: string): Promise<OnrezaD1Result<never>>;
+  }
+"#;
@@ -0,0 +1,61 @@
+//! Unit tests for ONREZA type declarations
+
+use super::{TYPES_FILE, generate, generate_for_project, update};
+use crate::config::Config;
+
+#[test]
+fn declares_global_runtime() {
+    let ts = generate(&Config::default(), &[]);
+    assert!(ts.contains("declare global {"));
+    assert!(ts.contains("var ONREZA: OnrezaRuntime;"));
+    assert!(ts.contains("readonly context: OnrezaContext;"));
+    assert!(ts.contains("onScheduled?(handler: OnrezaScheduledHandler): void;"));
+    assert!(ts.contains("readonly scheduledTime: number;"));
+    assert!(ts.contains("readonly kv: OnrezaKv;"));
+    assert!(ts.contains("readonly db: OnrezaDatabase;"));
+    assert!(ts.contains("type OnrezaEnvKey = never;"));
+}
+
+#[test]
+fn disabled_bindings_are_left_out() {
+    let config = Config::parse(r#"{ "bindings": { "kv": { "enabled": false } } }"#).unwrap();
+    let ts = generate(&config, &[]);
+    assert!(!ts.contains("readonly kv"));
+    assert!(!ts.contains("interface OnrezaKv "));
+    assert!(ts.contains("readonly db: OnrezaDatabase;"));
+}
+
+#[test]
+fn env_names_are_typed() {
+    let ts = generate(&Config::default(), &["API_URL", "SECRET"]);
+    assert!(ts.contains(r#"type OnrezaEnvKey = "API_URL" | "SECRET";"#));
+    assert!(ts.contains("get(key: OnrezaEnvKey): string;"));
+}
+
+#[test]
+fn project_env_names_without_values() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join(".env"), "PUBLIC_URL=https://example.com\n").unwrap();
+    std::fs::write(dir.path().join(".dev.vars"), "TOKEN=hunter2\n").unwrap();
+    let config = Config::parse(r#"{ "env": { "FROM_CONFIG": "1" } }"#).unwrap();
+
+    let ts = generate_for_project(dir.path(), &config, "development").unwrap();
+    assert!(
+        ts.contains(r#""FROM_CONFIG" | "PUBLIC_URL" | "TOKEN""#),
+        "{ts}"
+    );
+    assert!(!ts.contains("hunter2"));
+    assert!(!ts.contains("example.com"));
+}
+
+#[test]
+fn update_writes_only_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config::default();
+    assert!(update(dir.path(), &config, "development").unwrap());
+    assert!(dir.path().join(TYPES_FILE).is_file());
+    assert!(!update(dir.path(), &config, "development").unwrap());
+
+    std::fs::write(dir.path().join(".env"), "NEW_VAR=1\n").unwrap();
+    assert!(update(dir.path(), &config, "development").unwrap());
+}
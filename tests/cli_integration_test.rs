@@ -12,7 +12,7 @@ use std::fs;
 
 /// Get the binary command
 fn nrz() -> Command {
-    Command::cargo_bin("nrz").unwrap()
+    assert_cmd::cargo::cargo_bin_cmd!("nrz")
 }
 
 #[test]
@@ -251,3 +251,555 @@ fn dev_with_custom_command_works_without_detection() {
         "Should not fail on framework detection when --command is provided"
     );
 }
+
+#[test]
+fn db_schema_shows_columns_and_indexes() {
+    let temp = tempfile::tempdir().unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args([
+        "db",
+        "execute",
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL UNIQUE)",
+    ]);
+    cmd.assert().success();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["db", "schema"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("table users"))
+        .stderr(contains("email"))
+        .stderr(contains("NOT NULL"))
+        .stderr(contains("UNIQUE"));
+}
+
+#[test]
+fn db_schema_json_output() {
+    let temp = tempfile::tempdir().unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args([
+        "db",
+        "execute",
+        "CREATE TABLE \"odd name\" (id INTEGER PRIMARY KEY)",
+    ]);
+    cmd.assert().success();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["db", "schema", "--json"]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["tables"][0]["name"], "odd name");
+    assert_eq!(schema["tables"][0]["columns"][0]["type"], "INTEGER");
+    assert_eq!(schema["tables"][0]["primary_key"][0], "id");
+
+    // Table names that need quoting work in `db info` too
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["db", "info"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("odd name: 0 row(s)"));
+}
+
+#[test]
+fn db_schema_unknown_table_fails() {
+    let temp = tempfile::tempdir().unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp)
+        .args(["db", "execute", "CREATE TABLE t (id INTEGER)"]);
+    cmd.assert().success();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["db", "schema", "missing"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("no table or view named 'missing'"));
+}
+
+#[test]
+fn db_types_from_migrations() {
+    let temp = tempfile::tempdir().unwrap();
+    let migrations = temp.path().join("migrations");
+    fs::create_dir(&migrations).unwrap();
+    fs::write(
+        migrations.join("0001_init.sql"),
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL);",
+    )
+    .unwrap();
+    fs::write(
+        migrations.join("0002_bio.sql"),
+        "ALTER TABLE users ADD COLUMN bio TEXT;",
+    )
+    .unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp)
+        .args(["db", "types", "--migrations", "--out", "types/db.d.ts"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("applied 2 migration(s)"));
+
+    let ts = fs::read_to_string(temp.path().join("types/db.d.ts")).unwrap();
+    assert!(ts.contains("export interface Users {"));
+    assert!(ts.contains("email: string;"));
+    assert!(ts.contains("bio: string | null;"));
+
+    // Migrations never touch the local dev database
+    assert!(!temp.path().join(".onreza/data/dev.db").exists());
+}
+
+#[test]
+fn db_types_without_database_fails() {
+    let temp = tempfile::tempdir().unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["db", "types"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("database not found"));
+}
+
+#[test]
+fn db_diff_against_migrations_writes_migration() {
+    let temp = tempfile::tempdir().unwrap();
+    let migrations = temp.path().join("migrations");
+    fs::create_dir(&migrations).unwrap();
+    fs::write(
+        migrations.join("0001_init.sql"),
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL);",
+    )
+    .unwrap();
+
+    // Hand-edit the local database so it drifts from the migrations
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args([
+        "db",
+        "execute",
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL, bio TEXT)",
+    ]);
+    cmd.assert().success();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp)
+        .args(["db", "diff", "--out", "migrations/0002_bio.sql"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("+ column users.bio TEXT"))
+        .stderr(contains("1 change(s)"));
+
+    let sql = fs::read_to_string(migrations.join("0002_bio.sql")).unwrap();
+    assert!(sql.contains("ALTER TABLE \"users\" ADD COLUMN \"bio\" TEXT;"));
+
+    // With the new migration in place, the schemas match
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["db", "diff"]);
+    cmd.assert()
+        .success()
+        .stderr(contains("schemas are identical"));
+}
+
+#[test]
+fn build_uses_framework_output_dir() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(
+        temp.path().join("package.json"),
+        r#"{"devDependencies":{"@sveltejs/kit":"^2.0"}}"#,
+    )
+    .unwrap();
+    // SvelteKit builds to build/, so a stray dist/ is not the output
+    fs::create_dir_all(temp.path().join("dist/.onreza")).unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["build"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("SvelteKit builds to build/"));
+
+    fs::create_dir_all(temp.path().join("build/.onreza")).unwrap();
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["build"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("build/.onreza/manifest.json"));
+}
+
+#[test]
+fn dev_runs_command_through_detected_package_manager() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("package.json"), r#"{"name":"test"}"#).unwrap();
+    fs::write(temp.path().join("pnpm-lock.yaml"), "").unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp)
+        .args(["dev", "--command", "true", "--port", "43210"]);
+    let output = cmd.output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("starting: pnpm exec true"), "{stderr}");
+
+    // The flag overrides detection
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args([
+        "dev",
+        "--command",
+        "true",
+        "--port",
+        "43212",
+        "--package-manager",
+        "npm",
+    ]);
+    let output = cmd.output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("starting: npx --no true"), "{stderr}");
+}
+
+/// pnpm workspace with two apps: apps/web (astro) and apps/admin (nuxt)
+fn workspace_with_two_apps() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(
+        temp.path().join("pnpm-workspace.yaml"),
+        "packages:\n  - apps/*\n",
+    )
+    .unwrap();
+    fs::write(temp.path().join("package.json"), r#"{"private":true}"#).unwrap();
+    for (dir, dep) in [("web", "astro"), ("admin", "nuxt")] {
+        let app = temp.path().join("apps").join(dir);
+        fs::create_dir_all(&app).unwrap();
+        fs::write(
+            app.join("package.json"),
+            format!(r#"{{"name":"{dir}","dependencies":{{"{dep}":"*"}}}}"#),
+        )
+        .unwrap();
+    }
+    temp
+}
+
+#[test]
+fn workspace_root_with_several_apps_needs_app() {
+    let temp = workspace_with_two_apps();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["dev"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("several apps: admin, web"))
+        .stderr(contains("--app"));
+}
+
+#[test]
+fn kv_uses_per_app_data_dir() {
+    let temp = workspace_with_two_apps();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp)
+        .args(["kv", "--app", "web", "set", "greeting", "hi"]);
+    cmd.assert().success();
+    assert!(temp.path().join("apps/web/.onreza/data").is_dir());
+    assert!(!temp.path().join(".onreza").exists());
+
+    // --app is global, so it also works after the subcommand
+    let mut cmd = nrz();
+    cmd.current_dir(&temp)
+        .args(["kv", "get", "greeting", "--app", "apps/web"]);
+    cmd.assert().success().stdout(contains("hi"));
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp)
+        .args(["kv", "get", "greeting", "--app", "admin"]);
+    cmd.assert().success().stderr(contains("(not found)"));
+}
+
+#[test]
+fn dev_reads_command_and_port_from_config() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("package.json"), r#"{"name":"test"}"#).unwrap();
+    fs::write(
+        temp.path().join("onreza.jsonc"),
+        r#"{
+          // no framework dependency, so the command must come from here
+          "dev": { "command": "true", "port": 43220, "packageManager": "npm" }
+        }"#,
+    )
+    .unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["dev"]);
+    let output = cmd.output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("emulator ready on port 43221"), "{stderr}");
+    assert!(stderr.contains("starting: npx --no true"), "{stderr}");
+}
+
+#[test]
+fn invalid_config_is_reported() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(
+        temp.path().join("onreza.jsonc"),
+        r#"{ "bindings": { "kv": { "enabled": "yes" } } }"#,
+    )
+    .unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["kv", "list"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("invalid config"))
+        .stderr(contains("onreza.jsonc"));
+}
+
+#[test]
+fn build_reads_output_dir_from_config() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(
+        temp.path().join("onreza.jsonc"),
+        r#"{ "build": { "outputDir": "out" } }"#,
+    )
+    .unwrap();
+    fs::create_dir_all(temp.path().join("out/.onreza")).unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["build"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("out/.onreza/manifest.json"));
+}
+
+#[test]
+fn dev_prints_masked_env() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("package.json"), r#"{"name":"test"}"#).unwrap();
+    fs::write(
+        temp.path().join(".env"),
+        "PUBLIC_URL=https://example.com\nMODE_VAR=base\n",
+    )
+    .unwrap();
+    fs::write(temp.path().join(".env.staging"), "MODE_VAR=staging\n").unwrap();
+    fs::write(temp.path().join(".dev.vars"), "API_SECRET=sk_live_123456\n").unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args([
+        "dev",
+        "--command",
+        "true",
+        "--port",
+        "43230",
+        "--mode",
+        "staging",
+    ]);
+    let output = cmd.output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("env: 3 variable(s)"), "{stderr}");
+    assert!(stderr.contains("PUBLIC_URL=htt********"), "{stderr}");
+    assert!(
+        stderr.contains("MODE_VAR=******** (.env.staging)"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("API_SECRET=******** (.dev.vars)"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("sk_live"), "{stderr}");
+}
+
+#[test]
+fn dev_rejects_invalid_geo() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("package.json"), r#"{"name":"test"}"#).unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args([
+        "dev",
+        "--command",
+        "true",
+        "--port",
+        "43230",
+        "--geo",
+        "planet=Mars",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("invalid --geo key 'planet'"));
+}
+
+#[test]
+fn dev_serves_through_context_proxy() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("package.json"), r#"{"name":"test"}"#).unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args([
+        "dev",
+        "--command",
+        "true",
+        "--port",
+        "43240",
+        "--package-manager",
+        "npm",
+    ]);
+    let output = cmd.output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("dev server: http://localhost:43240"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn dev_explicit_emulator_port_conflict_fails() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("package.json"), r#"{"name":"test"}"#).unwrap();
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = taken.local_addr().unwrap().port();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args([
+        "dev",
+        "--command",
+        "true",
+        "--port",
+        "43250",
+        "--emulator-port",
+        &port.to_string(),
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(contains(format!("port {port} is")))
+        .stderr(contains("--emulator-port"));
+}
+
+#[test]
+fn types_writes_declarations_for_bindings() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(
+        temp.path().join("onreza.jsonc"),
+        r#"{ "bindings": { "db": { "enabled": false } }, "env": { "API_URL": "x" } }"#,
+    )
+    .unwrap();
+
+    let mut cmd = nrz();
+    cmd.current_dir(&temp).args(["types"]);
+    cmd.assert().success().stderr(contains("wrote onreza.d.ts"));
+
+    let ts = fs::read_to_string(temp.path().join("onreza.d.ts")).unwrap();
+    assert!(ts.contains("var ONREZA: OnrezaRuntime;"));
+    assert!(ts.contains("readonly kv: OnrezaKv;"));
+    assert!(!ts.contains("readonly db"));
+    assert!(ts.contains(r#""API_URL""#));
+}
+
+#[cfg(unix)]
+#[test]
+fn emulator_start_prints_address_and_stops_on_signal() {
+    use std::io::{BufRead, Read, Write};
+
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("package.json"), r#"{"name":"test"}"#).unwrap();
+
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_nrz"))
+        .current_dir(&temp)
+        .args(["emulator", "start", "--ephemeral"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    std::io::BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    let info: serde_json::Value = serde_json::from_str(&line).unwrap();
+    let port = info["port"].as_u64().unwrap();
+    assert_ne!(port, 0);
+    assert_eq!(info["url"], format!("http://127.0.0.1:{port}"));
+    assert_eq!(info["protocol"], nrz::emulator::protocol::PROTOCOL_VERSION);
+    let bootstrap = std::path::PathBuf::from(info["bootstrap"].as_str().unwrap());
+    let script = fs::read_to_string(&bootstrap).unwrap();
+    assert!(script.contains(info["token"].as_str().unwrap()));
+
+    let mut stream = std::net::TcpStream::connect(("127.0.0.1", port as u16)).unwrap();
+    write!(
+        stream,
+        "GET /__nrz/health HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\n\
+         Authorization: Bearer {}\r\nConnection: close\r\n\r\n",
+        info["token"].as_str().unwrap()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+    }
+    assert!(child.wait().unwrap().success());
+    // Ephemeral data is gone
+    assert!(!bootstrap.parent().unwrap().exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn emulator_start_leaves_the_dev_bootstrap_alone() {
+    use std::io::BufRead;
+
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("package.json"), r#"{"name":"test"}"#).unwrap();
+    let data = nrz::emulator::data_dir(temp.path());
+    fs::create_dir_all(&data).unwrap();
+    let dev_bootstrap = data.join(nrz::emulator::BOOTSTRAP_FILE);
+    fs::write(&dev_bootstrap, "// nrz dev session").unwrap();
+
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_nrz"))
+        .current_dir(&temp)
+        .args(["emulator", "start", "--port", "0"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    std::io::BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    let info: serde_json::Value = serde_json::from_str(&line).unwrap();
+    let bootstrap = std::path::PathBuf::from(info["bootstrap"].as_str().unwrap());
+    assert_ne!(bootstrap, dev_bootstrap);
+    assert!(bootstrap.exists());
+
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+    }
+    assert!(child.wait().unwrap().success());
+    assert!(!bootstrap.exists());
+    assert_eq!(
+        fs::read_to_string(&dev_bootstrap).unwrap(),
+        "// nrz dev session"
+    );
+}
+
+#[test]
+fn trigger_cron_needs_a_running_dev_session() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::write(temp.path().join("package.json"), r#"{"name":"app"}"#).unwrap();
+
+    nrz()
+        .current_dir(&temp)
+        .args(["trigger", "cron", "*/5 * * * *"])
+        .assert()
+        .failure()
+        .stderr(contains("no running `nrz dev` found"));
+
+    nrz()
+        .current_dir(&temp)
+        .args(["trigger", "cron", "*/5 * * *"])
+        .assert()
+        .failure()
+        .stderr(contains("expected 5 fields"));
+
+    nrz()
+        .current_dir(&temp)
+        .args(["trigger", "cron", "--at", "noon", "0 12 * * *"])
+        .assert()
+        .failure()
+        .stderr(contains("invalid time 'noon'"));
+}
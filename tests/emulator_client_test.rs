@@ -0,0 +1,235 @@
+//! Integration tests for the typed emulator client against the real server
+
+use nrz::emulator::client::EmulatorClient;
+use nrz::emulator::d1_compat::CompatMode;
+use nrz::emulator::protocol::{
+    DbBatchStatement, DbQueryRequest, KvRequest, KvSetOptions, PROTOCOL_VERSION, QueryMode, RpcCall,
+};
+use nrz::emulator::server::{EmulatorServer, RunningEmulator};
+use serde_json::json;
+
+async fn start() -> (EmulatorClient, RunningEmulator) {
+    let emulator = EmulatorServer::ephemeral().bind().await.unwrap();
+    (EmulatorClient::connect(&emulator).unwrap(), emulator)
+}
+
+#[tokio::test]
+async fn health_reports_ok() {
+    let (client, _emulator) = start().await;
+    assert_eq!(client.health().await.unwrap().status, "ok");
+}
+
+#[tokio::test]
+async fn handshake_checks_the_protocol_version() {
+    let (client, _emulator) = start().await;
+    let health = client.handshake().await.unwrap();
+    assert_eq!(health.protocol, PROTOCOL_VERSION);
+    assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[tokio::test]
+async fn kv_round_trip() {
+    let (client, _emulator) = start().await;
+
+    assert_eq!(client.kv_get("user:1").await.unwrap(), None);
+    assert!(!client.kv_has("user:1").await.unwrap());
+
+    let options = KvSetOptions {
+        expiration_ttl: Some(60),
+        metadata: Some(json!({ "role": "admin" })),
+    };
+    client.kv_set("user:1", "alice", &options).await.unwrap();
+    client
+        .kv_set("user:2", "bob", &KvSetOptions::default())
+        .await
+        .unwrap();
+    client
+        .kv_set("session:1", "x", &KvSetOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        client.kv_get("user:1").await.unwrap().as_deref(),
+        Some("alice")
+    );
+    let entry = client.kv_get_with_metadata("user:1").await.unwrap();
+    assert_eq!(entry.value.as_deref(), Some("alice"));
+    assert_eq!(entry.metadata, Some(json!({ "role": "admin" })));
+    assert!(client.kv_has("user:2").await.unwrap());
+
+    assert_eq!(
+        client.kv_list(Some("user:"), None).await.unwrap(),
+        ["user:1", "user:2"]
+    );
+    assert_eq!(client.kv_list(None, Some(1)).await.unwrap().len(), 1);
+
+    assert!(client.kv_delete("user:1").await.unwrap());
+    assert!(!client.kv_delete("user:1").await.unwrap());
+    assert_eq!(client.kv_get("user:1").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn db_query_modes() {
+    let (client, _emulator) = start().await;
+
+    client
+        .db_exec("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)")
+        .await
+        .unwrap();
+    let insert = client
+        .db_query(&DbQueryRequest::new(
+            "INSERT INTO users (name) VALUES (?), (?)",
+            vec![json!("alice"), json!("bob")],
+            QueryMode::Run,
+        ))
+        .await
+        .unwrap();
+    assert!(insert.success);
+    assert_eq!(insert.meta.changes, 2);
+    assert_eq!(insert.meta.last_row_id, 2);
+
+    let all = client
+        .db_query(&DbQueryRequest::new(
+            "SELECT id, name FROM users ORDER BY id",
+            vec![],
+            QueryMode::All,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(
+        all.results,
+        json!([{ "id": 1, "name": "alice" }, { "id": 2, "name": "bob" }])
+    );
+
+    let mut first = DbQueryRequest::new(
+        "SELECT name FROM users WHERE id = ?",
+        vec![json!(2)],
+        QueryMode::First,
+    );
+    first.column = Some("name".into());
+    assert_eq!(client.db_query(&first).await.unwrap().results, json!("bob"));
+
+    let mut raw = DbQueryRequest::new("SELECT id FROM users", vec![], QueryMode::Raw);
+    raw.column_names = Some(true);
+    assert_eq!(
+        client.db_query(&raw).await.unwrap().results,
+        json!([["id"], [1], [2]])
+    );
+}
+
+#[tokio::test]
+async fn db_batch_returns_a_result_per_statement() {
+    let (client, _emulator) = start().await;
+
+    client.db_exec("CREATE TABLE t (v INTEGER)").await.unwrap();
+    let results = client
+        .db_batch(vec![
+            DbBatchStatement {
+                sql: "INSERT INTO t VALUES (?)".into(),
+                bindings: vec![json!(7)],
+            },
+            DbBatchStatement {
+                sql: "SELECT v FROM t".into(),
+                bindings: vec![],
+            },
+        ])
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].meta.changes, 1);
+    assert_eq!(results[1].results, json!([{ "v": 7 }]));
+}
+
+#[tokio::test]
+async fn errors_carry_the_server_message() {
+    let mut server = EmulatorServer::ephemeral();
+    server.d1_compat = CompatMode::Strict;
+    let emulator = server.bind().await.unwrap();
+    let client = EmulatorClient::connect(&emulator).unwrap();
+
+    let err = client
+        .db_query(&DbQueryRequest::new(
+            "SELECT * FROM missing",
+            vec![],
+            QueryMode::All,
+        ))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no such table"), "{err}");
+
+    let err = client
+        .db_exec("PRAGMA journal_mode = DELETE")
+        .await
+        .unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("400"), "{message}");
+    assert!(message.contains("PRAGMA journal_mode"), "{message}");
+}
+
+#[tokio::test]
+async fn wrong_token_is_rejected() {
+    let (_, emulator) = start().await;
+    let client = EmulatorClient::new(emulator.url(), "not-the-token").unwrap();
+    let err = client.kv_get("k").await.unwrap_err();
+    assert!(err.to_string().contains("401"), "{err}");
+}
+
+#[tokio::test]
+async fn rpc_runs_calls_in_order_with_their_own_errors() {
+    let (client, _emulator) = start().await;
+
+    let calls = [
+        RpcCall::new(
+            1,
+            "kv.set",
+            KvRequest {
+                args: vec![json!("k"), json!("v")],
+            },
+        )
+        .unwrap(),
+        RpcCall::new(
+            2,
+            "kv.get",
+            KvRequest {
+                args: vec![json!("k")],
+            },
+        )
+        .unwrap(),
+        RpcCall::new(
+            3,
+            "db.query",
+            DbQueryRequest::new("SELECT * FROM missing", vec![], QueryMode::All),
+        )
+        .unwrap(),
+        RpcCall::new(
+            4,
+            "kv.get",
+            KvRequest {
+                args: vec![json!("other")],
+            },
+        )
+        .unwrap(),
+        RpcCall::new(5, "kv.rename", KvRequest { args: vec![] }).unwrap(),
+        RpcCall::new(6, "db.exec", json!({ "statement": "oops" })).unwrap(),
+    ];
+    let responses = client.rpc(&calls).await.unwrap();
+    let ids: Vec<u64> = responses.iter().map(|r| r.id).collect();
+    assert_eq!(ids, [1, 2, 3, 4, 5, 6]);
+
+    assert_eq!(responses[0].result, json!("OK"));
+    assert_eq!(responses[1].result, json!("v"));
+    let error = responses[2].error.as_ref().unwrap();
+    assert_eq!(error.code, 400);
+    assert!(error.message.contains("no such table"), "{}", error.message);
+    // A missing key is a null result, not an error
+    assert_eq!(responses[3].result, serde_json::Value::Null);
+    assert_eq!(responses[3].error, None);
+    assert_eq!(responses[4].error.as_ref().unwrap().code, 404);
+    let error = responses[5].error.as_ref().unwrap();
+    assert_eq!(error.code, 400);
+    assert!(
+        error.message.starts_with("db.exec: invalid params"),
+        "{}",
+        error.message
+    );
+}
@@ -4,232 +4,41 @@
 //! - KV: POST set → POST get → проверка значения
 //! - DB: POST exec (CREATE TABLE) → POST query (SELECT) → проверка results
 //! - Health endpoint → {"status":"ok"}
+//! - Shutdown handle, in-memory databases
 
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use nrz::emulator::d1_compat::CompatMode;
+use nrz::emulator::protocol::{PROTOCOL_HEADER, PROTOCOL_VERSION};
+use nrz::emulator::server::{EmulatorServer, RunningEmulator};
 
-use axum::routing::{get, post};
-use axum::{Json, Router};
-use rusqlite::Connection;
-use serde::{Deserialize, Serialize};
-
-use nrz::emulator::kv::KvStore;
-
-// --- Types copied from server.rs for testing ---
-
-#[derive(Deserialize)]
-struct KvRequest {
-    args: Vec<serde_json::Value>,
-}
-
-#[derive(Deserialize)]
-struct DbQueryRequest {
-    sql: String,
-    #[serde(default)]
-    bindings: Vec<serde_json::Value>,
-    mode: String,
-}
-
-#[derive(Deserialize)]
-struct DbExecRequest {
-    sql: String,
-}
-
-#[derive(Serialize)]
-struct HealthResponse {
-    status: &'static str,
-}
-
-#[derive(Clone)]
-struct AppState {
-    kv: KvStore,
-    db: Arc<Mutex<Connection>>,
-}
-
-async fn health() -> Json<HealthResponse> {
-    Json(HealthResponse { status: "ok" })
-}
-
-async fn kv_get(
-    axum::extract::State(state): axum::extract::State<AppState>,
-    Json(req): Json<KvRequest>,
-) -> Json<serde_json::Value> {
-    let key = req.args.first().and_then(|v| v.as_str()).unwrap_or("");
-    Json(serde_json::to_value(state.kv.get(key)).unwrap())
-}
-
-async fn kv_set(
-    axum::extract::State(state): axum::extract::State<AppState>,
-    Json(req): Json<KvRequest>,
-) -> Json<serde_json::Value> {
-    let key = req
-        .args
-        .first()
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    let value = req
-        .args
-        .get(1)
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-    state.kv.set(key, value, 0);
-    Json(serde_json::json!("OK"))
-}
-
-async fn db_exec(
-    axum::extract::State(state): axum::extract::State<AppState>,
-    Json(req): Json<DbExecRequest>,
-) -> Json<serde_json::Value> {
-    let conn = state.db.lock().unwrap();
-    match conn.execute_batch(&req.sql) {
-        Ok(_) => Json(serde_json::json!({
-            "success": true,
-            "results": [],
-            "meta": {
-                "changes": conn.changes() as i64,
-                "last_row_id": conn.last_insert_rowid(),
-                "duration": 0.0
-            }
-        })),
-        Err(e) => Json(serde_json::json!({
-            "success": false,
-            "error": e.to_string()
-        })),
-    }
-}
-
-async fn db_query(
-    axum::extract::State(state): axum::extract::State<AppState>,
-    Json(req): Json<DbQueryRequest>,
-) -> Json<serde_json::Value> {
-    use std::time::Instant;
-
-    let start = Instant::now();
-    let conn = state.db.lock().unwrap();
-
-    let mut stmt = match conn.prepare(&req.sql) {
-        Ok(s) => s,
-        Err(e) => {
-            return Json(serde_json::json!({
-                "success": false,
-                "error": e.to_string()
-            }));
-        }
-    };
-
-    // Bind parameters
-    for (i, val) in req.bindings.iter().enumerate() {
-        let idx = i + 1;
-        let _ = match val {
-            serde_json::Value::Null => stmt.raw_bind_parameter(idx, rusqlite::types::Null),
-            serde_json::Value::Bool(b) => stmt.raw_bind_parameter(idx, *b),
-            serde_json::Value::Number(n) => {
-                if let Some(i) = n.as_i64() {
-                    stmt.raw_bind_parameter(idx, i)
-                } else if let Some(f) = n.as_f64() {
-                    stmt.raw_bind_parameter(idx, f)
-                } else {
-                    stmt.raw_bind_parameter(idx, n.to_string())
-                }
-            }
-            serde_json::Value::String(s) => stmt.raw_bind_parameter(idx, s.as_str()),
-            other => stmt.raw_bind_parameter(idx, other.to_string()),
-        };
-    }
-
-    // Get column names
-    let col_names: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
-    let mut rows = Vec::new();
-
-    let mut raw_rows = stmt.raw_query();
-    while let Some(row) = raw_rows.next().unwrap() {
-        let mut obj = serde_json::Map::new();
-        for (i, name) in col_names.iter().enumerate() {
-            let val: serde_json::Value = match row.get_ref(i) {
-                Ok(rusqlite::types::ValueRef::Null) => serde_json::Value::Null,
-                Ok(rusqlite::types::ValueRef::Integer(n)) => serde_json::json!(n),
-                Ok(rusqlite::types::ValueRef::Real(f)) => serde_json::json!(f),
-                Ok(rusqlite::types::ValueRef::Text(s)) => {
-                    serde_json::Value::String(String::from_utf8_lossy(s).into_owned())
-                }
-                Ok(rusqlite::types::ValueRef::Blob(b)) => {
-                    serde_json::Value::String(format!("<blob {} bytes>", b.len()))
-                }
-                Err(_) => serde_json::Value::Null,
-            };
-            obj.insert(name.clone(), val);
-        }
-        rows.push(serde_json::Value::Object(obj));
-    }
-
-    let duration = start.elapsed().as_secs_f64();
-
-    Json(serde_json::json!({
-        "success": true,
-        "results": rows,
-        "meta": {
-            "changes": conn.changes() as i64,
-            "last_row_id": conn.last_insert_rowid(),
-            "duration": duration
-        }
-    }))
-}
-
-/// Helper to create a temporary database path
-fn temp_db_path() -> (PathBuf, tempfile::TempDir) {
-    let temp_dir = tempfile::tempdir().unwrap();
-    (temp_dir.path().join("test.db"), temp_dir)
+/// Start the real emulator with an in-memory database on a free port.
+/// Returns its base URL, a client that sends the session token, and the
+/// handle that keeps it running.
+async fn start_test_server() -> (String, reqwest::Client, RunningEmulator) {
+    start_server(EmulatorServer::ephemeral()).await
 }
 
-/// Start test server and return base URL
-async fn start_test_server() -> (String, KvStore, tempfile::TempDir) {
-    let kv = KvStore::new();
-    let (db_path, temp_dir) = temp_db_path();
-
-    let conn = Connection::open(&db_path).unwrap();
-    conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")
+async fn start_server(server: EmulatorServer) -> (String, reqwest::Client, RunningEmulator) {
+    let emulator = server.bind().await.unwrap();
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::AUTHORIZATION,
+        format!("Bearer {}", emulator.token()).parse().unwrap(),
+    );
+    headers.insert(PROTOCOL_HEADER, PROTOCOL_VERSION.into());
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
         .unwrap();
-
-    let state = AppState {
-        kv: kv.clone(),
-        db: Arc::new(Mutex::new(conn)),
-    };
-
-    let app = Router::new()
-        .route("/__nrz/health", get(health))
-        .route("/__nrz/kv/get", post(kv_get))
-        .route("/__nrz/kv/set", post(kv_set))
-        .route("/__nrz/db/query", post(db_query))
-        .route("/__nrz/db/exec", post(db_exec))
-        .with_state(state);
-
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
-    let port = listener.local_addr().unwrap().port();
-    let base_url = format!("http://127.0.0.1:{}", port);
-
-    tokio::spawn(async move {
-        axum::serve(listener, app).await.unwrap();
-    });
-
-    // Wait for server to be ready
-    for _ in 0..50 {
-        match reqwest::get(format!("{}/__nrz/health", base_url)).await {
-            Ok(resp) if resp.status().is_success() => break,
-            _ => tokio::time::sleep(Duration::from_millis(50)).await,
-        }
-    }
-
-    (base_url, kv, temp_dir)
+    (emulator.url(), client, emulator)
 }
 
 #[tokio::test]
 async fn health_endpoint_returns_ok() {
-    let (base_url, _kv, _temp) = start_test_server().await;
+    let (base_url, client, _emulator) = start_test_server().await;
 
-    let resp = reqwest::get(format!("{}/__nrz/health", base_url))
+    let resp = client
+        .get(format!("{}/__nrz/health", base_url))
+        .send()
         .await
         .unwrap();
 
@@ -237,12 +46,52 @@ async fn health_endpoint_returns_ok() {
 
     let body: serde_json::Value = resp.json().await.unwrap();
     assert_eq!(body["status"], "ok");
+    assert_eq!(body["protocol"], PROTOCOL_VERSION);
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(
+        body["capabilities"]
+            .as_array()
+            .unwrap()
+            .contains(&"kv".into())
+    );
 }
 
 #[tokio::test]
-async fn kv_set_and_get() {
-    let (base_url, _kv, _temp) = start_test_server().await;
+async fn requests_from_other_protocol_versions_are_rejected() {
+    let (base_url, _, emulator) = start_test_server().await;
     let client = reqwest::Client::new();
+    let request = |version: Option<u32>| {
+        let mut request = client
+            .post(format!("{base_url}/__nrz/kv/get"))
+            .bearer_auth(emulator.token())
+            .json(&serde_json::json!({ "args": ["k"] }));
+        if let Some(version) = version {
+            request = request.header(PROTOCOL_HEADER, version);
+        }
+        request.send()
+    };
+
+    // A bootstrap from before protocol versioning
+    let resp = request(None).await.unwrap();
+    assert_eq!(resp.status(), 426);
+    let text = resp.text().await.unwrap();
+    assert!(text.contains("older nrz"), "{text}");
+
+    let resp = request(Some(PROTOCOL_VERSION + 1)).await.unwrap();
+    assert_eq!(resp.status(), 426);
+    let text = resp.text().await.unwrap();
+    assert!(
+        text.contains(&format!("protocol v{}", PROTOCOL_VERSION + 1)),
+        "{text}"
+    );
+
+    let resp = request(Some(PROTOCOL_VERSION)).await.unwrap();
+    assert!(resp.status().is_success());
+}
+
+#[tokio::test]
+async fn kv_set_and_get() {
+    let (base_url, client, _emulator) = start_test_server().await;
 
     // Set a key
     let set_resp = client
@@ -275,8 +124,7 @@ async fn kv_set_and_get() {
 
 #[tokio::test]
 async fn kv_get_nonexistent_key_returns_null() {
-    let (base_url, _kv, _temp) = start_test_server().await;
-    let client = reqwest::Client::new();
+    let (base_url, client, _emulator) = start_test_server().await;
 
     let resp = client
         .post(format!("{}/__nrz/kv/get", base_url))
@@ -294,8 +142,7 @@ async fn kv_get_nonexistent_key_returns_null() {
 
 #[tokio::test]
 async fn db_exec_and_query() {
-    let (base_url, _kv, _temp) = start_test_server().await;
-    let client = reqwest::Client::new();
+    let (base_url, client, _emulator) = start_test_server().await;
 
     // Create table using exec
     let exec_resp = client
@@ -358,8 +205,7 @@ async fn db_exec_and_query() {
 
 #[tokio::test]
 async fn db_query_with_bindings() {
-    let (base_url, _kv, _temp) = start_test_server().await;
-    let client = reqwest::Client::new();
+    let (base_url, client, _emulator) = start_test_server().await;
 
     // Create table
     client
@@ -401,3 +247,234 @@ async fn db_query_with_bindings() {
     assert_eq!(results.len(), 1);
     assert_eq!(results[0]["value"], 42);
 }
+
+#[tokio::test]
+async fn strict_d1_compat_rejects_queries_with_400() {
+    let mut server = EmulatorServer::ephemeral();
+    server.d1_compat = CompatMode::Strict;
+    let (base_url, client, _emulator) = start_server(server).await;
+
+    let resp = client
+        .post(format!("{base_url}/__nrz/db/query"))
+        .json(&serde_json::json!({ "sql": "PRAGMA journal_mode = DELETE", "mode": "run" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    let text = resp.text().await.unwrap();
+    assert!(text.contains("D1 compatibility error"), "{text}");
+    assert!(text.contains("PRAGMA journal_mode"), "{text}");
+
+    let resp = client
+        .post(format!("{base_url}/__nrz/db/exec"))
+        .json(&serde_json::json!({ "sql": "CREATE TABLE t (id INTEGER); BEGIN; COMMIT;" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    let text = resp.text().await.unwrap();
+    assert!(text.contains("D1 compatibility error"), "{text}");
+
+    // Nothing ran: the script was rejected before its first statement
+    let resp = client
+        .post(format!("{base_url}/__nrz/db/query"))
+        .json(&serde_json::json!({ "sql": "SELECT * FROM t", "mode": "all" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    assert!(resp.text().await.unwrap().contains("no such table"));
+}
+
+#[tokio::test]
+async fn warn_d1_compat_runs_the_query() {
+    let (base_url, client, _emulator) = start_test_server().await;
+
+    let resp = client
+        .post(format!("{base_url}/__nrz/db/query"))
+        .json(&serde_json::json!({ "sql": "PRAGMA journal_mode = DELETE", "mode": "all" }))
+        .send()
+        .await
+        .unwrap();
+    let status = resp.status();
+    assert_eq!(status, 200, "{}", resp.text().await.unwrap());
+}
+
+#[tokio::test]
+async fn shutdown_stops_the_server() {
+    let (base_url, client, emulator) = start_test_server().await;
+    let addr = emulator.addr();
+    assert_ne!(addr.port(), 0);
+
+    emulator.shutdown().await.unwrap();
+    assert!(
+        client
+            .get(format!("{}/__nrz/health", base_url))
+            .send()
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn shutdown_after_waiting_stops_the_server() {
+    let (base_url, client, mut emulator) = start_test_server().await;
+    // As `nrz emulator start` does, racing it against a signal
+    tokio::select! {
+        result = emulator.wait() => panic!("server stopped: {result:?}"),
+        () = tokio::time::sleep(std::time::Duration::from_millis(50)) => {}
+    }
+
+    emulator.shutdown().await.unwrap();
+    assert!(
+        client
+            .get(format!("{}/__nrz/health", base_url))
+            .send()
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn start_serves_until_aborted() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut server = EmulatorServer::ephemeral();
+    server.addr.set_port(port);
+    let task = tokio::spawn(async move { server.start().await });
+
+    let url = format!("http://127.0.0.1:{port}/__nrz/health");
+    let mut status = None;
+    for _ in 0..100 {
+        if let Ok(resp) = reqwest::get(&url).await {
+            status = Some(resp.status());
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    // Serving, and guarding requests with a generated token
+    assert_eq!(status, Some(reqwest::StatusCode::UNAUTHORIZED));
+    assert!(!task.is_finished());
+    task.abort();
+}
+
+#[tokio::test]
+async fn servers_do_not_share_in_memory_data() {
+    let (first_url, first, _first) = start_test_server().await;
+    let (second_url, second, _second) = start_test_server().await;
+
+    first
+        .post(format!("{}/__nrz/db/exec", first_url))
+        .json(&serde_json::json!({ "sql": "CREATE TABLE only_here (id INTEGER)" }))
+        .send()
+        .await
+        .unwrap();
+    let body: serde_json::Value = second
+        .post(format!("{}/__nrz/db/query", second_url))
+        .json(&serde_json::json!({ "sql": "SELECT * FROM only_here", "mode": "all" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap_or(serde_json::Value::Null);
+    assert_ne!(body["success"], true);
+}
+
+#[tokio::test]
+async fn bind_generates_a_token_unless_one_is_set() {
+    let server = EmulatorServer::ephemeral();
+    assert!(server.token.is_empty());
+    assert!(server.router(0).is_err());
+    let first = server.bind().await.unwrap();
+    let second = EmulatorServer::ephemeral().bind().await.unwrap();
+    assert_eq!(first.token().len(), 64);
+    assert_ne!(first.token(), second.token());
+
+    let mut server = EmulatorServer::ephemeral();
+    server.token = "chosen".into();
+    assert_eq!(server.bind().await.unwrap().token(), "chosen");
+}
+
+/// Send a raw HTTP/1.1 request over the emulator's Unix socket
+#[cfg(unix)]
+async fn socket_request(socket: &std::path::Path, request: String) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut stream = tokio::net::UnixStream::connect(socket).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn serves_the_api_on_a_unix_socket() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("emulator.sock");
+    let mut server = EmulatorServer::ephemeral();
+    server.socket = Some(path.clone());
+    let emulator = server.bind().await.unwrap();
+    assert_eq!(emulator.socket(), Some(path.as_path()));
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let port = emulator.addr().port();
+    let response = socket_request(
+        &path,
+        format!(
+            "GET /__nrz/health HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\n\
+             Authorization: Bearer {}\r\nConnection: close\r\n\r\n",
+            emulator.token()
+        ),
+    )
+    .await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    // Same access rules as over TCP
+    let response = socket_request(
+        &path,
+        format!(
+            "GET /__nrz/health HTTP/1.1\r\nHost: 127.0.0.1:{port}\r\nConnection: close\r\n\r\n"
+        ),
+    )
+    .await;
+    assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+
+    emulator.shutdown().await.unwrap();
+    assert!(!path.exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn socket_falls_back_to_tcp_when_taken() {
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("emulator.sock");
+    // Left behind by an emulator that did not shut down
+    std::fs::write(&path, "").unwrap();
+
+    let mut server = EmulatorServer::ephemeral();
+    server.socket = Some(path.clone());
+    let first = server.bind().await.unwrap();
+    assert_eq!(first.socket(), Some(path.as_path()));
+
+    // A second emulator does not take over a live socket
+    let mut server = EmulatorServer::ephemeral();
+    server.socket = Some(path.clone());
+    let second = server.bind().await.unwrap();
+    assert_eq!(second.socket(), None);
+    let client = nrz::EmulatorClient::connect(&second).unwrap();
+    assert_eq!(client.health().await.unwrap().status, "ok");
+    assert!(path.exists());
+
+    // Nor one whose path is too long for a socket
+    let mut server = EmulatorServer::ephemeral();
+    server.socket = Some(temp.path().join("x".repeat(120)).join("emulator.sock"));
+    assert_eq!(server.bind().await.unwrap().socket(), None);
+}
@@ -0,0 +1,257 @@
+//! Integration tests for the emulator dashboard and its API
+
+use std::time::Duration;
+
+use nrz::emulator::activity::ActivityLog;
+use nrz::emulator::kv::KvStore;
+use nrz::emulator::protocol::{PROTOCOL_HEADER, PROTOCOL_VERSION};
+use nrz::emulator::server::{DevInfo, EmulatorServer, RunningEmulator};
+
+/// Real emulator server with a client that sends its token
+struct TestServer {
+    base: String,
+    client: reqwest::Client,
+    token: String,
+    activity: ActivityLog,
+    dev_info: DevInfo,
+    _emulator: RunningEmulator,
+    _temp: tempfile::TempDir,
+}
+
+impl TestServer {
+    async fn get(&self, path: &str) -> reqwest::Response {
+        self.client
+            .get(format!("{}{path}", self.base))
+            .send()
+            .await
+            .unwrap()
+    }
+
+    /// POST JSON; error responses come back as a JSON string
+    async fn post(&self, path: &str, body: serde_json::Value) -> serde_json::Value {
+        let text = self
+            .client
+            .post(format!("{}{path}", self.base))
+            .json(&body)
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text))
+    }
+}
+
+async fn start_server() -> TestServer {
+    let temp = tempfile::tempdir().unwrap();
+    let mut server = EmulatorServer::new(KvStore::new(), temp.path().join("dev.db"), 0);
+    let activity = ActivityLog::new();
+    server.activity = activity.clone();
+    let dev_info = DevInfo::new(serde_json::json!({ "mode": "development", "env": [] }));
+    server.dev_info = dev_info.clone();
+    let emulator = server.bind().await.unwrap();
+    let token = emulator.token().to_string();
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::AUTHORIZATION,
+        format!("Bearer {token}").parse().unwrap(),
+    );
+    headers.insert(PROTOCOL_HEADER, PROTOCOL_VERSION.into());
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+    TestServer {
+        base: emulator.url(),
+        client,
+        token,
+        activity,
+        dev_info,
+        _emulator: emulator,
+        _temp: temp,
+    }
+}
+
+#[tokio::test]
+async fn ui_page_is_self_contained() {
+    let server = start_server().await;
+    let resp = server.get("/__nrz/ui").await;
+    assert!(resp.status().is_success());
+    assert!(
+        resp.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/html")
+    );
+    let html = resp.text().await.unwrap();
+    assert!(html.contains("nrz emulator"));
+    // Works offline: no external scripts, styles or fonts
+    assert!(!html.contains("http://") && !html.contains("https://"));
+    assert!(html.contains(&format!(r#"const PROTOCOL = "{PROTOCOL_VERSION}";"#)));
+}
+
+#[tokio::test]
+async fn kv_entries_include_ttl_and_metadata() {
+    let server = start_server().await;
+    server.post("/__nrz/kv/set",
+        serde_json::json!({ "args": ["flag", "on", { "ttl": 120, "metadata": { "owner": "ui" } }] }),
+    )
+    .await;
+    server
+        .post(
+            "/__nrz/kv/set",
+            serde_json::json!({ "args": ["plain", "x", 0] }),
+        )
+        .await;
+
+    let entries: serde_json::Value = server.get("/__nrz/ui/api/kv").await.json().await.unwrap();
+    assert_eq!(entries[0]["key"], "flag");
+    assert_eq!(entries[0]["metadata"]["owner"], "ui");
+    assert!(entries[0]["expiresIn"].as_u64().unwrap() > 100);
+    assert_eq!(entries[1]["expiresIn"], serde_json::Value::Null);
+
+    let with_metadata = server
+        .post(
+            "/__nrz/kv/getWithMetadata",
+            serde_json::json!({ "args": ["flag"] }),
+        )
+        .await;
+    assert_eq!(with_metadata["value"], "on");
+    assert_eq!(with_metadata["metadata"]["owner"], "ui");
+}
+
+#[tokio::test]
+async fn activity_logs_kv_and_queries() {
+    let server = start_server().await;
+    server
+        .post("/__nrz/kv/get", serde_json::json!({ "args": ["missing"] }))
+        .await;
+    server
+        .post(
+            "/__nrz/db/query",
+            serde_json::json!({ "sql": "SELECT nope", "mode": "all" }),
+        )
+        .await;
+
+    let log: serde_json::Value = server
+        .get("/__nrz/ui/api/activity")
+        .await
+        .json()
+        .await
+        .unwrap();
+    let log = log.as_array().unwrap();
+    assert_eq!(log[0]["kind"], "kv");
+    assert_eq!(log[0]["summary"], "kv.get missing");
+    assert_eq!(log[1]["kind"], "db");
+    assert_eq!(log[1]["summary"], "SELECT nope");
+    assert!(log[1]["error"].as_str().unwrap().contains("no such column"));
+
+    // Polling from the last id returns nothing new
+    let last = log[1]["id"].as_u64().unwrap();
+    let newer: serde_json::Value = server
+        .get(&format!("/__nrz/ui/api/activity?since={last}"))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(newer, serde_json::json!([]));
+
+    let status = server
+        .client
+        .delete(format!("{}/__nrz/ui/api/activity", server.base))
+        .send()
+        .await
+        .unwrap()
+        .status();
+    assert_eq!(status, 204);
+}
+
+#[tokio::test]
+async fn activity_includes_shared_log_entries() {
+    let server = start_server().await;
+    server.activity.record(
+        nrz::emulator::activity::ActivityKind::Request,
+        "GET / 200",
+        Duration::ZERO,
+        None,
+        Some(serde_json::json!({ "requestId": "dev-1" })),
+    );
+    let log: serde_json::Value = server
+        .get("/__nrz/ui/api/activity")
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(log[0]["detail"]["requestId"], "dev-1");
+}
+
+#[tokio::test]
+async fn info_returns_dev_details() {
+    let server = start_server().await;
+    let info: serde_json::Value = server.get("/__nrz/ui/api/info").await.json().await.unwrap();
+    assert_eq!(info["d1Compat"], "warn");
+    assert_eq!(info["dev"]["mode"], "development");
+}
+
+#[tokio::test]
+async fn info_follows_dev_info_updates() {
+    let server = start_server().await;
+    server
+        .dev_info
+        .set(serde_json::json!({ "mode": "development", "env": [{ "name": "API_KEY" }] }));
+    let info: serde_json::Value = server.get("/__nrz/ui/api/info").await.json().await.unwrap();
+    assert_eq!(info["dev"]["env"][0]["name"], "API_KEY");
+}
+
+#[tokio::test]
+async fn requests_need_session_token() {
+    let server = start_server().await;
+    let anonymous = reqwest::Client::new();
+
+    let resp = anonymous
+        .post(format!("{}/__nrz/db/exec", server.base))
+        .json(&serde_json::json!({ "sql": "DROP TABLE users" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+
+    // The dashboard page takes the token from its URL
+    let token = &server.token;
+    let resp = anonymous
+        .get(format!("{}/__nrz/ui?token={token}", server.base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = anonymous
+        .get(format!("{}/__nrz/ui/api/kv?token={token}", server.base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+}
+
+#[tokio::test]
+async fn rejects_rebound_hosts_and_other_origins() {
+    let server = start_server().await;
+    let resp = server
+        .client
+        .get(format!("{}/__nrz/health", server.base))
+        .header("host", "attacker.example")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let resp = server
+        .client
+        .post(format!("{}/__nrz/kv/get", server.base))
+        .header("origin", "http://localhost:4321")
+        .json(&serde_json::json!({ "args": ["k"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+}
@@ -0,0 +1,35 @@
+// Reports what the injected bootstrap set up, for tests/runtime_test.rs
+import http from "node:http";
+
+const runtime = typeof Bun !== "undefined" ? "bun" : typeof Deno !== "undefined" ? "deno" : "node";
+
+// Last scheduled event, with the handler that ran and what it saw
+let lastScheduled = null;
+export function record(handler, event, env, ctx) {
+  if (event.cron === "0 0 1 1 *") throw new Error("new year failed");
+  ctx.waitUntil(
+    new Promise((resolve) => setTimeout(resolve, 50)).then(() => {
+      lastScheduled = { ...event, handler, greeting: env.get("GREETING"), requestId: ONREZA.context.requestId };
+    }),
+  );
+}
+
+// The app's scheduled handler, and the fallback for when this module is
+// not the entry
+export const scheduled = (event, env, ctx) => record("export", event, env, ctx);
+globalThis.ONREZA?.onScheduled((event, env, ctx) => record("onScheduled", event, env, ctx));
+
+http
+  .createServer((_req, res) => {
+    res.setHeader("content-type", "application/json");
+    res.end(
+      JSON.stringify({
+        runtime,
+        injected: typeof globalThis.ONREZA !== "undefined",
+        requestId: globalThis.ONREZA?.context.requestId ?? null,
+        greeting: globalThis.ONREZA?.env.get("GREETING") ?? null,
+        scheduled: lastScheduled,
+      }),
+    );
+  })
+  .listen(Number(process.env.PORT), "127.0.0.1");
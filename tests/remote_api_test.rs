@@ -0,0 +1,435 @@
+//! Integration tests for `--remote` db/kv commands
+//!
+//! Runs the CLI against a local mock of the platform API (NRZ_API_URL).
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use predicates::prelude::*;
+use predicates::str::contains;
+use rusqlite::Connection;
+use serde::Deserialize;
+
+const TOKEN: &str = "test-token";
+const PROJECT: &str = "prj_test";
+
+#[derive(Clone)]
+struct MockState {
+    /// (environment, key) → value
+    kv: Arc<Mutex<BTreeMap<(String, String), String>>>,
+    db: Arc<Mutex<Connection>>,
+}
+
+#[derive(Deserialize)]
+struct QueryBody {
+    sql: String,
+    mode: String,
+}
+
+#[derive(Deserialize)]
+struct SetBody {
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    prefix: Option<String>,
+    limit: Option<usize>,
+}
+
+type MockResult = Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)>;
+
+fn authorize(
+    headers: &HeaderMap,
+    project: &str,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    let auth = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if auth != format!("Bearer {TOKEN}") {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({ "error": "invalid token" })),
+        ));
+    }
+    if project != PROJECT {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "project not found" })),
+        ));
+    }
+    Ok(())
+}
+
+async fn db_query(
+    State(state): State<MockState>,
+    Path((project, _env)): Path<(String, String)>,
+    headers: HeaderMap,
+    Json(body): Json<QueryBody>,
+) -> MockResult {
+    authorize(&headers, &project)?;
+    assert_eq!(body.mode, "raw");
+
+    let conn = state.db.lock().unwrap();
+    let mut stmt = conn.prepare(&body.sql).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+    })?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
+    let mut results = vec![serde_json::json!(columns)];
+    let mut rows = stmt.raw_query();
+    while let Some(row) = rows.next().unwrap() {
+        let values: Vec<serde_json::Value> = (0..columns.len())
+            .map(|i| match row.get_ref(i).unwrap() {
+                rusqlite::types::ValueRef::Integer(n) => serde_json::json!(n),
+                rusqlite::types::ValueRef::Text(t) => {
+                    serde_json::json!(String::from_utf8_lossy(t))
+                }
+                _ => serde_json::Value::Null,
+            })
+            .collect();
+        results.push(serde_json::json!(values));
+    }
+    drop(rows);
+    drop(stmt);
+
+    Ok(Json(serde_json::json!({
+        "results": results,
+        "success": true,
+        "meta": { "changes": conn.changes(), "last_row_id": 0, "duration": 0.0 }
+    })))
+}
+
+async fn kv_list(
+    State(state): State<MockState>,
+    Path((project, env)): Path<(String, String)>,
+    Query(q): Query<ListQuery>,
+    headers: HeaderMap,
+) -> MockResult {
+    authorize(&headers, &project)?;
+    let kv = state.kv.lock().unwrap();
+    let keys: Vec<&String> = kv
+        .keys()
+        .filter(|(e, k)| *e == env && q.prefix.as_ref().is_none_or(|p| k.starts_with(p)))
+        .map(|(_, k)| k)
+        .take(q.limit.unwrap_or(1000))
+        .collect();
+    Ok(Json(serde_json::json!({ "keys": keys })))
+}
+
+async fn kv_get(
+    State(state): State<MockState>,
+    Path((project, env, key)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> MockResult {
+    authorize(&headers, &project)?;
+    match state.kv.lock().unwrap().get(&(env, key)) {
+        Some(value) => Ok(Json(serde_json::json!({ "value": value }))),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "not found" })),
+        )),
+    }
+}
+
+async fn kv_put(
+    State(state): State<MockState>,
+    Path((project, env, key)): Path<(String, String, String)>,
+    headers: HeaderMap,
+    Json(body): Json<SetBody>,
+) -> MockResult {
+    authorize(&headers, &project)?;
+    state.kv.lock().unwrap().insert((env, key), body.value);
+    Ok(Json(serde_json::json!({ "ok": true })))
+}
+
+async fn kv_delete(
+    State(state): State<MockState>,
+    Path((project, env, key)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> MockResult {
+    authorize(&headers, &project)?;
+    match state.kv.lock().unwrap().remove(&(env, key)) {
+        Some(_) => Ok(Json(serde_json::json!({ "ok": true }))),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "not found" })),
+        )),
+    }
+}
+
+/// Start the mock API and return its base URL.
+async fn start_mock_api() -> String {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+         INSERT INTO users (name) VALUES ('Alice'), ('Bob');
+         -- Internal to the platform, unlike a table that only looks alike
+         CREATE TABLE _cf_KV (key TEXT PRIMARY KEY, value BLOB);
+         CREATE TABLE acf_items (id INTEGER PRIMARY KEY);",
+    )
+    .unwrap();
+    let state = MockState {
+        kv: Arc::new(Mutex::new(BTreeMap::new())),
+        db: Arc::new(Mutex::new(conn)),
+    };
+
+    let base = "/api/projects/{project}/environments/{env}";
+    let app = Router::new()
+        .route(&format!("{base}/db/query"), post(db_query))
+        .route(&format!("{base}/kv"), get(kv_list))
+        .route(
+            &format!("{base}/kv/{{key}}"),
+            get(kv_get).put(kv_put).delete(kv_delete),
+        )
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{addr}")
+}
+
+/// Run nrz with the mock API configured, off the async runtime.
+async fn nrz(api_url: &str, args: &[&str]) -> assert_cmd::assert::Assert {
+    let temp = tempfile::tempdir().unwrap();
+    nrz_in(temp.path(), api_url, args).await
+}
+
+/// Run nrz in `dir` with the mock API configured, off the async runtime.
+async fn nrz_in(dir: &std::path::Path, api_url: &str, args: &[&str]) -> assert_cmd::assert::Assert {
+    let dir = dir.to_path_buf();
+    let api_url = api_url.to_string();
+    let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    tokio::task::spawn_blocking(move || {
+        assert_cmd::cargo::cargo_bin_cmd!("nrz")
+            .current_dir(&dir)
+            .env("NRZ_API_URL", api_url)
+            .env("NRZ_TOKEN", TOKEN)
+            .env("NRZ_PROJECT_ID", PROJECT)
+            .env("HOME", &dir)
+            .args(&args)
+            .assert()
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_kv_roundtrip() {
+    let api = start_mock_api().await;
+
+    nrz(&api, &["kv", "set", "greeting", "hello", "--remote"])
+        .await
+        .success()
+        .stderr(contains("OK"));
+
+    nrz(&api, &["kv", "get", "greeting", "--remote"])
+        .await
+        .success()
+        .stdout(contains("hello"));
+
+    nrz(&api, &["kv", "list", "--remote", "--prefix", "gre"])
+        .await
+        .success()
+        .stdout(contains("greeting"));
+
+    nrz(&api, &["kv", "delete", "greeting", "--remote"])
+        .await
+        .success()
+        .stderr(contains("deleted"));
+
+    nrz(&api, &["kv", "get", "greeting", "--remote"])
+        .await
+        .success()
+        .stderr(contains("(not found)"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_kv_environments_are_separate() {
+    let api = start_mock_api().await;
+
+    nrz(
+        &api,
+        &["kv", "set", "k", "prod", "--remote", "--env", "production"],
+    )
+    .await
+    .success();
+
+    nrz(&api, &["kv", "get", "k", "--remote"])
+        .await
+        .success()
+        .stderr(contains("(not found)"));
+
+    nrz(&api, &["kv", "get", "k", "--remote", "--env", "production"])
+        .await
+        .success()
+        .stdout(contains("prod"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_kv_key_is_url_encoded() {
+    let api = start_mock_api().await;
+
+    nrz(&api, &["kv", "set", "user/1 name", "x", "--remote"])
+        .await
+        .success();
+    nrz(&api, &["kv", "get", "user/1 name", "--remote"])
+        .await
+        .success()
+        .stdout(contains("x"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_db_execute_and_info() {
+    let api = start_mock_api().await;
+
+    nrz(
+        &api,
+        &[
+            "db",
+            "execute",
+            "SELECT name FROM users ORDER BY id",
+            "--remote",
+        ],
+    )
+    .await
+    .success()
+    .stderr(contains("name"))
+    .stderr(contains("Alice"))
+    .stderr(contains("2 row(s)"));
+
+    nrz(
+        &api,
+        &[
+            "db",
+            "execute",
+            "UPDATE users SET name = 'Carol'",
+            "--remote",
+        ],
+    )
+    .await
+    .success()
+    .stderr(contains("2 row(s) affected"));
+
+    nrz(&api, &["db", "info", "--remote", "--env", "production"])
+        .await
+        .success()
+        .stderr(contains("prj_test (production)"))
+        .stderr(contains("users: 2 row(s)"))
+        .stderr(contains("acf_items: 0 row(s)"))
+        .stderr(contains("_cf_KV").not());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_diff_keeps_tables_that_look_internal() {
+    let api = start_mock_api().await;
+    let temp = tempfile::tempdir().unwrap();
+    let migrations = temp.path().join("migrations");
+    std::fs::create_dir(&migrations).unwrap();
+    std::fs::write(
+        migrations.join("0001_init.sql"),
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+         CREATE TABLE acf_items (id INTEGER PRIMARY KEY);",
+    )
+    .unwrap();
+
+    nrz_in(
+        temp.path(),
+        &api,
+        &["db", "diff", "--from", "migrations", "--to", "remote"],
+    )
+    .await
+    .success()
+    .stderr(contains("schemas are identical"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_sql_error_is_reported() {
+    let api = start_mock_api().await;
+
+    nrz(
+        &api,
+        &["db", "execute", "SELECT * FROM missing", "--remote"],
+    )
+    .await
+    .failure()
+    .stderr(contains("no such table"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_bad_token_fails() {
+    let api = start_mock_api().await;
+
+    nrz(&api, &["kv", "get", "k", "--remote", "--token", "wrong"])
+        .await
+        .failure()
+        .stderr(contains("authentication failed"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_requires_project() {
+    let api = start_mock_api().await;
+    let api_url = api.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let temp = tempfile::tempdir().unwrap();
+        assert_cmd::cargo::cargo_bin_cmd!("nrz")
+            .current_dir(&temp)
+            .env("NRZ_API_URL", api_url)
+            .env("NRZ_TOKEN", TOKEN)
+            .env_remove("NRZ_PROJECT_ID")
+            .args(["kv", "list", "--remote"])
+            .assert()
+            .failure()
+            .stderr(contains("remote access requires a project"));
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_project_from_config() {
+    let api = start_mock_api().await;
+    let api_url = api.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("onreza.jsonc"),
+            format!(
+                r#"{{
+                  "projectId": "prj_other",
+                  "environments": {{ "production": {{ "projectId": "{PROJECT}" }} }}
+                }}"#
+            ),
+        )
+        .unwrap();
+        let nrz = |args: &[&str]| {
+            assert_cmd::cargo::cargo_bin_cmd!("nrz")
+                .current_dir(&temp)
+                .env("NRZ_API_URL", &api_url)
+                .env("NRZ_TOKEN", TOKEN)
+                .env_remove("NRZ_PROJECT_ID")
+                .args(args)
+                .assert()
+        };
+
+        nrz(&["kv", "list", "--remote", "--env", "production"]).success();
+        // Preview falls back to the top-level projectId
+        nrz(&["kv", "list", "--remote"])
+            .failure()
+            .stderr(contains("project not found"));
+    })
+    .await
+    .unwrap();
+}
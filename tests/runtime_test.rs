@@ -0,0 +1,412 @@
+//! Integration tests for preloading the bootstrap into each JS runtime.
+//!
+//! Runs `nrz dev` on the fixture app in tests/fixtures/runtime-app and asks
+//! it what the bootstrap set up. Node is required. The Bun and Deno tests
+//! are ignored by default: run them with `cargo test -- --ignored` where
+//! those runtimes are installed, as CI does.
+
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// Copy the fixture app into a temp dir, with a `.env` for `ONREZA.env`
+fn fixture() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/runtime-app");
+    for file in ["package.json", "server.mjs"] {
+        std::fs::copy(src.join(file), temp.path().join(file)).unwrap();
+    }
+    std::fs::write(temp.path().join(".env"), "GREETING=hello\n").unwrap();
+    temp
+}
+
+/// Stops `nrz dev` like Ctrl+C when dropped
+struct DevSession(Child);
+
+impl Drop for DevSession {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(self.0.id() as libc::pid_t, libc::SIGINT);
+        }
+        #[cfg(not(unix))]
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Start `nrz dev --command <command>` on the fixture in `dir` and wait
+/// until it serves, returning the session and port
+async fn start_fixture(dir: &Path, command: &str) -> (DevSession, u16) {
+    let port = free_port();
+    let session = DevSession(
+        Command::new(env!("CARGO_BIN_EXE_nrz"))
+            .current_dir(dir)
+            .args(["dev", "--command", command, "--port", &port.to_string()])
+            .args(["--package-manager", "npm"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+
+    for _ in 0..200 {
+        if let Ok(res) = reqwest::get(format!("http://127.0.0.1:{port}/")).await
+            && res.status().is_success()
+        {
+            return (session, port);
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("`nrz dev --command '{command}'` did not serve the fixture on port {port}");
+}
+
+/// What the fixture served on `port` reports
+async fn report(port: u16) -> serde_json::Value {
+    reqwest::get(format!("http://127.0.0.1:{port}/"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+/// Run the fixture with `command` and return what it reports
+async fn run_fixture(command: &str) -> serde_json::Value {
+    let temp = fixture();
+    let (session, port) = start_fixture(temp.path(), command).await;
+    let report = report(port).await;
+    drop(session);
+    report
+}
+
+fn assert_injected(report: &serde_json::Value, runtime: &str) {
+    assert_eq!(report["runtime"], runtime, "{report}");
+    assert_eq!(report["injected"], true, "{report}");
+    assert!(
+        report["requestId"]
+            .as_str()
+            .is_some_and(|id| id.starts_with("dev-")),
+        "{report}"
+    );
+    assert_eq!(report["greeting"], "hello", "{report}");
+}
+
+#[tokio::test]
+async fn node_preloads_bootstrap() {
+    let report = run_fixture("node server.mjs").await;
+    assert_injected(&report, "node");
+}
+
+#[tokio::test]
+#[ignore = "needs bun"]
+async fn bun_preloads_bootstrap() {
+    let report = run_fixture("bun server.mjs").await;
+    assert_injected(&report, "bun");
+}
+
+#[tokio::test]
+#[ignore = "needs deno"]
+async fn deno_preloads_bootstrap() {
+    let report = run_fixture("deno run -A server.mjs").await;
+    assert_injected(&report, "deno");
+}
+
+/// Run `code` as a module in node with `bootstrap` preloaded
+fn node_with_bootstrap(bootstrap: &Path, code: &str) -> std::process::Output {
+    Command::new("node")
+        .arg("--import")
+        .arg(format!("file://{}", bootstrap.display()))
+        .args(["--input-type=module", "-e", code])
+        .output()
+        .unwrap()
+}
+
+/// Start `nrz emulator start --ephemeral` in `dir` and return it with the
+/// JSON line it prints
+#[cfg(unix)]
+fn start_emulator(dir: &Path) -> (DevSession, serde_json::Value) {
+    use std::io::BufRead;
+
+    std::fs::write(dir.join("package.json"), r#"{"name":"test"}"#).unwrap();
+    let mut emulator = DevSession(
+        Command::new(env!("CARGO_BIN_EXE_nrz"))
+            .current_dir(dir)
+            .args(["emulator", "start", "--ephemeral"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+    let mut line = String::new();
+    std::io::BufReader::new(emulator.0.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    (emulator, serde_json::from_str(&line).unwrap())
+}
+
+#[cfg(unix)]
+#[test]
+fn bootstrap_checks_the_emulator_at_startup() {
+    let temp = tempfile::tempdir().unwrap();
+    let (_emulator, info) = start_emulator(temp.path());
+    let script = std::fs::read_to_string(info["bootstrap"].as_str().unwrap()).unwrap();
+    let token = info["token"].as_str().unwrap();
+
+    let current = temp.path().join("current.mjs");
+    std::fs::write(&current, &script).unwrap();
+    let output = node_with_bootstrap(
+        &current,
+        r#"await ONREZA.kv.set("k", "v"); console.log(await ONREZA.kv.get("k"));"#,
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().last(), Some("v"), "{stdout}");
+
+    // Bootstrap left over from another session
+    let stale = temp.path().join("stale.mjs");
+    std::fs::write(&stale, script.replace(token, &"0".repeat(64))).unwrap();
+    let output = node_with_bootstrap(&stale, "");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("belongs to another nrz session"),
+        "{stderr}"
+    );
+
+    // Bootstrap from an nrz speaking another protocol version
+    let newer = temp.path().join("newer.mjs");
+    let protocol = nrz::emulator::protocol::PROTOCOL_VERSION;
+    std::fs::write(
+        &newer,
+        script.replace(
+            &format!("const NRZ_PROTOCOL = {protocol};"),
+            &format!("const NRZ_PROTOCOL = {};", protocol + 1),
+        ),
+    )
+    .unwrap();
+    let output = node_with_bootstrap(&newer, "");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("protocol v{}", protocol + 1)),
+        "{stderr}"
+    );
+    assert!(stderr.contains("Restart the dev command"), "{stderr}");
+}
+
+#[cfg(unix)]
+#[test]
+fn bootstrap_batches_calls_made_in_the_same_tick() {
+    let temp = tempfile::tempdir().unwrap();
+    let (_emulator, info) = start_emulator(temp.path());
+    let bootstrap = Path::new(info["bootstrap"].as_str().unwrap());
+
+    let output = node_with_bootstrap(
+        bootstrap,
+        r#"
+        import http from "node:http";
+        const requests = { tcp: 0, socket: 0 };
+        const fetch = globalThis.fetch;
+        globalThis.fetch = (...args) => (requests.tcp++, fetch(...args));
+        const request = http.request;
+        http.request = (...args) => (requests.socket++, request(...args));
+        const results = await Promise.allSettled([
+          ONREZA.kv.set("a", "1"),
+          ONREZA.kv.get("a"),
+          ONREZA.db.prepare("SELECT v FROM missing").all(),
+          ONREZA.db.prepare("SELECT ? AS v").bind(2).first("v"),
+        ]);
+        console.log(JSON.stringify({
+          requests,
+          results: results.map((r) => r.status === "fulfilled" ? r.value : r.reason.message),
+        }));
+        "#,
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let report: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap()).unwrap();
+    // One request, through the emulator's Unix socket
+    assert_eq!(
+        report["requests"],
+        serde_json::json!({ "tcp": 0, "socket": 1 }),
+        "{report}"
+    );
+    assert_eq!(report["results"][0], "OK");
+    assert_eq!(report["results"][1], "1");
+    let error = report["results"][2].as_str().unwrap();
+    assert!(
+        error.starts_with("[nrz] db.prepare().all returned 400:")
+            && error.contains("no such table"),
+        "{error}"
+    );
+    assert_eq!(report["results"][3], 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn bootstrap_falls_back_to_tcp_without_the_socket() {
+    let temp = tempfile::tempdir().unwrap();
+    let (_emulator, info) = start_emulator(temp.path());
+    std::fs::remove_file(info["socket"].as_str().unwrap()).unwrap();
+
+    let output = node_with_bootstrap(
+        Path::new(info["bootstrap"].as_str().unwrap()),
+        r#"await ONREZA.kv.set("k", "tcp"); console.log(await ONREZA.kv.get("k"));"#,
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().last(), Some("tcp"), "{stdout}");
+}
+
+#[cfg(unix)]
+#[test]
+fn bootstrap_statements_return_what_d1_returns() {
+    let temp = tempfile::tempdir().unwrap();
+    let (_emulator, info) = start_emulator(temp.path());
+
+    let output = node_with_bootstrap(
+        Path::new(info["bootstrap"].as_str().unwrap()),
+        r#"
+        const db = ONREZA.db;
+        await db.exec("CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT)");
+        const batch = await db.batch([
+          db.prepare("INSERT INTO t (v) VALUES (?)").bind("a"),
+          db.prepare("INSERT INTO t (v) VALUES (?)").bind("b"),
+          db.prepare("SELECT v FROM t ORDER BY id"),
+        ]);
+        console.log(JSON.stringify({
+          batch: batch.map((r) => r.results),
+          first: await db.prepare("SELECT id, v FROM t WHERE v = ?").bind("b").first(),
+          firstColumn: await db.prepare("SELECT v FROM t ORDER BY id").first("v"),
+          firstNone: await db.prepare("SELECT v FROM t WHERE id = 99").first(),
+          raw: await db.prepare("SELECT id, v FROM t ORDER BY id").raw(),
+          rawColumns: await db.prepare("SELECT id, v FROM t ORDER BY id").raw({ columnNames: true }),
+        }));
+        "#,
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let report: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap()).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!({
+            "batch": [[], [], [{ "v": "a" }, { "v": "b" }]],
+            "first": { "id": 2, "v": "b" },
+            "firstColumn": "a",
+            "firstNone": null,
+            "raw": [[1, "a"], [2, "b"]],
+            "rawColumns": [["id", "v"], [1, "a"], [2, "b"]],
+        })
+    );
+}
+
+/// Run `nrz trigger cron <args>` in `dir`
+fn trigger_cron(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_nrz"))
+        .current_dir(dir)
+        .args(["trigger", "cron"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn trigger_cron_runs_the_scheduled_handler() {
+    let temp = fixture();
+    let (_session, port) = start_fixture(temp.path(), "node server.mjs").await;
+    let trigger = |args: &[&str]| trigger_cron(temp.path(), args);
+
+    let output = trigger(&["--at", "2026-03-01T09:30:00+02:00", "30 7 * * *"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(
+        stderr.contains("ran for 30 7 * * * at 2026-03-01T07:30:00Z"),
+        "{stderr}"
+    );
+    // Answered after waitUntil, through the proxy with its own context
+    let scheduled = &report(port).await["scheduled"];
+    assert_eq!(scheduled["handler"], "export", "{scheduled}");
+    assert_eq!(scheduled["type"], "scheduled", "{scheduled}");
+    assert_eq!(scheduled["cron"], "30 7 * * *");
+    assert_eq!(scheduled["scheduledTime"], 1_772_350_200_000_i64);
+    assert_eq!(scheduled["greeting"], "hello");
+    assert!(
+        scheduled["requestId"]
+            .as_str()
+            .is_some_and(|id| id.starts_with("dev-")),
+        "{scheduled}"
+    );
+
+    let output = trigger(&["0 0 1 1 *"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("scheduled handler threw: Error: new year failed"),
+        "{stderr}"
+    );
+
+    // Only with this session's token
+    let resp = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{port}/__nrz/scheduled"))
+        .body(r#"{"type":"scheduled","cron":"* * * * *","scheduledTime":0}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+}
+
+#[tokio::test]
+async fn scheduled_handler_comes_from_the_configured_entry_never_the_build() {
+    let temp = fixture();
+    // The fixture's exports are not those of the main module here
+    std::fs::write(temp.path().join("main.mjs"), r#"import "./server.mjs";"#).unwrap();
+    let handler = async || {
+        let (_session, port) = start_fixture(temp.path(), "node main.mjs").await;
+        let output = trigger_cron(temp.path(), &["* * * * *"]);
+        assert!(output.status.success(), "{output:?}");
+        report(port).await["scheduled"]["handler"].clone()
+    };
+
+    assert_eq!(handler().await, "onScheduled");
+
+    // Build output may be stale and start a server of its own
+    let dist = temp.path().join("dist");
+    std::fs::create_dir_all(dist.join(".onreza")).unwrap();
+    std::fs::write(
+        dist.join(".onreza/manifest.json"),
+        r#"{ "server": { "entry": "entry.mjs", "export": "fetch" } }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dist.join("entry.mjs"),
+        r#"throw new Error("the build entry was imported");"#,
+    )
+    .unwrap();
+    assert_eq!(handler().await, "onScheduled");
+
+    std::fs::write(
+        temp.path().join("cron.mjs"),
+        r#"import { record } from "./server.mjs";
+        export default {
+          scheduled(event, env, ctx) {
+            return record("configured entry", event, env, ctx);
+          },
+        };"#,
+    )
+    .unwrap();
+    std::fs::write(
+        temp.path().join("onreza.jsonc"),
+        r#"{ "dev": { "scheduledEntry": "cron.mjs" } }"#,
+    )
+    .unwrap();
+    assert_eq!(handler().await, "configured entry");
+}